@@ -0,0 +1,319 @@
+//!
+//! 阻塞的HTTP/1.1客户端
+//!
+//! 每个请求使用一个新的连接，并发送`Connection: close`，只支持`http://`
+//!
+
+use std::io;
+use std::io::{ErrorKind, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+use crate::error::Result;
+use crate::header::method::HTTPClientMethod;
+use crate::header::name::HeaderName;
+use crate::map::HTTPHeadMap;
+use crate::parser::ParserLimits;
+use crate::parser::server::HTTPServerParser;
+use crate::response::{HTTPBytes, HTTPResponseBuilder};
+use crate::response::client::{HTTPClientResponse, HTTPClientResponseBuilder};
+use crate::response::server::HTTPServerResponse;
+use crate::uri::Uri;
+
+const READ_SIZE: usize = 8 * 1024;
+
+///
+/// 阻塞的HTTP客户端
+///
+/// ```no_run
+/// use http_rs_plus::client::Client;
+///
+/// let response = Client::new().get("http://example.com/").unwrap();
+/// println!("{:?} {}", response.status(), String::from_utf8_lossy(response.body()));
+/// ```
+///
+#[derive(Clone, Debug)]
+pub struct Client {
+    header: HTTPHeadMap,
+    timeout: Option<Duration>,
+    limits: ParserLimits
+}
+
+impl Default for Client {
+    fn default() -> Self {
+        let mut header = HTTPHeadMap::new();
+        header.insert(HeaderName::USER_AGENT, format!("http-rs_plus/{}", env!("CARGO_PKG_VERSION")));
+        Client {
+            header,
+            timeout: None,
+            limits: ParserLimits::default()
+        }
+    }
+}
+
+impl Client {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    ///
+    /// 每个请求都带上的header，请求中已有的同名header优先
+    ///
+    pub fn header(self, header: HTTPHeadMap) -> Self {
+        let mut this = self;
+        this.header = header;
+        this
+    }
+    
+    ///
+    /// 连接、读取和写入各自的超时时间，默认没有超时
+    ///
+    pub fn timeout(self, timeout: Option<Duration>) -> Self {
+        let mut this = self;
+        this.timeout = timeout;
+        this
+    }
+    
+    ///
+    /// 解析响应时的大小限制
+    ///
+    pub fn limits(self, limits: ParserLimits) -> Self {
+        let mut this = self;
+        this.limits = limits;
+        this
+    }
+    
+    pub fn get<U>(&self, url: U) -> Result<HTTPServerResponse>
+        where
+            U: AsRef<str>
+    {
+        self.request(url, Self::build(HTTPClientMethod::GET, Vec::new()))
+    }
+    
+    pub fn post<U, T>(&self, url: U, body: T) -> Result<HTTPServerResponse>
+        where
+            U: AsRef<str>,
+            T: HTTPBytes
+    {
+        self.request(url, Self::build(HTTPClientMethod::POST, body))
+    }
+    
+    pub fn put<U, T>(&self, url: U, body: T) -> Result<HTTPServerResponse>
+        where
+            U: AsRef<str>,
+            T: HTTPBytes
+    {
+        self.request(url, Self::build(HTTPClientMethod::PUT, body))
+    }
+    
+    pub fn delete<U>(&self, url: U) -> Result<HTTPServerResponse>
+        where
+            U: AsRef<str>
+    {
+        self.request(url, Self::build(HTTPClientMethod::DELETE, Vec::new()))
+    }
+    
+    fn build<T>(method: HTTPClientMethod, body: T) -> HTTPClientResponse
+        where
+            T: HTTPBytes
+    {
+        HTTPClientResponseBuilder::new()
+            .method(method)
+            .response(HTTPResponseBuilder::builder().body(body).build())
+            .build()
+    }
+    
+    ///
+    /// 把`request`发给`url`，请求目标和Host由`url`决定，`request`中的resource被忽略
+    ///
+    pub fn request<U>(&self, url: U, request: HTTPClientResponse) -> Result<HTTPServerResponse>
+        where
+            U: AsRef<str>
+    {
+        let uri = Uri::parse(url.as_ref())?;
+        let authority = match (uri.scheme(), uri.authority()) {
+            (Some(scheme), Some(authority)) if scheme.eq_ignore_ascii_case("http") && !authority.host().is_empty() => authority,
+            _ => return Err(io::Error::new(ErrorKind::InvalidInput, "only http:// URLs with a host are supported").into())
+        };
+        let port = uri.port_or_default().unwrap_or(80);
+        
+        //Host不包括userinfo，默认端口省略
+        let host = if authority.is_ipv6() { format!("[{}]", authority.host()) } else { authority.host().to_string() };
+        let host = match authority.port() {
+            Some(port) if port != 80 => format!("{}:{}", host, port),
+            _ => host
+        };
+        let (response, method, _) = request.into_parts();
+        let mut request = HTTPClientResponse::new(response, method.clone(), uri.path_and_query());
+        let header = request.header_mut();
+        header.insert(HeaderName::HOST, host);
+        header.insert(HeaderName::CONNECTION, String::from("close"));
+        for (key, value) in &self.header {
+            if !header.contains_key(key) {
+                header.insert(key.clone(), value.clone());
+            }
+        }
+        
+        let mut stream = self.connect(authority.host(), port)?;
+        request.write_to(&mut stream)?;
+        stream.flush()?;
+        self.read_response(&mut stream, method)
+    }
+    
+    fn connect(&self, host: &str, port: u16) -> io::Result<TcpStream> {
+        let mut last = None;
+        for addr in (host, port).to_socket_addrs()? {
+            let stream = match self.timeout {
+                Some(timeout) => TcpStream::connect_timeout(&addr, timeout),
+                None => TcpStream::connect(addr)
+            };
+            match stream {
+                Ok(stream) => {
+                    stream.set_read_timeout(self.timeout)?;
+                    stream.set_write_timeout(self.timeout)?;
+                    return Ok(stream)
+                }
+                Err(error) => last = Some(error)
+            }
+        }
+        Err(last.unwrap_or_else(|| io::Error::new(ErrorKind::NotFound, "host resolved to no addresses")))
+    }
+    
+    ///
+    /// 读取最终的响应，跳过100 Continue之类的临时响应
+    ///
+    fn read_response<R>(&self, r: &mut R, method: HTTPClientMethod) -> Result<HTTPServerResponse>
+        where
+            R: Read
+    {
+        let mut parser = HTTPServerParser::new()
+            .limits(self.limits)
+            .request_method(method);
+        let mut buf = vec![0; READ_SIZE];
+        let mut pending = Vec::new();
+        
+        loop {
+            let mut consumed = 0;
+            while consumed < pending.len() && !parser.is_complete() {
+                let progress = parser.try_feed(&pending[consumed..])?;
+                if progress.consumed() == 0 {
+                    break
+                }
+                consumed += progress.consumed();
+            }
+            pending.drain(..consumed);
+            
+            if let Some(response) = parser.take() {
+                let code = response.method().code();
+                //101之后的字节属于新的协议，直接返回
+                if (100..200).contains(&code) && code != 101 {
+                    continue
+                }
+                return Ok(response)
+            }
+            
+            let read = match r.read(&mut buf) {
+                Ok(read) => read,
+                Err(error) if error.kind() == ErrorKind::Interrupted => continue,
+                Err(error) => return Err(error.into())
+            };
+            if read == 0 {
+                parser.try_finish()?;
+                continue
+            }
+            pending.extend_from_slice(&buf[..read]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::thread;
+    use std::time::Duration;
+    
+    use crate::client::Client;
+    use crate::error::Error;
+    
+    ///
+    /// 在本地启动只处理一个连接的服务器，返回地址和收到的请求
+    ///
+    fn serve(response: &'static [u8]) -> (String, thread::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let handle = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut request = String::new();
+            let mut length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if let Some(value) = line.strip_prefix("Content-Length: ") {
+                    length = value.trim().parse().unwrap();
+                }
+                request.push_str(&line);
+                if line == "\r\n" {
+                    break
+                }
+            }
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).unwrap();
+            request.push_str(&String::from_utf8(body).unwrap());
+            
+            let mut stream = reader.into_inner();
+            //分两次写，检查跨越读取边界的响应
+            let (head, tail) = response.split_at(response.len() / 2);
+            stream.write_all(head).unwrap();
+            stream.flush().unwrap();
+            thread::sleep(Duration::from_millis(10));
+            stream.write_all(tail).unwrap();
+            request
+        });
+        (format!("http://{}", addr), handle)
+    }
+    
+    #[test]
+    fn get() {
+        let (url, handle) = serve(b"HTTP/1.1 100 Continue\r\n\r\nHTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nhello\r\n0\r\n\r\n");
+        let response = Client::new()
+            .timeout(Some(Duration::from_secs(5)))
+            .get(format!("{}/a/b?x=1#fragment", url))
+            .unwrap();
+        assert_eq!(response.method().code(), 200);
+        assert_eq!(response.body(), b"hello");
+        
+        let request = handle.join().unwrap();
+        assert!(request.starts_with("GET /a/b?x=1 HTTP/1.1\r\n"));
+        assert!(request.contains(&format!("Host: {}\r\n", url.trim_start_matches("http://"))));
+        assert!(request.contains("Connection: close\r\n"));
+        assert!(request.contains("User-Agent: http-rs_plus/"));
+    }
+    
+    #[test]
+    fn post() {
+        //没有Content-Length的body读取到连接关闭
+        let (url, handle) = serve(b"HTTP/1.1 201 Created\r\nX-Id: 7\r\n\r\ncreated");
+        let response = Client::new().post(&url, "name=value").unwrap();
+        assert_eq!(response.method().code(), 201);
+        assert_eq!(response.header().get("X-Id").unwrap(), "7");
+        assert_eq!(response.body(), b"created");
+        
+        let request = handle.join().unwrap();
+        assert!(request.starts_with("POST / HTTP/1.1\r\n"));
+        assert!(request.ends_with("\r\n\r\nname=value"));
+    }
+    
+    #[test]
+    fn error() {
+        assert!(matches!(Client::new().get("https://example.com/"), Err(Error::Io(_))));
+        assert!(matches!(Client::new().get("/relative"), Err(Error::Io(_))));
+        assert!(matches!(Client::new().get("http://exa mple.com/"), Err(Error::Parse(_))));
+        
+        //响应在header结束之前被关闭
+        let (url, handle) = serve(b"HTTP/1.1 200 OK\r\nContent-Len");
+        assert!(matches!(Client::new().delete(&url), Err(Error::Parse(_))));
+        assert!(handle.join().unwrap().starts_with("DELETE / HTTP/1.1\r\n"));
+    }
+}
\ No newline at end of file
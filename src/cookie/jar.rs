@@ -0,0 +1,457 @@
+use std::net::IpAddr;
+use std::slice;
+use std::str::FromStr;
+
+use crate::cookie::Cookie;
+use crate::header::date::HttpDate;
+use crate::header::name::HeaderName;
+use crate::map::HTTPHeadMap;
+use crate::uri::Uri;
+
+///
+/// 保存在[`CookieJar`]中的cookie，Domain和Path已经确定，Max-Age已经换算为Expires
+///
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StoredCookie {
+    cookie: Cookie,
+    host_only: bool
+}
+
+impl StoredCookie {
+    ///
+    /// 没有检查cookie，`host_only`为false时也发送给子域名
+    ///
+    pub fn new<D, P>(cookie: Cookie, domain: D, path: P, host_only: bool) -> Self
+        where
+            D: ToString,
+            P: ToString
+    {
+        let mut cookie = cookie;
+        cookie.domain = Some(domain.to_string().to_ascii_lowercase());
+        cookie.path = Some(path.to_string());
+        cookie.max_age = None;
+        StoredCookie {
+            cookie,
+            host_only
+        }
+    }
+    
+    pub fn cookie(&self) -> &Cookie {
+        &self.cookie
+    }
+    
+    pub fn domain(&self) -> &str {
+        self.cookie.domain().unwrap_or_default()
+    }
+    
+    pub fn path(&self) -> &str {
+        self.cookie.path().unwrap_or("/")
+    }
+    
+    ///
+    /// 没有Domain属性的cookie只发送给设置它的主机
+    ///
+    pub fn is_host_only(&self) -> bool {
+        self.host_only
+    }
+    
+    ///
+    /// 会话cookie不会过期
+    ///
+    pub fn is_expired(&self, now: HttpDate) -> bool {
+        match self.cookie.expires() {
+            Some(expires) => expires <= now,
+            None => false
+        }
+    }
+    
+    fn matches(&self, host: &str, path: &str, secure: bool) -> bool {
+        let domain = if self.host_only {
+            host == self.domain()
+        } else {
+            domain_match(host, self.domain())
+        };
+        domain && path_match(path, self.path()) && (secure || !self.cookie.is_secure())
+    }
+    
+    fn same_key(&self, other: &StoredCookie) -> bool {
+        self.cookie.name() == other.cookie.name() && self.domain() == other.domain() && self.path() == other.path()
+    }
+}
+
+///
+/// cookie最长的有效期，400天(RFC 6265bis 5.5)
+///
+const MAX_COOKIE_AGE: u64 = 400 * 86400;
+
+///
+/// 常见的公共后缀，没有'.'的域名(例如`com`)总是公共后缀，其他的后缀可以用[`CookieJar::public_suffix`]添加
+///
+const PUBLIC_SUFFIXES: &[&str] = &[
+    "ac.uk", "co.uk", "gov.uk", "ltd.uk", "me.uk", "net.uk", "org.uk", "plc.uk",
+    "com.au", "edu.au", "gov.au", "net.au", "org.au",
+    "ac.jp", "co.jp", "go.jp", "ne.jp", "or.jp",
+    "co.nz", "net.nz", "org.nz",
+    "com.br", "com.cn", "net.cn", "org.cn", "gov.cn", "com.hk", "com.tw", "co.kr",
+    "co.in", "co.il", "co.za", "com.mx", "com.sg", "com.tr",
+    "github.io", "gitlab.io", "netlify.app", "vercel.app", "pages.dev", "workers.dev", "web.app",
+    "appspot.com", "blogspot.com", "herokuapp.com", "firebaseapp.com", "cloudfront.net", "azurewebsites.net"
+];
+
+///
+/// 客户端的cookie存储，按照RFC 6265 5.3保存收到的cookie，发送请求时按照域名、路径、Secure以及过期时间选择
+///
+/// Domain属性是公共后缀时只能等于请求的主机，并且当作没有Domain属性处理(RFC 6265 5.3 第5步)
+///
+#[derive(Clone, Debug, Default)]
+pub struct CookieJar {
+    cookies: Vec<StoredCookie>,
+    public_suffixes: Vec<String>
+}
+
+impl CookieJar {
+    pub fn new() -> Self {
+        CookieJar::default()
+    }
+    
+    ///
+    /// 添加内置列表之外的公共后缀，例如`example-hosting.net`
+    ///
+    pub fn public_suffix<T>(self, suffix: T) -> Self
+        where
+            T: ToString
+    {
+        let mut this = self;
+        this.public_suffixes.push(suffix.to_string().trim_start_matches('.').to_ascii_lowercase());
+        this
+    }
+    
+    ///
+    /// `domain`是否是公共后缀，`domain`已经是小写
+    ///
+    pub fn is_public_suffix(&self, domain: &str) -> bool {
+        !domain.contains('.')
+            || PUBLIC_SUFFIXES.contains(&domain)
+            || self.public_suffixes.iter().any(|suffix| suffix == domain)
+    }
+    
+    ///
+    /// 保存从`uri`收到的cookie，被拒绝或者已经过期时返回false，已经过期的cookie会删除同名的旧cookie
+    ///
+    pub fn store(&mut self, uri: &Uri, cookie: Cookie) -> bool {
+        self.store_at(uri, cookie, HttpDate::now())
+    }
+    
+    pub(crate) fn store_at(&mut self, uri: &Uri, cookie: Cookie, now: HttpDate) -> bool {
+        let host = match uri.host() {
+            Some(host) => host.to_ascii_lowercase(),
+            None => return false
+        };
+        let secure = is_secure(uri);
+        //非安全连接不能设置Secure cookie(RFC 6265bis 5.6)
+        if cookie.is_secure() && !secure {
+            return false
+        }
+        
+        let (domain, host_only) = match cookie.domain() {
+            //公共后缀只能设置给它自己，不能发送给其他的子域名
+            Some(domain) if self.is_public_suffix(domain) => {
+                if domain != host {
+                    return false
+                }
+                (host.clone(), true)
+            }
+            Some(domain) if domain == host => (host.clone(), false),
+            Some(domain) if domain_match(&host, domain) => (domain.to_string(), false),
+            Some(_) => return false,
+            None => (host.clone(), true)
+        };
+        let path = match cookie.path() {
+            Some(path) => path.to_string(),
+            None => default_path(uri.path())
+        };
+        
+        //__Secure-和__Host-前缀
+        if cookie.name().starts_with("__Secure-") && !cookie.is_secure() {
+            return false
+        }
+        if cookie.name().starts_with("__Host-") && (!cookie.is_secure() || !host_only || path != "/") {
+            return false
+        }
+        
+        //Max-Age和Expires都最多到400天之后
+        let limit = now.unix().saturating_add(MAX_COOKIE_AGE);
+        let mut cookie = cookie;
+        if let Some(max_age) = cookie.max_age() {
+            let expires = if max_age <= 0 {
+                0
+            } else {
+                now.unix().saturating_add(max_age as u64)
+            };
+            cookie.expires = Some(HttpDate::from_unix(expires));
+        }
+        if let Some(expires) = cookie.expires() {
+            cookie.expires = Some(HttpDate::from_unix(expires.unix().min(limit)));
+        }
+        
+        let stored = StoredCookie::new(cookie, domain, path, host_only);
+        let expired = stored.is_expired(now);
+        self.insert(stored, expired);
+        !expired
+    }
+    
+    ///
+    /// 替换名字、Domain、Path都相同的cookie，位置不变，这样发送时的顺序仍然是创建的顺序
+    ///
+    pub(crate) fn insert(&mut self, stored: StoredCookie, expired: bool) {
+        match self.cookies.iter().position(|cookie| cookie.same_key(&stored)) {
+            Some(index) if expired => {
+                self.cookies.remove(index);
+            }
+            Some(index) => self.cookies[index] = stored,
+            None if expired => {}
+            None => self.cookies.push(stored)
+        }
+    }
+    
+    ///
+    /// 保存响应中所有的`Set-Cookie`，返回保存的数量，无法解析的值被忽略
+    ///
+    pub fn store_response(&mut self, uri: &Uri, header: &HTTPHeadMap) -> usize {
+        self.store_response_at(uri, header, HttpDate::now())
+    }
+    
+    pub(crate) fn store_response_at(&mut self, uri: &Uri, header: &HTTPHeadMap, now: HttpDate) -> usize {
+        header.get_all(HeaderName::SET_COOKIE)
+              .iter()
+              .filter_map(|value| Cookie::parse_set_cookie(value).ok())
+              .filter(|cookie| self.store_at(uri, cookie.clone(), now))
+              .count()
+    }
+    
+    ///
+    /// 请求`uri`时应该发送的cookie，路径长的在前，路径相同时先创建的在前(RFC 6265 5.4)
+    ///
+    pub fn cookies(&self, uri: &Uri) -> Vec<&Cookie> {
+        self.cookies_at(uri, HttpDate::now())
+    }
+    
+    pub(crate) fn cookies_at(&self, uri: &Uri, now: HttpDate) -> Vec<&Cookie> {
+        let host = match uri.host() {
+            Some(host) => host.to_ascii_lowercase(),
+            None => return Vec::new()
+        };
+        let path = if uri.path().is_empty() { "/" } else { uri.path() };
+        let secure = is_secure(uri);
+        
+        let mut cookies = self.cookies
+                              .iter()
+                              .filter(|cookie| cookie.matches(&host, path, secure) && !cookie.is_expired(now))
+                              .collect::<Vec<_>>();
+        //稳定排序，路径长度相同时保持创建的顺序
+        cookies.sort_by_key(|cookie| std::cmp::Reverse(cookie.path().len()));
+        cookies.into_iter()
+               .map(|cookie| cookie.cookie())
+               .collect()
+    }
+    
+    ///
+    /// `Cookie`请求header的值，没有cookie时返回None
+    ///
+    pub fn header_value(&self, uri: &Uri) -> Option<String> {
+        self.header_value_at(uri, HttpDate::now())
+    }
+    
+    pub(crate) fn header_value_at(&self, uri: &Uri, now: HttpDate) -> Option<String> {
+        let cookies = self.cookies_at(uri, now);
+        if cookies.is_empty() {
+            return None
+        }
+        Some(cookies.iter()
+                    .map(|cookie| cookie.pair())
+                    .collect::<Vec<_>>()
+                    .join("; "))
+    }
+    
+    ///
+    /// 删除名字、Domain、Path都相同的cookie
+    ///
+    pub fn remove(&mut self, domain: &str, path: &str, name: &str) -> Option<StoredCookie> {
+        let index = self.cookies
+                        .iter()
+                        .position(|cookie| cookie.cookie.name() == name && cookie.domain() == domain && cookie.path() == path)?;
+        Some(self.cookies.remove(index))
+    }
+    
+    pub fn remove_expired(&mut self) {
+        let now = HttpDate::now();
+        self.cookies.retain(|cookie| !cookie.is_expired(now))
+    }
+    
+    ///
+    /// 删除所有会话cookie，相当于浏览器关闭
+    ///
+    pub fn clear_session(&mut self) {
+        self.cookies.retain(|cookie| !cookie.cookie.is_session())
+    }
+    
+    pub fn clear(&mut self) {
+        self.cookies.clear()
+    }
+    
+    pub fn len(&self) -> usize {
+        self.cookies.len()
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.cookies.is_empty()
+    }
+    
+    pub fn iter(&self) -> slice::Iter<'_, StoredCookie> {
+        self.cookies.iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a CookieJar {
+    type Item = &'a StoredCookie;
+    type IntoIter = slice::Iter<'a, StoredCookie>;
+    
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+fn is_secure(uri: &Uri) -> bool {
+    matches!(uri.scheme().map(|scheme| scheme.to_ascii_lowercase()).as_deref(), Some("https") | Some("wss"))
+}
+
+///
+/// RFC 6265 5.1.3，IP地址只能完全相同
+///
+fn domain_match(host: &str, domain: &str) -> bool {
+    if host == domain {
+        return true
+    }
+    host.ends_with(domain)
+        && host[..host.len() - domain.len()].ends_with('.')
+        && IpAddr::from_str(host).is_err()
+}
+
+///
+/// RFC 6265 5.1.4
+///
+fn path_match(path: &str, cookie_path: &str) -> bool {
+    path == cookie_path
+        || (path.starts_with(cookie_path) && (cookie_path.ends_with('/') || path[cookie_path.len()..].starts_with('/')))
+}
+
+///
+/// 没有Path属性时使用请求路径最后一个'/'之前的部分
+///
+fn default_path(path: &str) -> String {
+    if !path.starts_with('/') {
+        return String::from("/")
+    }
+    match path.rfind('/') {
+        Some(0) | None => String::from("/"),
+        Some(index) => path[..index].to_string()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::cookie::Cookie;
+    use crate::cookie::jar::CookieJar;
+    use crate::header::date::HttpDate;
+    use crate::parser::server::HTTPServerParser;
+    use crate::uri::Uri;
+    
+    fn uri(raw: &str) -> Uri {
+        Uri::parse(raw).unwrap()
+    }
+    
+    #[test]
+    fn matching() {
+        let now = HttpDate::from_unix(1_700_000_000);
+        let mut jar = CookieJar::new();
+        let origin = uri("https://www.example.com/docs/index.html");
+        
+        assert!(jar.store_at(&origin, Cookie::parse_set_cookie("host=1").unwrap(), now));
+        assert!(jar.store_at(&origin, Cookie::parse_set_cookie("wide=2; Domain=example.com; Path=/").unwrap(), now));
+        assert!(jar.store_at(&origin, Cookie::parse_set_cookie("deep=3; Path=/docs/api; Secure").unwrap(), now));
+        //不是请求主机的上级域名，或者是顶级域名
+        assert!(!jar.store_at(&origin, Cookie::parse_set_cookie("a=1; Domain=other.com").unwrap(), now));
+        assert!(!jar.store_at(&origin, Cookie::parse_set_cookie("a=1; Domain=com").unwrap(), now));
+        assert!(!jar.store_at(&uri("http://a.example.co.uk/"), Cookie::parse_set_cookie("a=1; Domain=co.uk").unwrap(), now));
+        assert!(!jar.store_at(&uri("https://user.github.io/"), Cookie::parse_set_cookie("a=1; Domain=github.io").unwrap(), now));
+        assert!(!jar.store_at(&uri("http://shop.example.com.au/"), Cookie::parse_set_cookie("a=1; Domain=.COM.AU").unwrap(), now));
+        assert!(!jar.store_at(&uri("http://www.example.com/"), Cookie::parse_set_cookie("a=1; Secure").unwrap(), now));
+        assert!(!jar.store_at(&origin, Cookie::parse_set_cookie("__Host-a=1; Secure; Path=/docs").unwrap(), now));
+        assert_eq!(jar.len(), 3);
+        
+        assert_eq!(jar.header_value_at(&uri("https://www.example.com/docs/api/v1"), now).unwrap(), "deep=3; host=1; wide=2");
+        assert_eq!(jar.header_value_at(&uri("http://www.example.com/docs/api"), now).unwrap(), "host=1; wide=2");
+        assert_eq!(jar.header_value_at(&uri("https://static.example.com/docs"), now).unwrap(), "wide=2");
+        assert_eq!(jar.header_value_at(&uri("https://www.example.com/docsx"), now).unwrap(), "wide=2");
+        assert!(jar.header_value_at(&uri("https://example.org/"), now).is_none());
+    }
+    
+    #[test]
+    fn public_suffix() {
+        let now = HttpDate::from_unix(1_700_000_000);
+        let mut jar = CookieJar::new().public_suffix("hosting.example");
+        
+        assert!(!jar.store_at(&uri("http://a.example.co.uk/"), Cookie::parse_set_cookie("a=1; Domain=co.uk").unwrap(), now));
+        assert!(jar.header_value_at(&uri("http://bank.co.uk/"), now).is_none());
+        assert!(!jar.store_at(&uri("http://a.hosting.example/"), Cookie::parse_set_cookie("a=1; Domain=hosting.example").unwrap(), now));
+        
+        //Domain等于主机时只发送给这个主机
+        assert!(jar.store_at(&uri("http://github.io/"), Cookie::parse_set_cookie("b=2; Domain=github.io").unwrap(), now));
+        assert!(jar.iter().all(|cookie| cookie.is_host_only()));
+        assert_eq!(jar.header_value_at(&uri("http://github.io/"), now).unwrap(), "b=2");
+        assert!(jar.header_value_at(&uri("http://user.github.io/"), now).is_none());
+        
+        assert!(jar.store_at(&uri("http://a.example.co.uk/"), Cookie::parse_set_cookie("c=3; Domain=example.co.uk").unwrap(), now));
+        assert_eq!(jar.header_value_at(&uri("http://b.example.co.uk/"), now).unwrap(), "c=3");
+    }
+    
+    #[test]
+    fn expiry() {
+        let now = HttpDate::from_unix(1_700_000_000);
+        let mut jar = CookieJar::new();
+        let origin = uri("http://example.com/");
+        
+        jar.store_at(&origin, Cookie::parse_set_cookie("a=1; Max-Age=60").unwrap(), now);
+        jar.store_at(&origin, Cookie::parse_set_cookie("b=2; Expires=Thu, 01 Jan 2099 00:00:00 GMT; Max-Age=10").unwrap(), now);
+        assert_eq!(jar.header_value_at(&origin, now).unwrap(), "a=1; b=2");
+        assert_eq!(jar.header_value_at(&origin, HttpDate::from_unix(now.unix() + 30)).unwrap(), "a=1");
+        
+        //同名cookie被替换，Max-Age=0删除
+        jar.store_at(&origin, Cookie::parse_set_cookie("a=3").unwrap(), now);
+        assert_eq!(jar.header_value_at(&origin, now).unwrap(), "a=3; b=2");
+        assert!(!jar.store_at(&origin, Cookie::parse_set_cookie("a=; Max-Age=0").unwrap(), now));
+        assert!(!jar.store_at(&origin, Cookie::parse_set_cookie("b=; Expires=Thu, 01 Jan 1970 00:00:00 GMT").unwrap(), now));
+        assert!(jar.is_empty());
+        
+        //过大的Max-Age和Expires都被限制为400天
+        assert!(jar.store_at(&origin, Cookie::parse_set_cookie("c=1; Max-Age=9223372036854775807").unwrap(), now));
+        assert!(jar.store_at(&origin, Cookie::parse_set_cookie("d=1; Expires=Fri, 31 Dec 9999 23:59:59 GMT").unwrap(), now));
+        let limit = HttpDate::from_unix(now.unix() + 400 * 86400);
+        assert!(jar.iter().all(|cookie| cookie.cookie().expires() == Some(limit)));
+        assert!(jar.header_value_at(&origin, HttpDate::from_unix(limit.unix() - 1)).is_some());
+        assert!(jar.header_value_at(&origin, limit).is_none());
+    }
+    
+    #[test]
+    fn response() {
+        let mut parser = HTTPServerParser::new();
+        parser.feed(b"HTTP/1.1 200 OK\r\nSet-Cookie: a=1; Path=/\r\nSet-Cookie: b=2; HttpOnly\r\nSet-Cookie: bad\r\nContent-Length: 0\r\n\r\n").unwrap();
+        let response = parser.take().unwrap();
+        
+        let mut jar = CookieJar::new();
+        let origin = uri("http://example.com/login");
+        assert_eq!(jar.store_response(&origin, response.header()), 2);
+        assert_eq!(jar.header_value(&uri("http://example.com/home")).unwrap(), "a=1; b=2");
+        assert!(jar.iter().any(|cookie| cookie.cookie().is_http_only() && cookie.is_host_only()));
+    }
+}
\ No newline at end of file
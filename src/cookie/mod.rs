@@ -0,0 +1,449 @@
+//!
+//! Cookie(RFC 6265)
+//!
+//! [`Cookie`]对应一个`Set-Cookie`或者`Cookie`中的一项，[`CookieJar`]按照域名和路径保存客户端收到的cookie，
+//! 可以读写curl和浏览器使用的cookies.txt
+//!
+
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+use crate::header::date::HttpDate;
+use crate::map::is_tchar;
+
+pub use jar::{CookieJar, StoredCookie};
+
+pub mod jar;
+pub mod netscape;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CookieError {
+    //没有'='或者名字为空
+    MissingPair,
+    //名字不是token
+    InvalidName,
+    //值包含空白、'"'、','、';'或者'\'
+    InvalidValue,
+    //Domain或者Path包含';'或者控制字符
+    InvalidAttribute
+}
+
+pub type CookieResult<T> = Result<T, CookieError>;
+
+///
+/// SameSite属性(RFC 6265bis)
+///
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum SameSite {
+    Strict,
+    Lax,
+    None
+}
+
+impl SameSite {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SameSite::Strict => "Strict",
+            SameSite::Lax => "Lax",
+            SameSite::None => "None"
+        }
+    }
+    
+    ///
+    /// 不区分大小写
+    ///
+    pub fn parse(raw: &str) -> Option<Self> {
+        [SameSite::Strict, SameSite::Lax, SameSite::None]
+            .iter()
+            .find(|same_site| same_site.as_str().eq_ignore_ascii_case(raw))
+            .copied()
+    }
+}
+
+impl Display for SameSite {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+///
+/// 一个cookie以及它的属性，`Display`输出`Set-Cookie`的值
+///
+/// 解析时按照RFC 6265 5.2的宽松规则，无法识别的属性和格式错误的属性值会被忽略
+///
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Cookie {
+    name: String,
+    value: String,
+    expires: Option<HttpDate>,
+    max_age: Option<i64>,
+    domain: Option<String>,
+    path: Option<String>,
+    secure: bool,
+    http_only: bool,
+    same_site: Option<SameSite>,
+    partitioned: bool
+}
+
+impl Cookie {
+    ///
+    /// 不检查名字和值，发送之前可以使用`validate`
+    ///
+    pub fn new<N, V>(name: N, value: V) -> Self
+        where
+            N: ToString,
+            V: ToString
+    {
+        Cookie {
+            name: name.to_string(),
+            value: value.to_string(),
+            expires: None,
+            max_age: None,
+            domain: None,
+            path: None,
+            secure: false,
+            http_only: false,
+            same_site: None,
+            partitioned: false
+        }
+    }
+    
+    pub fn builder<N, V>(name: N, value: V) -> CookieBuilder
+        where
+            N: ToString,
+            V: ToString
+    {
+        CookieBuilder {
+            cookie: Cookie::new(name, value)
+        }
+    }
+    
+    ///
+    /// 解析`Set-Cookie`的值，例如`id=a3fWa; Max-Age=2592000; Path=/; Secure; HttpOnly`
+    ///
+    pub fn parse_set_cookie(raw: &str) -> CookieResult<Self> {
+        let mut sp = raw.split(';');
+        let (name, value) = sp.next()
+                              .unwrap_or_default()
+                              .split_once('=')
+                              .ok_or(CookieError::MissingPair)?;
+        let (name, value) = (name.trim(), value.trim());
+        if name.is_empty() {
+            return Err(CookieError::MissingPair)
+        }
+        
+        let mut cookie = Cookie::new(name, value);
+        for attribute in sp {
+            let (key, value) = match attribute.split_once('=') {
+                Some((key, value)) => (key.trim(), value.trim()),
+                None => (attribute.trim(), "")
+            };
+            
+            match key.to_ascii_lowercase().as_str() {
+                "expires" => {
+                    if let Some(date) = HttpDate::parse_cookie(value) {
+                        cookie.expires = Some(date);
+                    }
+                }
+                "max-age" => {
+                    if let Some(max_age) = parse_max_age(value) {
+                        cookie.max_age = Some(max_age);
+                    }
+                }
+                "domain" => {
+                    //开头的'.'没有意义(RFC 6265 5.2.3)
+                    let domain = value.strip_prefix('.').unwrap_or(value);
+                    if !domain.is_empty() {
+                        cookie.domain = Some(domain.to_ascii_lowercase());
+                    }
+                }
+                "path" => {
+                    //不是以'/'开头时使用默认路径
+                    cookie.path = if value.starts_with('/') { Some(value.to_string()) } else { None };
+                }
+                "secure" => cookie.secure = true,
+                "httponly" => cookie.http_only = true,
+                "samesite" => cookie.same_site = SameSite::parse(value),
+                "partitioned" => cookie.partitioned = true,
+                _ => {}
+            }
+        }
+        Ok(cookie)
+    }
+    
+    ///
+    /// 解析请求中的`Cookie`，例如`a=1; b=2`，没有'='的项被忽略
+    ///
+    pub fn parse_cookie_header(raw: &str) -> Vec<Self> {
+        raw.split(';')
+           .filter_map(|pair| pair.split_once('='))
+           .map(|(name, value)| (name.trim(), value.trim()))
+           .filter(|(name, _)| !name.is_empty())
+           .map(|(name, value)| Cookie::new(name, value))
+           .collect()
+    }
+    
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+    
+    pub fn set_value<V>(&mut self, value: V)
+        where
+            V: ToString
+    {
+        self.value = value.to_string()
+    }
+    
+    pub fn expires(&self) -> Option<HttpDate> {
+        self.expires
+    }
+    
+    ///
+    /// 秒数，0和负数表示立即过期
+    ///
+    pub fn max_age(&self) -> Option<i64> {
+        self.max_age
+    }
+    
+    ///
+    /// 小写，不带开头的'.'
+    ///
+    pub fn domain(&self) -> Option<&str> {
+        self.domain.as_deref()
+    }
+    
+    pub fn path(&self) -> Option<&str> {
+        self.path.as_deref()
+    }
+    
+    pub fn is_secure(&self) -> bool {
+        self.secure
+    }
+    
+    pub fn is_http_only(&self) -> bool {
+        self.http_only
+    }
+    
+    pub fn same_site(&self) -> Option<SameSite> {
+        self.same_site
+    }
+    
+    pub fn is_partitioned(&self) -> bool {
+        self.partitioned
+    }
+    
+    ///
+    /// 既没有Max-Age也没有Expires，浏览器关闭时删除
+    ///
+    pub fn is_session(&self) -> bool {
+        self.max_age.is_none() && self.expires.is_none()
+    }
+    
+    ///
+    /// `Cookie`请求header中的`name=value`
+    ///
+    pub fn pair(&self) -> String {
+        format!("{}={}", self.name, self.value)
+    }
+    
+    ///
+    /// 检查名字、值以及Domain、Path，发送时不会产生额外的属性或者被注入的行
+    ///
+    pub fn validate(&self) -> CookieResult<()> {
+        if self.name.is_empty() || !self.name.bytes().all(is_tchar) {
+            return Err(CookieError::InvalidName)
+        }
+        //值可以用双引号包围
+        let value = self.value
+                        .strip_prefix('"')
+                        .and_then(|value| value.strip_suffix('"'))
+                        .unwrap_or(&self.value);
+        if !value.bytes().all(is_cookie_octet) {
+            return Err(CookieError::InvalidValue)
+        }
+        let attributes = [self.domain.as_deref(), self.path.as_deref()];
+        if attributes.iter().flatten().any(|value| value.bytes().any(|b| b == b';' || b.is_ascii_control())) {
+            return Err(CookieError::InvalidAttribute)
+        }
+        Ok(())
+    }
+}
+
+impl Display for Cookie {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}={}", self.name, self.value)?;
+        if let Some(expires) = self.expires {
+            write!(f, "; Expires={}", expires)?;
+        }
+        if let Some(max_age) = self.max_age {
+            write!(f, "; Max-Age={}", max_age)?;
+        }
+        if let Some(domain) = &self.domain {
+            write!(f, "; Domain={}", domain)?;
+        }
+        if let Some(path) = &self.path {
+            write!(f, "; Path={}", path)?;
+        }
+        if self.secure {
+            f.write_str("; Secure")?;
+        }
+        if self.http_only {
+            f.write_str("; HttpOnly")?;
+        }
+        if let Some(same_site) = self.same_site {
+            write!(f, "; SameSite={}", same_site)?;
+        }
+        if self.partitioned {
+            f.write_str("; Partitioned")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct CookieBuilder {
+    cookie: Cookie
+}
+
+impl CookieBuilder {
+    pub fn expires(self, expires: HttpDate) -> Self {
+        let mut this = self;
+        this.cookie.expires = Some(expires);
+        this
+    }
+    
+    pub fn max_age(self, max_age: i64) -> Self {
+        let mut this = self;
+        this.cookie.max_age = Some(max_age);
+        this
+    }
+    
+    pub fn domain<T>(self, domain: T) -> Self
+        where
+            T: ToString
+    {
+        let mut this = self;
+        let domain = domain.to_string().to_ascii_lowercase();
+        this.cookie.domain = Some(domain.strip_prefix('.').unwrap_or(&domain).to_string());
+        this
+    }
+    
+    pub fn path<T>(self, path: T) -> Self
+        where
+            T: ToString
+    {
+        let mut this = self;
+        this.cookie.path = Some(path.to_string());
+        this
+    }
+    
+    pub fn secure(self, secure: bool) -> Self {
+        let mut this = self;
+        this.cookie.secure = secure;
+        this
+    }
+    
+    pub fn http_only(self, http_only: bool) -> Self {
+        let mut this = self;
+        this.cookie.http_only = http_only;
+        this
+    }
+    
+    pub fn same_site(self, same_site: SameSite) -> Self {
+        let mut this = self;
+        this.cookie.same_site = Some(same_site);
+        this
+    }
+    
+    ///
+    /// CHIPS，只能和Secure一起使用
+    ///
+    pub fn partitioned(self, partitioned: bool) -> Self {
+        let mut this = self;
+        this.cookie.partitioned = partitioned;
+        this
+    }
+    
+    pub fn build(self) -> Cookie {
+        self.cookie
+    }
+}
+
+///
+/// `Max-Age`：可选的'-'加上数字，溢出时取最大值
+///
+fn parse_max_age(raw: &str) -> Option<i64> {
+    let digits = raw.strip_prefix('-').unwrap_or(raw);
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None
+    }
+    Some(match raw.parse::<i64>() {
+        Ok(max_age) => max_age,
+        Err(_) if raw.starts_with('-') => i64::MIN,
+        Err(_) => i64::MAX
+    })
+}
+
+///
+/// cookie-octet(RFC 6265 4.1.1)
+///
+fn is_cookie_octet(b: u8) -> bool {
+    matches!(b, 0x21 | 0x23..=0x2b | 0x2d..=0x3a | 0x3c..=0x5b | 0x5d..=0x7e)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::cookie::{Cookie, CookieError, SameSite};
+    use crate::header::date::HttpDate;
+    
+    #[test]
+    fn set_cookie() {
+        let cookie = Cookie::parse_set_cookie(
+            "id=a3fWa; Expires=Wed, 21 Oct 2015 07:28:00 GMT; Max-Age=2592000; Domain=.Example.com; Path=/docs; Secure; HttpOnly; SameSite=lax; Partitioned; Foo=bar"
+        ).unwrap();
+        assert_eq!((cookie.name(), cookie.value()), ("id", "a3fWa"));
+        assert_eq!(cookie.expires(), HttpDate::parse("Wed, 21 Oct 2015 07:28:00 GMT"));
+        assert_eq!(cookie.max_age(), Some(2592000));
+        assert_eq!(cookie.domain(), Some("example.com"));
+        assert_eq!(cookie.path(), Some("/docs"));
+        assert!(cookie.is_secure() && cookie.is_http_only() && cookie.is_partitioned());
+        assert_eq!(cookie.same_site(), Some(SameSite::Lax));
+        assert_eq!(
+            cookie.to_string(),
+            "id=a3fWa; Expires=Wed, 21 Oct 2015 07:28:00 GMT; Max-Age=2592000; Domain=example.com; Path=/docs; Secure; HttpOnly; SameSite=Lax; Partitioned"
+        );
+        assert_eq!(Cookie::parse_set_cookie(&cookie.to_string()), Ok(cookie));
+        
+        //格式错误的属性被忽略
+        let cookie = Cookie::parse_set_cookie("a = b ; Max-Age=1x; Path=docs; Expires=soon").unwrap();
+        assert_eq!(cookie, Cookie::new("a", "b"));
+        assert!(cookie.is_session());
+        
+        assert_eq!(Cookie::parse_set_cookie("novalue"), Err(CookieError::MissingPair));
+        assert_eq!(Cookie::parse_set_cookie("=x"), Err(CookieError::MissingPair));
+    }
+    
+    #[test]
+    fn build() {
+        let cookie = Cookie::builder("session", "\"abc\"")
+            .path("/")
+            .max_age(0)
+            .http_only(true)
+            .same_site(SameSite::Strict)
+            .build();
+        assert_eq!(cookie.to_string(), "session=\"abc\"; Max-Age=0; Path=/; HttpOnly; SameSite=Strict");
+        assert!(cookie.validate().is_ok());
+        
+        assert_eq!(Cookie::new("a b", "x").validate(), Err(CookieError::InvalidName));
+        assert_eq!(Cookie::new("a", "x;y").validate(), Err(CookieError::InvalidValue));
+        let cookie = Cookie::builder("a", "x").path("/\r\nSet-Cookie: evil=1").build();
+        assert_eq!(cookie.validate(), Err(CookieError::InvalidAttribute));
+        
+        let cookies = Cookie::parse_cookie_header("a=1; b=x=y;; c");
+        assert_eq!(cookies, vec![Cookie::new("a", "1"), Cookie::new("b", "x=y")]);
+    }
+}
\ No newline at end of file
@@ -0,0 +1,219 @@
+//!
+//! Netscape/curl的cookies.txt格式
+//!
+//! 每行七个用制表符分隔的字段：域名、是否包含子域名、路径、Secure、过期时间(Unix秒数，0表示会话cookie)、名字、值
+//!
+//! curl用`#HttpOnly_`前缀标记HttpOnly的cookie，其余以`#`开头的行是注释
+//!
+
+use std::fs::File;
+use std::io;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+use crate::cookie::{Cookie, CookieJar, StoredCookie};
+use crate::header::date::HttpDate;
+
+const HTTP_ONLY_PREFIX: &str = "#HttpOnly_";
+
+impl CookieJar {
+    ///
+    /// 读取cookies.txt，和已有的cookie合并，返回读取的数量
+    ///
+    /// 格式错误的行和已经过期的cookie被忽略，这和curl的行为相同
+    ///
+    pub fn read_netscape<R>(&mut self, r: R) -> io::Result<usize>
+        where
+            R: BufRead
+    {
+        let now = HttpDate::now();
+        let mut count = 0;
+        for line in r.lines() {
+            if let Some(stored) = parse_line(&line?) {
+                if !stored.is_expired(now) {
+                    self.insert(stored, false);
+                    count += 1;
+                }
+            }
+        }
+        Ok(count)
+    }
+    
+    ///
+    /// 按照curl的格式写入所有cookie，包括会话cookie
+    ///
+    /// 字段中有制表符或者换行的cookie无法表示，会被跳过
+    ///
+    pub fn write_netscape<W>(&self, w: &mut W) -> io::Result<()>
+        where
+            W: Write
+    {
+        w.write_all(b"# Netscape HTTP Cookie File\n")?;
+        for stored in self {
+            let cookie = stored.cookie();
+            //只有制表符和换行会破坏文件的结构，空格之类的字符原样写入
+            let fields = [stored.domain(), stored.path(), cookie.name(), cookie.value()];
+            if fields.iter().any(|field| field.contains(['\t', '\r', '\n'])) {
+                continue
+            }
+            
+            let prefix = if cookie.is_http_only() { HTTP_ONLY_PREFIX } else { "" };
+            let (domain_prefix, subdomains) = if stored.is_host_only() { ("", "FALSE") } else { (".", "TRUE") };
+            writeln!(
+                w,
+                "{}{}{}\t{}\t{}\t{}\t{}\t{}\t{}",
+                prefix,
+                domain_prefix,
+                stored.domain(),
+                subdomains,
+                stored.path(),
+                if cookie.is_secure() { "TRUE" } else { "FALSE" },
+                cookie.expires().map(|expires| expires.unix()).unwrap_or(0),
+                cookie.name(),
+                cookie.value()
+            )?;
+        }
+        Ok(())
+    }
+    
+    pub fn load<P>(path: P) -> io::Result<Self>
+        where
+            P: AsRef<Path>
+    {
+        let mut jar = CookieJar::new();
+        jar.read_netscape(BufReader::new(File::open(path)?))?;
+        Ok(jar)
+    }
+    
+    pub fn save<P>(&self, path: P) -> io::Result<()>
+        where
+            P: AsRef<Path>
+    {
+        let mut w = BufWriter::new(File::create(path)?);
+        self.write_netscape(&mut w)?;
+        w.flush()
+    }
+}
+
+fn parse_line(line: &str) -> Option<StoredCookie> {
+    let line = line.trim_end_matches(['\r', '\n']);
+    let (line, http_only) = match line.strip_prefix(HTTP_ONLY_PREFIX) {
+        Some(line) => (line, true),
+        None if line.starts_with('#') || line.trim().is_empty() => return None,
+        None => (line, false)
+    };
+    
+    let fields = line.split('\t').collect::<Vec<_>>();
+    //curl会写出没有值的行
+    let (domain, subdomains, path, secure, expires, name, value) = match fields[..] {
+        [domain, subdomains, path, secure, expires, name, value] => (domain, subdomains, path, secure, expires, name, value),
+        [domain, subdomains, path, secure, expires, name] => (domain, subdomains, path, secure, expires, name, ""),
+        _ => return None
+    };
+    let expires = expires.parse::<u64>().ok()?;
+    if name.is_empty() || domain.is_empty() {
+        return None
+    }
+    
+    let mut builder = Cookie::builder(name, value)
+        .secure(secure.eq_ignore_ascii_case("TRUE"))
+        .http_only(http_only);
+    if expires != 0 {
+        builder = builder.expires(HttpDate::from_unix(expires));
+    }
+    //".example.com"同样表示包含子域名
+    let host_only = !subdomains.eq_ignore_ascii_case("TRUE") && !domain.starts_with('.');
+    let domain = domain.strip_prefix('.').unwrap_or(domain);
+    let path = if path.starts_with('/') { path } else { "/" };
+    
+    Some(StoredCookie::new(builder.build(), domain, path, host_only))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::cookie::{Cookie, CookieJar, StoredCookie};
+    use crate::header::method::HTTPClientMethod;
+    use crate::parser::server::HTTPServerParser;
+    use crate::response::client::HTTPClientResponseBuilder;
+    use crate::uri::Uri;
+    
+    const FILE: &str = "# Netscape HTTP Cookie File\n\
+                        # https://curl.se/docs/http-cookies.html\n\
+                        \n\
+                        .example.com\tTRUE\t/\tFALSE\t4102444800\ttheme\tdark\n\
+                        #HttpOnly_api.example.com\tFALSE\t/v1\tTRUE\t0\tsid\tabc123\n\
+                        old.example.com\tFALSE\t/\tFALSE\t1\texpired\tx\n\
+                        broken line\n\
+                        example.com\tFALSE\t/\tFALSE\t0\tempty\n";
+    
+    #[test]
+    fn read_write() {
+        let mut jar = CookieJar::new();
+        assert_eq!(jar.read_netscape(FILE.as_bytes()).unwrap(), 3);
+        
+        let sid = jar.iter().find(|stored| stored.cookie().name() == "sid").unwrap();
+        assert!(sid.is_host_only() && sid.cookie().is_http_only() && sid.cookie().is_secure());
+        assert!(sid.cookie().is_session());
+        assert_eq!(sid.path(), "/v1");
+        
+        let mut buf = Vec::new();
+        jar.write_netscape(&mut buf).unwrap();
+        assert_eq!(
+            String::from_utf8(buf.clone()).unwrap(),
+            "# Netscape HTTP Cookie File\n\
+             .example.com\tTRUE\t/\tFALSE\t4102444800\ttheme\tdark\n\
+             #HttpOnly_api.example.com\tFALSE\t/v1\tTRUE\t0\tsid\tabc123\n\
+             example.com\tFALSE\t/\tFALSE\t0\tempty\t\n"
+        );
+        
+        let mut again = CookieJar::new();
+        again.read_netscape(&buf[..]).unwrap();
+        assert_eq!(again.iter().collect::<Vec<_>>(), jar.iter().collect::<Vec<_>>());
+    }
+    
+    #[test]
+    fn separators() {
+        let mut jar = CookieJar::new();
+        assert_eq!(jar.read_netscape("example.com\tFALSE\t/\tFALSE\t0\tpref\ta b\n".as_bytes()).unwrap(), 1);
+        jar.insert(StoredCookie::new(Cookie::builder("bad", "a\tb").build(), "example.com", "/", true), false);
+        jar.insert(StoredCookie::new(Cookie::builder("bad\n", "1").build(), "example.com", "/", true), false);
+        
+        //只跳过有制表符或者换行的cookie
+        let mut buf = Vec::new();
+        jar.write_netscape(&mut buf).unwrap();
+        assert_eq!(
+            String::from_utf8(buf.clone()).unwrap(),
+            "# Netscape HTTP Cookie File\n\
+             example.com\tFALSE\t/\tFALSE\t0\tpref\ta b\n"
+        );
+        
+        let mut again = CookieJar::new();
+        again.read_netscape(&buf[..]).unwrap();
+        assert_eq!(again.iter().next().unwrap().cookie().value(), "a b");
+    }
+    
+    #[test]
+    fn session() {
+        let mut jar = CookieJar::new();
+        jar.read_netscape(FILE.as_bytes()).unwrap();
+        
+        let uri = Uri::parse("https://api.example.com/v1/users").unwrap();
+        let request = HTTPClientResponseBuilder::new()
+            .method(HTTPClientMethod::GET)
+            .resource(uri.path_and_query())
+            .cookies(&jar, &uri)
+            .build();
+        assert_eq!(request.header().get("Cookie").unwrap(), "sid=abc123; theme=dark");
+        
+        //响应中的Set-Cookie写回之后可以再保存为文件
+        let mut parser = HTTPServerParser::new();
+        parser.feed(b"HTTP/1.1 200 OK\r\nSet-Cookie: sid=new; Path=/v1; Secure; HttpOnly\r\nSet-Cookie: theme=; Domain=example.com; Path=/; Max-Age=0\r\nContent-Length: 0\r\n\r\n").unwrap();
+        assert_eq!(jar.store_response(&uri, parser.take().unwrap().header()), 1);
+        
+        let mut buf = Vec::new();
+        jar.write_netscape(&mut buf).unwrap();
+        let text = String::from_utf8(buf).unwrap();
+        assert!(text.contains("#HttpOnly_api.example.com\tFALSE\t/v1\tTRUE\t0\tsid\tnew\n"));
+        assert!(!text.contains("theme"));
+    }
+}
\ No newline at end of file
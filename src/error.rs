@@ -1,2 +1,509 @@
 pub use super::header::HTTPVersionMatchError;
 
+use std::error;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::io;
+
+use crate::cookie::CookieError;
+use crate::header::method::ServerMethodCode;
+use crate::header::HTTPMethodMatchError;
+use crate::header::typed::TypedHeaderError;
+use crate::map::HeaderMappingError;
+use crate::multipart::MultipartError;
+use crate::parser::HTTPParseError;
+use crate::uri::UriError;
+
+///
+/// 解析失败的位置
+///
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    StartLine,
+    Method,
+    //请求目标或者URI不合法
+    Target,
+    Status,
+    Version,
+    //解析器只知道header行有错，单独解析header时带有具体原因
+    Header(Option<HeaderMappingError>),
+    ContentLength,
+    Chunk,
+    //multipart body的格式错误
+    Multipart(MultipartError),
+    UnexpectedEof
+}
+
+///
+/// 语法错误，`offset`是出错的字节在消息中的位置，从单独的枚举转换而来时未知
+///
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    kind: ParseErrorKind,
+    offset: Option<usize>
+}
+
+impl ParseError {
+    pub fn new(kind: ParseErrorKind, offset: Option<usize>) -> Self {
+        ParseError {
+            kind,
+            offset
+        }
+    }
+    
+    pub fn kind(&self) -> ParseErrorKind {
+        self.kind
+    }
+    
+    pub fn offset(&self) -> Option<usize> {
+        self.offset
+    }
+}
+
+///
+/// 消息语法正确但是违反了协议，例如可能导致请求走私的长度，或者发送时会破坏消息结构的字段
+///
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ProtocolError {
+    ConflictingLength,
+    DuplicateContentLength,
+    UnsupportedTransferEncoding,
+    //发送时header名不是token或者值包含控制字符
+    InvalidField,
+    //扩展方法不是token
+    InvalidMethod,
+    //请求目标为空或者包含空白、控制字符
+    InvalidTarget,
+    //状态码不是三位数
+    InvalidStatus,
+    //原因短语包含控制字符
+    InvalidReason,
+    //Content-Length不是十进制数字
+    InvalidContentLength,
+    //1xx、204、304的响应带有body
+    UnexpectedBody,
+    //Content-Length和body的长度不同
+    ContentLengthMismatch,
+    //Transfer-Encoding中chunked出现了不止一次
+    RepeatedChunked,
+    //HTTP/1.1的请求没有Host
+    MissingHost,
+    //请求有多个Host
+    DuplicateHost
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LimitError {
+    StartLine,
+    HeaderCount,
+    HeaderSize,
+    BodySize
+}
+
+///
+/// http-rs_plus所有错误的统一类型
+///
+#[derive(Debug)]
+pub enum Error {
+    Parse(ParseError),
+    Io(io::Error),
+    Protocol(ProtocolError),
+    Limit(LimitError)
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    ///
+    /// 带上解析器给出的位置，例如`parser.feed(buf).map_err(|e| Error::parse_at(e, parser.offset()))?`，
+    /// 解析器的`try_feed`已经这样做了
+    ///
+    pub fn parse_at(error: HTTPParseError, offset: usize) -> Self {
+        match Error::from(error) {
+            Error::Parse(parse) => Error::Parse(ParseError::new(parse.kind, Some(offset))),
+            error => error
+        }
+    }
+    
+    ///
+    /// 服务器因为这个错误拒绝请求时应该使用的状态码
+    ///
+    pub fn status_code(&self) -> ServerMethodCode {
+        match self {
+            Error::Parse(_) | Error::Protocol(_) => 400,
+            Error::Io(_) => 500,
+            Error::Limit(LimitError::StartLine) => 414,
+            Error::Limit(LimitError::HeaderCount) | Error::Limit(LimitError::HeaderSize) => 431,
+            Error::Limit(LimitError::BodySize) => 413
+        }
+    }
+}
+
+impl Display for ParseErrorKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseErrorKind::StartLine => f.write_str("invalid start line"),
+            ParseErrorKind::Method => f.write_str("invalid method"),
+            ParseErrorKind::Target => f.write_str("invalid request target"),
+            ParseErrorKind::Status => f.write_str("invalid status code"),
+            ParseErrorKind::Version => f.write_str("invalid HTTP version"),
+            ParseErrorKind::Header(Some(error)) => write!(f, "invalid header field: {}", error),
+            ParseErrorKind::Header(None) => f.write_str("invalid header field"),
+            ParseErrorKind::ContentLength => f.write_str("invalid Content-Length"),
+            ParseErrorKind::Chunk => f.write_str("invalid chunked encoding"),
+            ParseErrorKind::Multipart(error) => write!(f, "invalid multipart body: {}", error),
+            ParseErrorKind::UnexpectedEof => f.write_str("connection closed before message completed")
+        }
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.offset {
+            Some(offset) => write!(f, "{} at byte {}", self.kind, offset),
+            None => write!(f, "{}", self.kind)
+        }
+    }
+}
+
+impl Display for ProtocolError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ProtocolError::ConflictingLength => "both Content-Length and Transfer-Encoding are present",
+            ProtocolError::DuplicateContentLength => "conflicting Content-Length values",
+            ProtocolError::UnsupportedTransferEncoding => "transfer coding is not chunked",
+            ProtocolError::InvalidField => "header field cannot be serialized",
+            ProtocolError::InvalidMethod => "request method is not a token",
+            ProtocolError::InvalidTarget => "request target contains invalid characters",
+            ProtocolError::InvalidStatus => "status code is not three digits",
+            ProtocolError::InvalidReason => "reason phrase contains control characters",
+            ProtocolError::InvalidContentLength => "Content-Length is not a decimal number",
+            ProtocolError::UnexpectedBody => "status code does not allow a body",
+            ProtocolError::ContentLengthMismatch => "Content-Length does not match the body length",
+            ProtocolError::RepeatedChunked => "chunked is applied more than once",
+            ProtocolError::MissingHost => "HTTP/1.1 request has no Host",
+            ProtocolError::DuplicateHost => "request has more than one Host"
+        })
+    }
+}
+
+impl Display for LimitError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            LimitError::StartLine => "start line too long",
+            LimitError::HeaderCount => "too many header fields",
+            LimitError::HeaderSize => "header section too large",
+            LimitError::BodySize => "body too large"
+        })
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Parse(error) => write!(f, "parse error: {}", error),
+            Error::Io(error) => write!(f, "io error: {}", error),
+            Error::Protocol(error) => write!(f, "protocol error: {}", error),
+            Error::Limit(error) => write!(f, "limit exceeded: {}", error)
+        }
+    }
+}
+
+impl error::Error for ParseError {}
+
+impl error::Error for ProtocolError {}
+
+impl error::Error for LimitError {}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Parse(error) => Some(error),
+            Error::Io(error) => Some(error),
+            Error::Protocol(error) => Some(error),
+            Error::Limit(error) => Some(error)
+        }
+    }
+}
+
+impl Display for HTTPMethodMatchError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            HTTPMethodMatchError::NoMatch => f.write_str("unknown method")
+        }
+    }
+}
+
+impl Display for HTTPVersionMatchError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            HTTPVersionMatchError::UnknownChars => f.write_str("HTTP version is not valid text"),
+            HTTPVersionMatchError::NotMatch => f.write_str("unknown HTTP version")
+        }
+    }
+}
+
+impl Display for HeaderMappingError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            HeaderMappingError::UnknownChars => "field value is not UTF-8",
+            HeaderMappingError::UnknownString => "missing ':' in header field",
+            HeaderMappingError::EmptyRaw | HeaderMappingError::EmptyString => "empty header field",
+            HeaderMappingError::InvalidName => "field name is not a token",
+            HeaderMappingError::InvalidValue => "field value contains invalid characters"
+        })
+    }
+}
+
+impl Display for TypedHeaderError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            TypedHeaderError::Missing => "header field is missing",
+            TypedHeaderError::Invalid => "header field value is invalid"
+        })
+    }
+}
+
+impl Display for UriError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            UriError::Empty => "empty URI",
+            UriError::InvalidScheme => "invalid URI scheme",
+            UriError::InvalidAuthority => "invalid URI authority",
+            UriError::InvalidHost => "invalid URI host",
+            UriError::InvalidPort => "invalid URI port",
+            UriError::InvalidChar => "URI contains invalid characters",
+            UriError::InvalidPercentEncoding => "invalid percent-encoding in URI",
+            UriError::InvalidForm => "URI is not a valid request target",
+            UriError::EncodedSeparator => "path contains an encoded separator",
+            UriError::Traversal => "path escapes the base directory"
+        })
+    }
+}
+
+impl Display for CookieError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            CookieError::MissingPair => "cookie has no name=value pair",
+            CookieError::InvalidName => "cookie name is not a token",
+            CookieError::InvalidValue => "cookie value contains invalid characters",
+            CookieError::InvalidAttribute => "cookie attribute contains invalid characters"
+        })
+    }
+}
+
+impl Display for MultipartError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            MultipartError::MissingBoundary => "content type is not multipart or has no boundary",
+            MultipartError::InvalidBoundary => "invalid multipart boundary",
+            MultipartError::InvalidDelimiter => "invalid multipart delimiter line",
+            MultipartError::InvalidHeader => "invalid multipart part header",
+            MultipartError::HeaderTooLarge => "multipart part header too large",
+            MultipartError::UnexpectedEof => "multipart body ended before the close delimiter"
+        })
+    }
+}
+
+impl Display for HTTPParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        Display::fmt(&Error::from(*self), f)
+    }
+}
+
+impl error::Error for HTTPMethodMatchError {}
+
+impl error::Error for HTTPVersionMatchError {}
+
+impl error::Error for HeaderMappingError {}
+
+impl error::Error for TypedHeaderError {}
+
+impl error::Error for UriError {}
+
+impl error::Error for CookieError {}
+
+impl error::Error for MultipartError {}
+
+impl error::Error for HTTPParseError {}
+
+impl From<ParseError> for Error {
+    fn from(error: ParseError) -> Self {
+        Error::Parse(error)
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Self {
+        Error::Io(error)
+    }
+}
+
+impl From<ProtocolError> for Error {
+    fn from(error: ProtocolError) -> Self {
+        Error::Protocol(error)
+    }
+}
+
+impl From<LimitError> for Error {
+    fn from(error: LimitError) -> Self {
+        Error::Limit(error)
+    }
+}
+
+impl From<HTTPMethodMatchError> for Error {
+    fn from(_: HTTPMethodMatchError) -> Self {
+        Error::Parse(ParseError::new(ParseErrorKind::Method, None))
+    }
+}
+
+impl From<HTTPVersionMatchError> for Error {
+    fn from(_: HTTPVersionMatchError) -> Self {
+        Error::Parse(ParseError::new(ParseErrorKind::Version, None))
+    }
+}
+
+impl From<HeaderMappingError> for Error {
+    fn from(error: HeaderMappingError) -> Self {
+        Error::Parse(ParseError::new(ParseErrorKind::Header(Some(error)), None))
+    }
+}
+
+impl From<TypedHeaderError> for Error {
+    fn from(_: TypedHeaderError) -> Self {
+        Error::Parse(ParseError::new(ParseErrorKind::Header(None), None))
+    }
+}
+
+impl From<UriError> for Error {
+    fn from(_: UriError) -> Self {
+        Error::Parse(ParseError::new(ParseErrorKind::Target, None))
+    }
+}
+
+impl From<CookieError> for Error {
+    fn from(_: CookieError) -> Self {
+        Error::Parse(ParseError::new(ParseErrorKind::Header(None), None))
+    }
+}
+
+///
+/// 部分的header太大属于超过限制，其余的都是格式错误
+///
+impl From<MultipartError> for Error {
+    fn from(error: MultipartError) -> Self {
+        match error {
+            MultipartError::HeaderTooLarge => Error::Limit(LimitError::HeaderSize),
+            error => Error::Parse(ParseError::new(ParseErrorKind::Multipart(error), None))
+        }
+    }
+}
+
+impl From<HTTPParseError> for Error {
+    fn from(error: HTTPParseError) -> Self {
+        let kind = match error {
+            HTTPParseError::InvalidStartLine => ParseErrorKind::StartLine,
+            HTTPParseError::InvalidMethod => ParseErrorKind::Method,
+            HTTPParseError::InvalidTarget => ParseErrorKind::Target,
+            HTTPParseError::InvalidStatus => ParseErrorKind::Status,
+            HTTPParseError::InvalidVersion => ParseErrorKind::Version,
+            HTTPParseError::InvalidHeader => ParseErrorKind::Header(None),
+            HTTPParseError::InvalidContentLength => ParseErrorKind::ContentLength,
+            HTTPParseError::InvalidChunk => ParseErrorKind::Chunk,
+            HTTPParseError::UnexpectedEof => ParseErrorKind::UnexpectedEof,
+            HTTPParseError::StartLineTooLong => return Error::Limit(LimitError::StartLine),
+            HTTPParseError::TooManyHeaders => return Error::Limit(LimitError::HeaderCount),
+            HTTPParseError::HeaderTooLarge => return Error::Limit(LimitError::HeaderSize),
+            HTTPParseError::BodyTooLarge => return Error::Limit(LimitError::BodySize),
+            HTTPParseError::ConflictingLength => return Error::Protocol(ProtocolError::ConflictingLength),
+            HTTPParseError::DuplicateContentLength => return Error::Protocol(ProtocolError::DuplicateContentLength),
+            HTTPParseError::UnsupportedTransferEncoding => return Error::Protocol(ProtocolError::UnsupportedTransferEncoding),
+            HTTPParseError::RepeatedChunked => return Error::Protocol(ProtocolError::RepeatedChunked),
+            HTTPParseError::MissingHost => return Error::Protocol(ProtocolError::MissingHost),
+            HTTPParseError::DuplicateHost => return Error::Protocol(ProtocolError::DuplicateHost)
+        };
+        Error::Parse(ParseError::new(kind, None))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::error::{Error, LimitError, ParseErrorKind, Result};
+    use crate::header::method::HTTPClientMethod;
+    use crate::multipart::{Multipart, MultipartError};
+    use crate::parser::client::HTTPClientParser;
+    use crate::parser::server::HTTPServerParser;
+    
+    fn method(raw: &str) -> Result<HTTPClientMethod> {
+        Ok(HTTPClientMethod::from(raw)?)
+    }
+    
+    fn multipart(body: &[u8], boundary: &str) -> Result<usize> {
+        let mut multipart = Multipart::new(body, boundary)?;
+        let mut count = 0;
+        while multipart.next_part()?.is_some() {
+            count += 1;
+        }
+        Ok(count)
+    }
+    
+    #[test]
+    fn convert() {
+        assert!(method("GET").is_ok());
+        assert!(method("FETCH").is_ok());
+        match method("FET CH").unwrap_err() {
+            Error::Parse(error) => assert_eq!(error.kind(), ParseErrorKind::Method),
+            error => panic!("{}", error)
+        }
+        
+        assert_eq!(multipart(b"--b\r\n\r\nvalue\r\n--b--\r\n", "b").unwrap(), 1);
+        match multipart(b"", "").unwrap_err() {
+            Error::Parse(error) => assert_eq!(error.kind(), ParseErrorKind::Multipart(MultipartError::InvalidBoundary)),
+            error => panic!("{}", error)
+        }
+    }
+    
+    #[test]
+    fn offset() {
+        let mut parser = HTTPClientParser::new();
+        let buf = b"GET / HTTP/1.1\r\nHost: x\r\nBad Name: y\r\n\r\n";
+        let error = parser.feed(buf)
+                          .map_err(|e| Error::parse_at(e, parser.offset()))
+                          .unwrap_err();
+        
+        match &error {
+            Error::Parse(parse) => assert_eq!(parse.offset(), Some(25)),
+            error => panic!("{}", error)
+        }
+        assert_eq!(error.status_code(), 400);
+        assert_eq!(error.to_string(), "parse error: invalid header field at byte 25");
+        
+        //起始行的错误指向出错的部分，由header决定的错误指向对应的header行
+        let offset = |buf: &[u8]| match HTTPClientParser::new().try_feed(buf).unwrap_err() {
+            Error::Parse(parse) => parse.offset().unwrap(),
+            error => panic!("{}", error)
+        };
+        assert_eq!(offset(b"GET / HTTQ/1.1\r\nHost: x\r\n\r\n"), 6);
+        assert_eq!(offset(b"GET /a b HTTP/1.1\r\n\r\n"), 0);
+        assert_eq!(offset(b"\r\nGET a{b HTTP/1.1\r\n\r\n"), 6);
+        
+        //长度冲突属于协议错误，位置由解析器给出
+        let offset = |buf: &[u8]| {
+            let mut parser = HTTPClientParser::new();
+            assert!(matches!(parser.try_feed(buf), Err(Error::Protocol(_))));
+            parser.offset()
+        };
+        assert_eq!(offset(b"POST / HTTP/1.1\r\nHost: x\r\nContent-Length: 3, 4\r\n\r\n"), 26);
+        assert_eq!(offset(b"POST / HTTP/1.1\r\nContent-Length: 3\r\nHost: x\r\nTransfer-Encoding: chunked\r\n\r\n"), 45);
+        assert_eq!(offset(b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\nTransfer-Encoding: gzip\r\n\r\n"), 45);
+        assert_eq!(offset(b"GET / HTTP/1.1\r\nHost: a\r\nHost: b\r\n\r\n"), 25);
+        
+        let mut parser = HTTPServerParser::new();
+        match parser.try_feed(b"HTTP/1.1 2000 OK\r\n\r\n").unwrap_err() {
+            Error::Parse(parse) => assert_eq!(parse.offset(), Some(9)),
+            error => panic!("{}", error)
+        }
+        
+        assert_eq!(Error::from(LimitError::HeaderSize).status_code(), 431);
+    }
+}
\ No newline at end of file
@@ -0,0 +1,153 @@
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::fmt;
+use std::fmt::{Debug, Formatter};
+
+trait AnyClone: Any + Send + Sync {
+    fn clone_box(&self) -> Box<dyn AnyClone>;
+    
+    fn as_any(&self) -> &dyn Any;
+    
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+    
+    fn into_any(self: Box<Self>) -> Box<dyn Any>;
+}
+
+impl<T> AnyClone for T
+    where
+        T: Any + Clone + Send + Sync
+{
+    fn clone_box(&self) -> Box<dyn AnyClone> {
+        Box::new(self.clone())
+    }
+    
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+    
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+}
+
+impl Clone for Box<dyn AnyClone> {
+    fn clone(&self) -> Self {
+        (**self).clone_box()
+    }
+}
+
+///
+/// 按照类型保存的附加数据，例如中间件解析出来的用户信息，不会被发送
+///
+/// 每个类型只保存一个值
+///
+#[derive(Clone, Default)]
+pub struct Extensions {
+    map: HashMap<TypeId, Box<dyn AnyClone>>
+}
+
+impl Extensions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    ///
+    /// 返回同类型的旧值
+    ///
+    pub fn insert<T>(&mut self, value: T) -> Option<T>
+        where
+            T: Any + Clone + Send + Sync
+    {
+        self.map
+            .insert(TypeId::of::<T>(), Box::new(value))
+            .and_then(|old| old.into_any().downcast().ok())
+            .map(|old| *old)
+    }
+    
+    pub fn get<T>(&self) -> Option<&T>
+        where
+            T: Any + Clone + Send + Sync
+    {
+        self.map
+            .get(&TypeId::of::<T>())
+            .and_then(|value| (**value).as_any().downcast_ref())
+    }
+    
+    pub fn get_mut<T>(&mut self) -> Option<&mut T>
+        where
+            T: Any + Clone + Send + Sync
+    {
+        self.map
+            .get_mut(&TypeId::of::<T>())
+            .and_then(|value| (**value).as_any_mut().downcast_mut())
+    }
+    
+    pub fn remove<T>(&mut self) -> Option<T>
+        where
+            T: Any + Clone + Send + Sync
+    {
+        self.map
+            .remove(&TypeId::of::<T>())
+            .and_then(|old| old.into_any().downcast().ok())
+            .map(|old| *old)
+    }
+    
+    pub fn contains<T>(&self) -> bool
+        where
+            T: Any + Clone + Send + Sync
+    {
+        self.map.contains_key(&TypeId::of::<T>())
+    }
+    
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+    
+    pub fn clear(&mut self) {
+        self.map.clear()
+    }
+}
+
+impl Debug for Extensions {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Extensions")
+         .field("len", &self.map.len())
+         .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::extensions::Extensions;
+    
+    #[derive(Clone, Debug, PartialEq)]
+    struct User(String);
+    
+    #[test]
+    fn typed() {
+        let mut extensions = Extensions::new();
+        assert_eq!(extensions.insert(User("a".to_string())), None);
+        assert_eq!(extensions.insert(7u32), None);
+        assert_eq!(extensions.insert(User("b".to_string())), Some(User("a".to_string())));
+        
+        if let Some(n) = extensions.get_mut::<u32>() {
+            *n += 1;
+        }
+        let copy = extensions.clone();
+        assert_eq!(copy.get::<u32>(), Some(&8));
+        assert_eq!(copy.get::<User>().unwrap().0, "b");
+        assert!(!copy.contains::<u64>());
+        
+        assert_eq!(extensions.remove::<u32>(), Some(8));
+        assert_eq!(extensions.len(), 1);
+        assert_eq!(copy.len(), 2);
+    }
+}
\ No newline at end of file
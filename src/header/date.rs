@@ -0,0 +1,346 @@
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const DAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+const LONG_DAYS: [&str; 7] = ["Monday", "Tuesday", "Wednesday", "Thursday", "Friday", "Saturday", "Sunday"];
+const MONTHS: [&str; 12] = ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+//9999-12-31 23:59:59，四位年份能表示的最晚时间，所有平台的SystemTime都能表示
+const MAX_SECS: u64 = 253402300799;
+
+///
+/// HTTP-date(RFC 9110 5.6.7)，精确到秒
+///
+/// 发送时总是使用IMF-fixdate，解析时也接受过时的RFC 850以及asctime格式
+///
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct HttpDate {
+    secs: u64
+}
+
+impl HttpDate {
+    pub fn now() -> Self {
+        HttpDate::from(SystemTime::now())
+    }
+    
+    pub fn from_unix(secs: u64) -> Self {
+        HttpDate {
+            secs
+        }
+    }
+    
+    pub fn unix(&self) -> u64 {
+        self.secs
+    }
+    
+    ///
+    /// SystemTime无法表示时返回9999-12-31 23:59:59
+    ///
+    pub fn system_time(&self) -> SystemTime {
+        UNIX_EPOCH.checked_add(Duration::from_secs(self.secs))
+                  .unwrap_or_else(|| UNIX_EPOCH + Duration::from_secs(MAX_SECS))
+    }
+    
+    pub fn parse(raw: &str) -> Option<Self> {
+        let raw = raw.trim();
+        Self::parse_imf_fixdate(raw)
+            .or_else(|| Self::parse_rfc850(raw))
+            .or_else(|| Self::parse_asctime(raw))
+    }
+    
+    ///
+    /// `Sun, 06 Nov 1994 08:49:37 GMT`
+    ///
+    fn parse_imf_fixdate(raw: &str) -> Option<Self> {
+        let (day_name, rest) = raw.split_once(", ")?;
+        DAYS.iter().position(|day| *day == day_name)?;
+        
+        let mut sp = rest.split(' ');
+        let day = two_digits(sp.next()?)?;
+        let month = month(sp.next()?)?;
+        let year = sp.next()?;
+        if year.len() != 4 {
+            return None
+        }
+        let year = year.parse().ok()?;
+        let time = time(sp.next()?)?;
+        if sp.next()? != "GMT" || sp.next().is_some() {
+            return None
+        }
+        
+        Self::from_parts(year, month, day, time)
+    }
+    
+    ///
+    /// `Sunday, 06-Nov-94 08:49:37 GMT`
+    ///
+    fn parse_rfc850(raw: &str) -> Option<Self> {
+        let (day_name, rest) = raw.split_once(", ")?;
+        LONG_DAYS.iter().position(|day| *day == day_name)?;
+        
+        let mut sp = rest.split(' ');
+        let mut date = sp.next()?.split('-');
+        let day = two_digits(date.next()?)?;
+        let month = month(date.next()?)?;
+        let year = two_digits(date.next()?)?;
+        //两位年份，70以后的是19xx
+        let year = if year >= 70 { 1900 + year } else { 2000 + year };
+        let time = time(sp.next()?)?;
+        if sp.next()? != "GMT" || sp.next().is_some() {
+            return None
+        }
+        
+        Self::from_parts(year, month, day, time)
+    }
+    
+    ///
+    /// `Sun Nov  6 08:49:37 1994`
+    ///
+    fn parse_asctime(raw: &str) -> Option<Self> {
+        let mut sp = raw.split_whitespace();
+        let day_name = sp.next()?;
+        DAYS.iter().position(|day| *day == day_name)?;
+        let month = month(sp.next()?)?;
+        let day = sp.next()?;
+        if day.is_empty() || day.len() > 2 {
+            return None
+        }
+        let day = day.parse().ok()?;
+        let time = time(sp.next()?)?;
+        let year = sp.next()?;
+        if year.len() != 4 || sp.next().is_some() {
+            return None
+        }
+        
+        Self::from_parts(year.parse().ok()?, month, day, time)
+    }
+    
+    ///
+    /// Set-Cookie的Expires使用的宽松格式(RFC 6265 5.1.1)，例如`Wed, 21-Oct-2015 07:28:00 GMT`
+    ///
+    /// 1970年之前的日期返回Unix纪元，对cookie来说同样是已经过期
+    ///
+    pub fn parse_cookie(raw: &str) -> Option<Self> {
+        let is_delimiter = |c: char| {
+            c == '\t' || (' '..='/').contains(&c) || (';'..='@').contains(&c) || ('['..='`').contains(&c) || ('{'..='~').contains(&c)
+        };
+        let (mut time, mut day, mut month, mut year) = (None, None, None, None);
+        for token in raw.split(is_delimiter).filter(|token| !token.is_empty()) {
+            if time.is_none() {
+                if let Some(secs) = cookie_time(token) {
+                    time = Some(secs);
+                    continue
+                }
+            }
+            let digits = leading_digits(token);
+            if day.is_none() && (1..=2).contains(&digits.len()) {
+                day = digits.parse::<u64>().ok();
+                continue
+            }
+            if month.is_none() {
+                let prefix = token.get(..3).unwrap_or_default();
+                if let Some(index) = MONTHS.iter().position(|month| prefix.eq_ignore_ascii_case(month)) {
+                    month = Some(index as u64 + 1);
+                    continue
+                }
+            }
+            if year.is_none() && (2..=4).contains(&digits.len()) {
+                year = digits.parse::<u64>().ok();
+            }
+        }
+        
+        let year = match year? {
+            year @ 70..=99 => year + 1900,
+            year @ 0..=69 => year + 2000,
+            year => year
+        };
+        let (day, month, time) = (day?, month?, time?);
+        if !(1..=31).contains(&day) || year < 1601 {
+            return None
+        }
+        if year < 1970 {
+            return Some(HttpDate::from_unix(0))
+        }
+        Self::from_parts(year, month, day, time)
+    }
+    
+    fn from_parts(year: u64, month: u64, day: u64, time: u64) -> Option<Self> {
+        if year < 1970 || day == 0 || day > days_in_month(year, month) {
+            return None
+        }
+        Some(HttpDate {
+            secs: days_from_civil(year, month, day) * 86400 + time
+        })
+    }
+}
+
+impl From<SystemTime> for HttpDate {
+    fn from(time: SystemTime) -> Self {
+        let secs = time.duration_since(UNIX_EPOCH)
+                       .map(|duration| duration.as_secs())
+                       .unwrap_or(0);
+        HttpDate {
+            secs
+        }
+    }
+}
+
+impl From<HttpDate> for SystemTime {
+    fn from(date: HttpDate) -> Self {
+        date.system_time()
+    }
+}
+
+impl Display for HttpDate {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let days = self.secs / 86400;
+        let time = self.secs % 86400;
+        let (year, month, day) = civil_from_days(days);
+        //1970-01-01是星期四
+        let weekday = DAYS[((days + 3) % 7) as usize];
+        
+        write!(
+            f,
+            "{}, {:02} {} {:04} {:02}:{:02}:{:02} GMT",
+            weekday,
+            day,
+            MONTHS[month as usize - 1],
+            year,
+            time / 3600,
+            time % 3600 / 60,
+            time % 60
+        )
+    }
+}
+
+fn two_digits(raw: &str) -> Option<u64> {
+    if raw.len() != 2 || !raw.bytes().all(|b| b.is_ascii_digit()) {
+        return None
+    }
+    raw.parse().ok()
+}
+
+fn month(raw: &str) -> Option<u64> {
+    MONTHS.iter()
+          .position(|month| *month == raw)
+          .map(|index| index as u64 + 1)
+}
+
+///
+/// `08:49:37`，返回当天的秒数
+///
+fn time(raw: &str) -> Option<u64> {
+    let mut sp = raw.split(':');
+    let hour = two_digits(sp.next()?)?;
+    let minute = two_digits(sp.next()?)?;
+    let second = two_digits(sp.next()?)?;
+    //允许闰秒
+    if sp.next().is_some() || hour > 23 || minute > 59 || second > 60 {
+        return None
+    }
+    Some(hour * 3600 + minute * 60 + second)
+}
+
+///
+/// cookie-date中的`hh:mm:ss`，每个部分一到两位数字，后面可以跟着非数字字符
+///
+fn cookie_time(token: &str) -> Option<u64> {
+    let mut sp = token.splitn(3, ':');
+    let mut parts = [0u64; 3];
+    for (index, part) in parts.iter_mut().enumerate() {
+        let raw = sp.next()?;
+        //最后一部分只看开头的数字
+        let raw = if index == 2 { leading_digits(raw) } else { raw };
+        if raw.is_empty() || raw.len() > 2 || !raw.bytes().all(|b| b.is_ascii_digit()) {
+            return None
+        }
+        *part = raw.parse().ok()?;
+    }
+    let [hour, minute, second] = parts;
+    if hour > 23 || minute > 59 || second > 59 {
+        return None
+    }
+    Some(hour * 3600 + minute * 60 + second)
+}
+
+fn leading_digits(token: &str) -> &str {
+    let end = token.find(|c: char| !c.is_ascii_digit()).unwrap_or(token.len());
+    &token[..end]
+}
+
+fn is_leap_year(year: u64) -> bool {
+    (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+}
+
+fn days_in_month(year: u64, month: u64) -> u64 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31
+    }
+}
+
+///
+/// 1970-01-01以来的天数
+///
+pub(crate) fn days_from_civil(year: u64, month: u64, day: u64) -> u64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year / 400;
+    let year_of_era = year - era * 400;
+    let month = if month > 2 { month - 3 } else { month + 9 };
+    let day_of_year = (153 * month + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+fn civil_from_days(days: u64) -> (u64, u64, u64) {
+    let days = days + 719468;
+    let era = days / 146097;
+    let day_of_era = days - era * 146097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month + 2) / 5 + 1;
+    let month = if month < 10 { month + 3 } else { month - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::header::date::HttpDate;
+    
+    #[test]
+    fn parse() {
+        let date = HttpDate::parse("Sun, 06 Nov 1994 08:49:37 GMT").unwrap();
+        assert_eq!(date.unix(), 784111777);
+        assert_eq!(HttpDate::parse("Sunday, 06-Nov-94 08:49:37 GMT"), Some(date));
+        assert_eq!(HttpDate::parse("Sun Nov  6 08:49:37 1994"), Some(date));
+        assert_eq!(date.to_string(), "Sun, 06 Nov 1994 08:49:37 GMT");
+        
+        assert_eq!(HttpDate::from_unix(951782400).to_string(), "Tue, 29 Feb 2000 00:00:00 GMT");
+        assert!(HttpDate::parse("Sun, 31 Feb 1994 08:49:37 GMT").is_none());
+        assert!(HttpDate::parse("Sun, 06 Nov 1994 08:49:37 UTC").is_none());
+    }
+    
+    #[test]
+    fn system_time() {
+        let date = HttpDate::parse("Fri, 31 Dec 9999 23:59:59 GMT").unwrap();
+        assert_eq!(HttpDate::from(date.system_time()), date);
+        //不会溢出
+        let max = HttpDate::from_unix(u64::MAX).system_time();
+        assert!(max >= date.system_time());
+    }
+    
+    #[test]
+    fn cookie_date() {
+        let date = HttpDate::parse("Wed, 21 Oct 2015 07:28:00 GMT").unwrap();
+        assert_eq!(HttpDate::parse_cookie("Wed, 21 Oct 2015 07:28:00 GMT"), Some(date));
+        assert_eq!(HttpDate::parse_cookie("Wed, 21-Oct-15 07:28:00 GMT"), Some(date));
+        assert_eq!(HttpDate::parse_cookie("21 october 2015 7:28:0"), Some(date));
+        assert_eq!(HttpDate::parse_cookie("Thu, 01 Jan 1900 00:00:00 GMT"), Some(HttpDate::from_unix(0)));
+        assert!(HttpDate::parse_cookie("Wed, 32 Oct 2015 07:28:00 GMT").is_none());
+        assert!(HttpDate::parse_cookie("next week").is_none());
+    }
+}
@@ -1,3 +1,10 @@
+use std::borrow::Cow;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+use crate::error::ProtocolError;
+use crate::header::status::StatusCode;
+use crate::map::{is_field_value_char, is_tchar};
 use crate::prelude::HTTPBytes;
 
 #[derive(Copy, Clone, Debug)]
@@ -5,7 +12,14 @@ pub enum HTTPMethodMatchError {
     NoMatch
 }
 
-#[derive(Copy, Clone, Debug)]
+pub type ClientMethodString = String;
+
+///
+/// 请求方法，区分大小写(RFC 9110 9.1)
+///
+/// 没有对应变体的token都是`Extension`，例如WebDAV的`PROPFIND`或者`PURGE`
+///
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum HTTPClientMethod {
     GET,
     POST,
@@ -13,27 +27,96 @@ pub enum HTTPClientMethod {
     DELETE,
     HEAD,
     OPTIONS,
-    TRACE
+    TRACE,
+    PATCH,
+    CONNECT,
+    Extension(ClientMethodString)
 }
 
+//IANA HTTP Method Registry中的扩展方法：(方法, safe, idempotent)
+const EXTENSION_METHODS: &[(&str, bool, bool)] = &[
+    ("ACL", false, true),
+    ("BASELINE-CONTROL", false, true),
+    ("BIND", false, true),
+    ("CHECKIN", false, true),
+    ("CHECKOUT", false, true),
+    ("COPY", false, true),
+    ("LABEL", false, true),
+    ("LINK", false, true),
+    ("LOCK", false, false),
+    ("MERGE", false, true),
+    ("MKACTIVITY", false, true),
+    ("MKCALENDAR", false, true),
+    ("MKCOL", false, true),
+    ("MKREDIRECTREF", false, true),
+    ("MKWORKSPACE", false, true),
+    ("MOVE", false, true),
+    ("ORDERPATCH", false, true),
+    ("PROPFIND", true, true),
+    ("PROPPATCH", false, true),
+    ("REBIND", false, true),
+    ("REPORT", true, true),
+    ("SEARCH", true, true),
+    ("UNBIND", false, true),
+    ("UNCHECKOUT", false, true),
+    ("UNLINK", false, true),
+    ("UNLOCK", false, true),
+    ("UPDATE", false, true),
+    ("UPDATEREDIRECTREF", false, true),
+    ("VERSION-CONTROL", false, true)
+];
+
 #[allow(clippy::from_over_into)]
 impl Into<String> for HTTPClientMethod {
     fn into(self) -> String {
         match self {
-            HTTPClientMethod::GET => String::from("GET"),
-            HTTPClientMethod::POST => String::from("POST"),
-            HTTPClientMethod::PUT => String::from("PUT"),
-            HTTPClientMethod::DELETE => String::from("DELETE"),
-            HTTPClientMethod::HEAD => String::from("HEAD"),
-            HTTPClientMethod::OPTIONS => String::from("OPTIONS"),
-            HTTPClientMethod::TRACE => String::from("TRACE")
+            HTTPClientMethod::Extension(method) => method,
+            method => method.as_str().to_string()
         }
     }
 }
 
-#[allow(clippy::from_over_into)]
-impl<'a> Into<&'a str> for HTTPClientMethod {
-    fn into(self) -> &'a str {
+impl HTTPClientMethod {
+    ///
+    /// 标准方法返回对应的变体，其他token返回`Extension`，不是token时返回`NoMatch`
+    ///
+    pub fn from<T>(t: T) -> Result<Self, HTTPMethodMatchError>
+        where
+            T: HTTPBytes
+    {
+        Self::extension(t.string())
+    }
+    
+    fn standard(method: &str) -> Option<Self> {
+        match method {
+            "GET" => Some(HTTPClientMethod::GET),
+            "POST" => Some(HTTPClientMethod::POST),
+            "PUT" => Some(HTTPClientMethod::PUT),
+            "DELETE" => Some(HTTPClientMethod::DELETE),
+            "HEAD" => Some(HTTPClientMethod::HEAD),
+            "OPTIONS" => Some(HTTPClientMethod::OPTIONS),
+            "TRACE" => Some(HTTPClientMethod::TRACE),
+            "PATCH" => Some(HTTPClientMethod::PATCH),
+            "CONNECT" => Some(HTTPClientMethod::CONNECT),
+            _ => None
+        }
+    }
+    
+    ///
+    /// 检查之后的`Extension`，和`from`一样标准方法返回对应的变体
+    ///
+    pub fn extension<T>(method: T) -> Result<Self, HTTPMethodMatchError>
+        where
+            T: ToString
+    {
+        let method = method.to_string();
+        if method.is_empty() || !method.bytes().all(is_tchar) {
+            return Err(HTTPMethodMatchError::NoMatch)
+        }
+        Ok(Self::standard(&method).unwrap_or(HTTPClientMethod::Extension(method)))
+    }
+    
+    pub fn as_str(&self) -> &str {
         match self {
             HTTPClientMethod::GET => "GET",
             HTTPClientMethod::POST => "POST",
@@ -41,27 +124,59 @@ impl<'a> Into<&'a str> for HTTPClientMethod {
             HTTPClientMethod::DELETE => "DELETE",
             HTTPClientMethod::HEAD => "HEAD",
             HTTPClientMethod::OPTIONS => "OPTIONS",
-            HTTPClientMethod::TRACE => "TRACE"
+            HTTPClientMethod::TRACE => "TRACE",
+            HTTPClientMethod::PATCH => "PATCH",
+            HTTPClientMethod::CONNECT => "CONNECT",
+            HTTPClientMethod::Extension(method) => method
         }
     }
-}
-
-impl HTTPClientMethod {
-    pub fn from<T>(t: T) -> Result<Self, HTTPMethodMatchError>
-        where
-            T: HTTPBytes
-    {
-        match t.string().as_str() {
-            "GET" => Ok(HTTPClientMethod::GET),
-            "POST" => Ok(HTTPClientMethod::POST),
-            "PUT" => Ok(HTTPClientMethod::PUT),
-            "DELETE" => Ok(HTTPClientMethod::DELETE),
-            "HEAD" => Ok(HTTPClientMethod::HEAD),
-            "OPTIONS" => Ok(HTTPClientMethod::OPTIONS),
-            "TRACE" => Ok(HTTPClientMethod::TRACE),
-            _ => Err(HTTPMethodMatchError::NoMatch)
+    
+    ///
+    /// 发送之前检查直接构造的`Extension`
+    ///
+    pub fn validate(&self) -> Result<(), ProtocolError> {
+        match self {
+            HTTPClientMethod::Extension(method) if method.is_empty() || !method.bytes().all(is_tchar) => {
+                Err(ProtocolError::InvalidMethod)
+            }
+            _ => Ok(())
         }
     }
+    
+    fn registered(&self) -> Option<(bool, bool)> {
+        EXTENSION_METHODS.iter()
+                         .find(|(method, _, _)| *method == self.as_str())
+                         .map(|(_, safe, idempotent)| (*safe, *idempotent))
+    }
+    
+    ///
+    /// 只读的方法(RFC 9110 9.2.1)，未注册的扩展方法不是safe
+    ///
+    pub fn is_safe(&self) -> bool {
+        match self {
+            HTTPClientMethod::GET | HTTPClientMethod::HEAD | HTTPClientMethod::OPTIONS | HTTPClientMethod::TRACE => true,
+            HTTPClientMethod::Extension(_) => self.registered().map(|(safe, _)| safe).unwrap_or(false),
+            _ => false
+        }
+    }
+    
+    ///
+    /// 重复发送和发送一次效果相同的方法(RFC 9110 9.2.2)，可以自动重试
+    ///
+    pub fn is_idempotent(&self) -> bool {
+        match self {
+            HTTPClientMethod::PUT | HTTPClientMethod::DELETE => true,
+            HTTPClientMethod::Extension(_) => self.registered().map(|(_, idempotent)| idempotent).unwrap_or(false),
+            method => method.is_safe()
+        }
+    }
+    
+    ///
+    /// 响应可以被缓存的方法(RFC 9110 9.2.3)，POST的响应需要明确的新鲜度信息才能缓存
+    ///
+    pub fn is_cacheable(&self) -> bool {
+        matches!(self, HTTPClientMethod::GET | HTTPClientMethod::HEAD | HTTPClientMethod::POST)
+    }
 }
 
 pub type ServerMethodString = String;
@@ -97,24 +212,6 @@ impl Into<String> for HTTPServerMethod {
     }
 }
 
-#[allow(clippy::from_over_into)]
-impl<'a> Into<&'a str> for HTTPServerMethod {
-    fn into(self) -> &'a str {
-        match self {
-            HTTPServerMethod::OK => "200 OK",
-            HTTPServerMethod::Created => "201 Created",
-            HTTPServerMethod::Accepted => "202 Accepted",
-            HTTPServerMethod::BadRequest => "400 Bad Request",
-            HTTPServerMethod::Unauthorized => "401 Unauthorized",
-            HTTPServerMethod::Forbidden => "403 Forbidden",
-            HTTPServerMethod::NotFound => "404 Not Found",
-            HTTPServerMethod::InternalServerError => "500 Internal Server Error",
-            HTTPServerMethod::Other(code, method) => format!("{} {}", code, method)
-                .leak()
-        }
-    }
-}
-
 impl HTTPServerMethod {
     pub fn from<T>(t: T) -> Result<Self, HTTPMethodMatchError>
         where
@@ -132,49 +229,167 @@ impl HTTPServerMethod {
             _ => Err(HTTPMethodMatchError::NoMatch)
         }
     }
+    
+    ///
+    /// 按照状态码匹配，没有对应的变体时返回Other(code, reason)
+    ///
+    pub fn from_code<T>(code: ServerMethodCode, reason: T) -> Self
+        where
+            T: ToString
+    {
+        match code {
+            200 => HTTPServerMethod::OK,
+            201 => HTTPServerMethod::Created,
+            202 => HTTPServerMethod::Accepted,
+            400 => HTTPServerMethod::BadRequest,
+            401 => HTTPServerMethod::Unauthorized,
+            403 => HTTPServerMethod::Forbidden,
+            404 => HTTPServerMethod::NotFound,
+            500 => HTTPServerMethod::InternalServerError,
+            _ => HTTPServerMethod::Other(code, reason.to_string())
+        }
+    }
+    
+    ///
+    /// 检查之后的`Other`，状态码必须是三位数，原因短语不能包含CR、LF等控制字符
+    ///
+    pub fn other<T>(code: ServerMethodCode, reason: T) -> Result<Self, ProtocolError>
+        where
+            T: ToString
+    {
+        let method = HTTPServerMethod::Other(code, reason.to_string());
+        method.validate()?;
+        Ok(method)
+    }
+    
+    ///
+    /// 发送之前检查状态行是否会被破坏
+    ///
+    pub fn validate(&self) -> Result<(), ProtocolError> {
+        match self {
+            HTTPServerMethod::Other(code, _) if !(100..1000).contains(code) => Err(ProtocolError::InvalidStatus),
+            HTTPServerMethod::Other(_, reason) if !reason.bytes().all(is_field_value_char) => Err(ProtocolError::InvalidReason),
+            _ => Ok(())
+        }
+    }
+    
+    ///
+    /// 状态行中的原因短语，借用自身，不分配内存
+    ///
+    pub fn reason(&self) -> &str {
+        match self {
+            HTTPServerMethod::Other(_, reason) => reason,
+            method => method.status()
+                            .and_then(|status| status.canonical_reason())
+                            .unwrap_or_default()
+        }
+    }
+    
+    ///
+    /// 状态码不在100到999之间时返回None
+    ///
+    pub fn status(&self) -> Option<StatusCode> {
+        u16::try_from(self.code())
+            .ok()
+            .and_then(StatusCode::from_u16)
+    }
+    
+    pub fn code(&self) -> ServerMethodCode {
+        match self {
+            HTTPServerMethod::OK => 200,
+            HTTPServerMethod::Created => 201,
+            HTTPServerMethod::Accepted => 202,
+            HTTPServerMethod::BadRequest => 400,
+            HTTPServerMethod::Unauthorized => 401,
+            HTTPServerMethod::Forbidden => 403,
+            HTTPServerMethod::NotFound => 404,
+            HTTPServerMethod::InternalServerError => 500,
+            HTTPServerMethod::Other(code, _) => *code
+        }
+    }
+}
+
+///
+/// 按照状态码选择变体，原因短语使用标准的短语
+///
+impl From<StatusCode> for HTTPServerMethod {
+    fn from(status: StatusCode) -> Self {
+        HTTPServerMethod::from_code(
+            status.as_u16() as ServerMethodCode,
+            status.canonical_reason().unwrap_or_default()
+        )
+    }
 }
 
 #[allow(clippy::all)]
 pub trait HTTPMethodMessage {
-    fn as_bytes(&self) -> &[u8];
+    ///
+    /// 起始行中的片段，自定义的内容需要拼接时返回新分配的字节
+    ///
+    fn as_bytes(&self) -> Cow<'_, [u8]>;
 }
 
 impl HTTPMethodMessage for HTTPClientMethod {
-    fn as_bytes(&self) -> &[u8] {
-        let msg: &str = (*self).into();
-        msg.as_bytes()
+    fn as_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Borrowed(self.as_str().as_bytes())
     }
 }
 
 impl HTTPMethodMessage for HTTPServerMethod {
-    fn as_bytes(&self) -> &[u8] {
-        let msg: &str = self.clone().into();
-        msg.as_bytes()
+    fn as_bytes(&self) -> Cow<'_, [u8]> {
+        //固定的变体直接借用静态的状态行片段，只有Other需要拼接
+        let bytes: &'static [u8] = match self {
+            HTTPServerMethod::OK => b"200 OK",
+            HTTPServerMethod::Created => b"201 Created",
+            HTTPServerMethod::Accepted => b"202 Accepted",
+            HTTPServerMethod::BadRequest => b"400 Bad Request",
+            HTTPServerMethod::Unauthorized => b"401 Unauthorized",
+            HTTPServerMethod::Forbidden => b"403 Forbidden",
+            HTTPServerMethod::NotFound => b"404 Not Found",
+            HTTPServerMethod::InternalServerError => b"500 Internal Server Error",
+            HTTPServerMethod::Other(..) => return Cow::Owned(self.to_string().into_bytes())
+        };
+        Cow::Borrowed(bytes)
     }
 }
 
-impl ToString for HTTPServerMethod {
-    fn to_string(&self) -> String {
-        (*self).clone().into()
+impl Display for HTTPServerMethod {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.code(), self.reason())
     }
 }
 
-impl ToString for HTTPClientMethod {
-    fn to_string(&self) -> String {
-        (*self).into()
+impl Display for HTTPClientMethod {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
     }
 }
 
 #[cfg(test)]
 mod server_method_test {
+    use std::borrow::Cow;
+    
+    use crate::error::ProtocolError;
     use crate::header::method::{HTTPMethodMessage, HTTPServerMethod};
+    use crate::header::status::StatusCode;
     
     #[test]
     fn leak_test() {
         let user_method = HTTPServerMethod::Other(200, "OK".to_string());
-        let s: &str = user_method.into();
+        let s = user_method.to_string();
         
-        assert_eq!(s, "200 OK")
+        assert_eq!(s, "200 OK");
+        assert_eq!(user_method.reason(), "OK");
+    }
+    
+    #[test]
+    fn status_test() {
+        let method: HTTPServerMethod = StatusCode::NOT_FOUND.into();
+        assert_eq!(method.to_string(), "404 Not Found");
+        let method: HTTPServerMethod = StatusCode::IM_USED.into();
+        assert_eq!(method.reason(), "IM Used");
+        assert_eq!(HTTPServerMethod::Other(5000, String::new()).status(), None);
+        assert!(HTTPServerMethod::BadRequest.status().unwrap().is_client_error());
     }
     
     #[test]
@@ -183,14 +398,38 @@ mod server_method_test {
         assert_eq!("200 OK".to_string(), ok_method)
     }
     
+    #[test]
+    fn checked_test() {
+        assert!(HTTPServerMethod::other(299, "Custom\tReason").is_ok());
+        assert_eq!(HTTPServerMethod::other(200, "OK\r\nSet-Cookie: a=1").unwrap_err(), ProtocolError::InvalidReason);
+        assert_eq!(HTTPServerMethod::other(20, "OK").unwrap_err(), ProtocolError::InvalidStatus);
+        assert!(HTTPServerMethod::Other(200, "\n".to_string()).validate().is_err());
+    }
+    
     #[test]
     fn byte_test() {
         let bytes = HTTPServerMethod::OK.as_bytes();
-        assert_eq!("200 OK".as_bytes(), bytes);
+        assert_eq!("200 OK".as_bytes(), &*bytes);
+        
+        let fixed = [
+            HTTPServerMethod::OK,
+            HTTPServerMethod::Created,
+            HTTPServerMethod::Accepted,
+            HTTPServerMethod::BadRequest,
+            HTTPServerMethod::Unauthorized,
+            HTTPServerMethod::Forbidden,
+            HTTPServerMethod::NotFound,
+            HTTPServerMethod::InternalServerError
+        ];
+        for method in fixed {
+            let bytes = method.as_bytes();
+            assert!(matches!(bytes, Cow::Borrowed(_)));
+            assert_eq!(&*bytes, method.to_string().as_bytes());
+        }
         
         let method = HTTPServerMethod::Other(200, "OK".to_string());
         let bytes = method.as_bytes();
-        assert_eq!("200 OK".as_bytes(), bytes);
+        assert_eq!("200 OK".as_bytes(), &*bytes);
     }
 }
 
@@ -207,6 +446,33 @@ mod client_method_test {
     #[test]
     fn byte_test() {
         let bytes = HTTPClientMethod::GET.as_bytes();
-        assert_eq!("GET".as_bytes(), bytes);
+        assert_eq!("GET".as_bytes(), &*bytes);
+    }
+    
+    #[test]
+    fn extension_test() {
+        let purge = HTTPClientMethod::from("PURGE").unwrap();
+        assert_eq!(purge, HTTPClientMethod::Extension("PURGE".to_string()));
+        assert_eq!(purge.to_string(), "PURGE");
+        assert_eq!(HTTPClientMethod::extension("PATCH").unwrap(), HTTPClientMethod::PATCH);
+        assert!(HTTPClientMethod::from("GET /").is_err());
+        assert!(HTTPClientMethod::extension("").is_err());
+        assert!(HTTPClientMethod::Extension("A\r\nB".to_string()).validate().is_err());
+        //方法区分大小写
+        assert!(matches!(HTTPClientMethod::from("get").unwrap(), HTTPClientMethod::Extension(_)));
+    }
+    
+    #[test]
+    fn property_test() {
+        assert!(HTTPClientMethod::GET.is_safe() && HTTPClientMethod::GET.is_cacheable());
+        assert!(!HTTPClientMethod::POST.is_idempotent() && HTTPClientMethod::POST.is_cacheable());
+        assert!(HTTPClientMethod::PUT.is_idempotent() && !HTTPClientMethod::PUT.is_safe());
+        assert!(!HTTPClientMethod::PATCH.is_idempotent());
+        assert!(!HTTPClientMethod::CONNECT.is_safe() && !HTTPClientMethod::CONNECT.is_cacheable());
+        
+        let propfind = HTTPClientMethod::from("PROPFIND").unwrap();
+        assert!(propfind.is_safe() && propfind.is_idempotent());
+        assert!(!HTTPClientMethod::from("LOCK").unwrap().is_idempotent());
+        assert!(!HTTPClientMethod::from("PURGE").unwrap().is_idempotent());
     }
 }
\ No newline at end of file
@@ -6,6 +6,10 @@ pub use method::HTTPMethodMatchError;
 pub use prelude::*;
 pub use version::HTTPVersionMatchError;
 
+pub mod date;
 pub mod method;
+pub mod name;
 pub mod version;
-pub mod prelude;
\ No newline at end of file
+pub mod prelude;
+pub mod status;
+pub mod typed;
\ No newline at end of file
@@ -0,0 +1,261 @@
+use std::fmt;
+use std::fmt::{Debug, Display, Formatter};
+use std::hash::{Hash, Hasher};
+
+use crate::map::{HeaderMappingError, HeaderMappingResult, is_tchar};
+
+#[derive(Clone)]
+enum Repr {
+    Static(&'static str),
+    Owned(String)
+}
+
+///
+/// header名，比较和哈希都不区分大小写，保留原本的写法用于发送
+///
+#[derive(Clone)]
+pub struct HeaderName {
+    inner: Repr
+}
+
+macro_rules! standard_headers {
+    ($($(#[$doc:meta])* $constant:ident => $name:expr;)+) => {
+        impl HeaderName {
+            $(
+                $(#[$doc])*
+                pub const $constant: HeaderName = HeaderName::from_static($name);
+            )+
+        }
+        
+        #[cfg(test)]
+        const STANDARD_HEADERS: &[HeaderName] = &[$(HeaderName::$constant),+];
+    }
+}
+
+standard_headers! {
+    ACCEPT => "Accept";
+    ACCEPT_CHARSET => "Accept-Charset";
+    ACCEPT_ENCODING => "Accept-Encoding";
+    ACCEPT_LANGUAGE => "Accept-Language";
+    ACCEPT_RANGES => "Accept-Ranges";
+    ACCESS_CONTROL_ALLOW_CREDENTIALS => "Access-Control-Allow-Credentials";
+    ACCESS_CONTROL_ALLOW_HEADERS => "Access-Control-Allow-Headers";
+    ACCESS_CONTROL_ALLOW_METHODS => "Access-Control-Allow-Methods";
+    ACCESS_CONTROL_ALLOW_ORIGIN => "Access-Control-Allow-Origin";
+    ACCESS_CONTROL_EXPOSE_HEADERS => "Access-Control-Expose-Headers";
+    ACCESS_CONTROL_MAX_AGE => "Access-Control-Max-Age";
+    ACCESS_CONTROL_REQUEST_HEADERS => "Access-Control-Request-Headers";
+    ACCESS_CONTROL_REQUEST_METHOD => "Access-Control-Request-Method";
+    AGE => "Age";
+    ALLOW => "Allow";
+    ALT_SVC => "Alt-Svc";
+    AUTHORIZATION => "Authorization";
+    CACHE_CONTROL => "Cache-Control";
+    CONNECTION => "Connection";
+    CONTENT_DISPOSITION => "Content-Disposition";
+    CONTENT_ENCODING => "Content-Encoding";
+    CONTENT_LANGUAGE => "Content-Language";
+    CONTENT_LENGTH => "Content-Length";
+    CONTENT_LOCATION => "Content-Location";
+    CONTENT_RANGE => "Content-Range";
+    CONTENT_SECURITY_POLICY => "Content-Security-Policy";
+    CONTENT_TYPE => "Content-Type";
+    COOKIE => "Cookie";
+    DATE => "Date";
+    ETAG => "ETag";
+    EXPECT => "Expect";
+    EXPIRES => "Expires";
+    FORWARDED => "Forwarded";
+    FROM => "From";
+    HOST => "Host";
+    IF_MATCH => "If-Match";
+    IF_MODIFIED_SINCE => "If-Modified-Since";
+    IF_NONE_MATCH => "If-None-Match";
+    IF_RANGE => "If-Range";
+    IF_UNMODIFIED_SINCE => "If-Unmodified-Since";
+    KEEP_ALIVE => "Keep-Alive";
+    LAST_MODIFIED => "Last-Modified";
+    LINK => "Link";
+    LOCATION => "Location";
+    MAX_FORWARDS => "Max-Forwards";
+    ORIGIN => "Origin";
+    PRAGMA => "Pragma";
+    PROXY_AUTHENTICATE => "Proxy-Authenticate";
+    PROXY_AUTHORIZATION => "Proxy-Authorization";
+    RANGE => "Range";
+    REFERER => "Referer";
+    REFERRER_POLICY => "Referrer-Policy";
+    RETRY_AFTER => "Retry-After";
+    SEC_WEBSOCKET_ACCEPT => "Sec-WebSocket-Accept";
+    SEC_WEBSOCKET_EXTENSIONS => "Sec-WebSocket-Extensions";
+    SEC_WEBSOCKET_KEY => "Sec-WebSocket-Key";
+    SEC_WEBSOCKET_PROTOCOL => "Sec-WebSocket-Protocol";
+    SEC_WEBSOCKET_VERSION => "Sec-WebSocket-Version";
+    SERVER => "Server";
+    SET_COOKIE => "Set-Cookie";
+    STRICT_TRANSPORT_SECURITY => "Strict-Transport-Security";
+    TE => "TE";
+    TRAILER => "Trailer";
+    TRANSFER_ENCODING => "Transfer-Encoding";
+    UPGRADE => "Upgrade";
+    USER_AGENT => "User-Agent";
+    VARY => "Vary";
+    VIA => "Via";
+    WWW_AUTHENTICATE => "WWW-Authenticate";
+    X_CONTENT_TYPE_OPTIONS => "X-Content-Type-Options";
+    X_FRAME_OPTIONS => "X-Frame-Options";
+}
+
+impl HeaderName {
+    ///
+    /// 编译期的header名，不是token时编译失败
+    ///
+    pub const fn from_static(name: &'static str) -> Self {
+        let bytes = name.as_bytes();
+        if bytes.is_empty() {
+            panic!("header name is empty")
+        }
+        let mut index = 0;
+        while index < bytes.len() {
+            let b = bytes[index];
+            if !(b.is_ascii_alphanumeric() || matches!(b, b'!' | b'#' | b'$' | b'%' | b'&' | b'\'' | b'*' | b'+' | b'-' | b'.' | b'^' | b'_' | b'`' | b'|' | b'~')) {
+                panic!("header name is not a token")
+            }
+            index += 1;
+        }
+        
+        HeaderName {
+            inner: Repr::Static(name)
+        }
+    }
+    
+    ///
+    /// 运行时检查的header名，不是token时返回`InvalidName`
+    ///
+    pub fn try_from_str(name: &str) -> HeaderMappingResult<Self> {
+        if name.is_empty() || !name.bytes().all(is_tchar) {
+            return Err(HeaderMappingError::InvalidName)
+        }
+        Ok(HeaderName::from(name))
+    }
+    
+    pub fn as_str(&self) -> &str {
+        match &self.inner {
+            Repr::Static(name) => name,
+            Repr::Owned(name) => name
+        }
+    }
+}
+
+impl From<String> for HeaderName {
+    fn from(name: String) -> Self {
+        HeaderName {
+            inner: Repr::Owned(name)
+        }
+    }
+}
+
+impl From<&str> for HeaderName {
+    fn from(name: &str) -> Self {
+        HeaderName::from(name.to_string())
+    }
+}
+
+impl From<&HeaderName> for HeaderName {
+    fn from(name: &HeaderName) -> Self {
+        name.clone()
+    }
+}
+
+#[allow(clippy::from_over_into)]
+impl Into<String> for HeaderName {
+    fn into(self) -> String {
+        match self.inner {
+            Repr::Static(name) => name.to_string(),
+            Repr::Owned(name) => name
+        }
+    }
+}
+
+impl AsRef<str> for HeaderName {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl PartialEq for HeaderName {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_str().eq_ignore_ascii_case(other.as_str())
+    }
+}
+
+impl Eq for HeaderName {}
+
+impl PartialEq<str> for HeaderName {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str().eq_ignore_ascii_case(other)
+    }
+}
+
+impl PartialEq<&str> for HeaderName {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str().eq_ignore_ascii_case(other)
+    }
+}
+
+impl PartialEq<String> for HeaderName {
+    fn eq(&self, other: &String) -> bool {
+        self.as_str().eq_ignore_ascii_case(other)
+    }
+}
+
+impl PartialEq<HeaderName> for &str {
+    fn eq(&self, other: &HeaderName) -> bool {
+        other == self
+    }
+}
+
+impl Hash for HeaderName {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        for b in self.as_str().bytes() {
+            state.write_u8(b.to_ascii_lowercase());
+        }
+        state.write_u8(0xff);
+    }
+}
+
+impl Display for HeaderName {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Debug for HeaderName {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        Debug::fmt(self.as_str(), f)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+    
+    use crate::header::name::{HeaderName, STANDARD_HEADERS};
+    
+    #[test]
+    fn case_insensitive() {
+        assert_eq!(HeaderName::CONTENT_LENGTH, HeaderName::from("content-length"));
+        assert_eq!(HeaderName::from("CONTENT-TYPE"), "Content-Type");
+        assert_eq!(HeaderName::from("x-custom").to_string(), "x-custom");
+        
+        let mut set = HashSet::new();
+        set.insert(HeaderName::from("Set-Cookie"));
+        assert!(set.contains(&HeaderName::SET_COOKIE));
+    }
+    
+    #[test]
+    fn standard() {
+        let set = STANDARD_HEADERS.iter().cloned().collect::<HashSet<_>>();
+        assert_eq!(set.len(), STANDARD_HEADERS.len());
+    }
+}
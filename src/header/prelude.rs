@@ -1,2 +1,4 @@
 pub use super::method::HTTPMethodMessage;
-
+pub use super::name::HeaderName;
+pub use super::typed::TypedHeader;
+pub use super::status::StatusCode;
\ No newline at end of file
@@ -0,0 +1,213 @@
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+///
+/// 三位数的状态码，`Display`输出`404 Not Found`这样的状态行片段，不分配内存
+///
+/// 常量覆盖IANA HTTP Status Code Registry中已经分配的状态码
+///
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct StatusCode(u16);
+
+macro_rules! status_codes {
+    ($($(#[$doc:meta])* $constant:ident => $code:expr, $reason:expr;)+) => {
+        impl StatusCode {
+            $(
+                $(#[$doc])*
+                pub const $constant: StatusCode = StatusCode($code);
+            )+
+            
+            ///
+            /// 标准的原因短语，未分配的状态码返回None
+            ///
+            pub fn canonical_reason(&self) -> Option<&'static str> {
+                match self.0 {
+                    $($code => Some($reason),)+
+                    _ => None
+                }
+            }
+        }
+        
+        #[cfg(test)]
+        const STATUS_CODES: &[StatusCode] = &[$(StatusCode::$constant),+];
+    }
+}
+
+status_codes! {
+    CONTINUE => 100, "Continue";
+    SWITCHING_PROTOCOLS => 101, "Switching Protocols";
+    PROCESSING => 102, "Processing";
+    EARLY_HINTS => 103, "Early Hints";
+    
+    OK => 200, "OK";
+    CREATED => 201, "Created";
+    ACCEPTED => 202, "Accepted";
+    NON_AUTHORITATIVE_INFORMATION => 203, "Non-Authoritative Information";
+    NO_CONTENT => 204, "No Content";
+    RESET_CONTENT => 205, "Reset Content";
+    PARTIAL_CONTENT => 206, "Partial Content";
+    MULTI_STATUS => 207, "Multi-Status";
+    ALREADY_REPORTED => 208, "Already Reported";
+    IM_USED => 226, "IM Used";
+    
+    MULTIPLE_CHOICES => 300, "Multiple Choices";
+    MOVED_PERMANENTLY => 301, "Moved Permanently";
+    FOUND => 302, "Found";
+    SEE_OTHER => 303, "See Other";
+    NOT_MODIFIED => 304, "Not Modified";
+    USE_PROXY => 305, "Use Proxy";
+    TEMPORARY_REDIRECT => 307, "Temporary Redirect";
+    PERMANENT_REDIRECT => 308, "Permanent Redirect";
+    
+    BAD_REQUEST => 400, "Bad Request";
+    UNAUTHORIZED => 401, "Unauthorized";
+    PAYMENT_REQUIRED => 402, "Payment Required";
+    FORBIDDEN => 403, "Forbidden";
+    NOT_FOUND => 404, "Not Found";
+    METHOD_NOT_ALLOWED => 405, "Method Not Allowed";
+    NOT_ACCEPTABLE => 406, "Not Acceptable";
+    PROXY_AUTHENTICATION_REQUIRED => 407, "Proxy Authentication Required";
+    REQUEST_TIMEOUT => 408, "Request Timeout";
+    CONFLICT => 409, "Conflict";
+    GONE => 410, "Gone";
+    LENGTH_REQUIRED => 411, "Length Required";
+    PRECONDITION_FAILED => 412, "Precondition Failed";
+    /// RFC 7231中叫做Payload Too Large
+    CONTENT_TOO_LARGE => 413, "Content Too Large";
+    URI_TOO_LONG => 414, "URI Too Long";
+    UNSUPPORTED_MEDIA_TYPE => 415, "Unsupported Media Type";
+    RANGE_NOT_SATISFIABLE => 416, "Range Not Satisfiable";
+    EXPECTATION_FAILED => 417, "Expectation Failed";
+    MISDIRECTED_REQUEST => 421, "Misdirected Request";
+    /// RFC 4918中叫做Unprocessable Entity
+    UNPROCESSABLE_CONTENT => 422, "Unprocessable Content";
+    LOCKED => 423, "Locked";
+    FAILED_DEPENDENCY => 424, "Failed Dependency";
+    TOO_EARLY => 425, "Too Early";
+    UPGRADE_REQUIRED => 426, "Upgrade Required";
+    PRECONDITION_REQUIRED => 428, "Precondition Required";
+    TOO_MANY_REQUESTS => 429, "Too Many Requests";
+    REQUEST_HEADER_FIELDS_TOO_LARGE => 431, "Request Header Fields Too Large";
+    UNAVAILABLE_FOR_LEGAL_REASONS => 451, "Unavailable For Legal Reasons";
+    
+    INTERNAL_SERVER_ERROR => 500, "Internal Server Error";
+    NOT_IMPLEMENTED => 501, "Not Implemented";
+    BAD_GATEWAY => 502, "Bad Gateway";
+    SERVICE_UNAVAILABLE => 503, "Service Unavailable";
+    GATEWAY_TIMEOUT => 504, "Gateway Timeout";
+    HTTP_VERSION_NOT_SUPPORTED => 505, "HTTP Version Not Supported";
+    VARIANT_ALSO_NEGOTIATES => 506, "Variant Also Negotiates";
+    INSUFFICIENT_STORAGE => 507, "Insufficient Storage";
+    LOOP_DETECTED => 508, "Loop Detected";
+    NOT_EXTENDED => 510, "Not Extended";
+    NETWORK_AUTHENTICATION_REQUIRED => 511, "Network Authentication Required";
+}
+
+impl StatusCode {
+    ///
+    /// 100到999之间的状态码，未分配的也可以使用
+    ///
+    pub fn from_u16(code: u16) -> Option<Self> {
+        if (100..1000).contains(&code) {
+            Some(StatusCode(code))
+        } else {
+            None
+        }
+    }
+    
+    pub fn as_u16(&self) -> u16 {
+        self.0
+    }
+    
+    ///
+    /// 1xx
+    ///
+    pub fn is_informational(&self) -> bool {
+        (100..200).contains(&self.0)
+    }
+    
+    ///
+    /// 2xx
+    ///
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.0)
+    }
+    
+    ///
+    /// 3xx
+    ///
+    pub fn is_redirection(&self) -> bool {
+        (300..400).contains(&self.0)
+    }
+    
+    ///
+    /// 4xx
+    ///
+    pub fn is_client_error(&self) -> bool {
+        (400..500).contains(&self.0)
+    }
+    
+    ///
+    /// 5xx
+    ///
+    pub fn is_server_error(&self) -> bool {
+        (500..600).contains(&self.0)
+    }
+    
+    ///
+    /// 1xx，204，304的响应不能有body(RFC 9110 6.4.1)
+    ///
+    pub fn allows_body(&self) -> bool {
+        !(self.is_informational() || self.0 == 204 || self.0 == 304)
+    }
+}
+
+impl Default for StatusCode {
+    fn default() -> Self {
+        StatusCode::OK
+    }
+}
+
+#[allow(clippy::from_over_into)]
+impl Into<u16> for StatusCode {
+    fn into(self) -> u16 {
+        self.0
+    }
+}
+
+impl Display for StatusCode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.canonical_reason() {
+            Some(reason) => write!(f, "{} {}", self.0, reason),
+            None => write!(f, "{}", self.0)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::header::status::{STATUS_CODES, StatusCode};
+    
+    #[test]
+    fn registry() {
+        for status in STATUS_CODES {
+            assert!(StatusCode::from_u16(status.as_u16()).is_some());
+            assert!(status.canonical_reason().is_some());
+        }
+        assert_eq!(StatusCode::NOT_FOUND.to_string(), "404 Not Found");
+        assert_eq!(StatusCode::from_u16(299).unwrap().to_string(), "299");
+        assert_eq!(StatusCode::from_u16(1000), None);
+        assert_eq!(StatusCode::from_u16(99), None);
+    }
+    
+    #[test]
+    fn class() {
+        assert!(StatusCode::EARLY_HINTS.is_informational());
+        assert!(StatusCode::IM_USED.is_success());
+        assert!(StatusCode::PERMANENT_REDIRECT.is_redirection());
+        assert!(StatusCode::UNAVAILABLE_FOR_LEGAL_REASONS.is_client_error());
+        assert!(StatusCode::NETWORK_AUTHENTICATION_REQUIRED.is_server_error());
+        assert!(!StatusCode::NOT_MODIFIED.allows_body());
+        assert!(StatusCode::OK < StatusCode::NOT_FOUND);
+    }
+}
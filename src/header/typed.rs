@@ -0,0 +1,844 @@
+use std::borrow::Cow;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+use crate::header::date::HttpDate;
+use crate::header::name::HeaderName;
+use crate::map::{HTTPHeadMap, HTTPHeadValue, is_field_value_char, is_tchar};
+use crate::parser::parse_content_length;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TypedHeaderError {
+    //没有这个header
+    Missing,
+    //header的值不符合格式
+    Invalid
+}
+
+pub type TypedHeaderResult<T> = Result<T, TypedHeaderError>;
+
+///
+/// 有类型的header，从header值解码，编码为header值
+///
+/// `decode`拿到所有同名header的值(按照顺序)，`encode`的每一个值都会成为单独的一行
+///
+pub trait TypedHeader: Sized {
+    fn name() -> HeaderName;
+    
+    fn decode(values: &[HTTPHeadValue]) -> TypedHeaderResult<Self>;
+    
+    fn encode(&self) -> Vec<HTTPHeadValue>;
+}
+
+impl HTTPHeadMap {
+    ///
+    /// 没有这个header或者值不合法时返回None
+    ///
+    pub fn typed_get<T>(&self) -> Option<T>
+        where
+            T: TypedHeader
+    {
+        self.typed_try_get().ok()
+    }
+    
+    pub fn typed_try_get<T>(&self) -> TypedHeaderResult<T>
+        where
+            T: TypedHeader
+    {
+        let values = self.get_all(T::name());
+        if values.is_empty() {
+            return Err(TypedHeaderError::Missing)
+        }
+        T::decode(&values)
+    }
+    
+    ///
+    /// 替换所有同名的header
+    ///
+    pub fn typed_insert<T>(&mut self, header: T)
+        where
+            T: TypedHeader
+    {
+        self.remove(T::name());
+        for value in header.encode() {
+            self.append(T::name(), value);
+        }
+    }
+}
+
+///
+/// 按照逗号分隔列表，引号内的逗号不分隔，空元素忽略(RFC 9110 5.6.1)
+///
+pub(crate) fn split_list(raw: &str) -> Vec<&str> {
+    let mut items = Vec::new();
+    let mut quoted = false;
+    let mut escaped = false;
+    let mut start = 0;
+    
+    for (index, c) in raw.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            ',' if !quoted => {
+                items.push(raw[start..index].trim());
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    items.push(raw[start..].trim());
+    
+    items.retain(|item| !item.is_empty());
+    items
+}
+
+fn split_values(values: &[HTTPHeadValue]) -> Vec<&str> {
+    values.iter()
+          .flat_map(|value| split_list(value))
+          .collect()
+}
+
+fn is_token(raw: &str) -> bool {
+    !raw.is_empty() && raw.bytes().all(is_tchar)
+}
+
+///
+/// 需要时加上引号
+///
+pub(crate) fn quote(value: &str) -> Cow<'_, str> {
+    if is_token(value) {
+        Cow::Borrowed(value)
+    } else {
+        Cow::Owned(format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\"")))
+    }
+}
+
+///
+/// `;name=value;name="quoted"`，返回参数以及无法解析时的None
+///
+pub(crate) fn parse_parameters(raw: &str) -> Option<Vec<(String, String)>> {
+    let mut params = Vec::new();
+    let mut rest = raw.trim_start();
+    
+    while let Some(param) = rest.strip_prefix(';') {
+        let param = param.trim_start();
+        if param.is_empty() {
+            break
+        }
+        let (name, value) = param.split_once('=')?;
+        let name = name.trim();
+        if !is_token(name) {
+            return None
+        }
+        let value = value.trim_start();
+        
+        let (value, remain) = if let Some(quoted) = value.strip_prefix('"') {
+            let mut text = String::new();
+            let mut chars = quoted.char_indices();
+            let end = loop {
+                match chars.next()? {
+                    (index, '"') => break index + 1,
+                    (_, '\\') => text.push(chars.next()?.1),
+                    (_, c) => text.push(c)
+                }
+            };
+            (text, &quoted[end..])
+        } else {
+            let end = value.find([';', ' ', '\t']).unwrap_or(value.len());
+            if !is_token(&value[..end]) {
+                return None
+            }
+            (value[..end].to_string(), &value[end..])
+        };
+        
+        params.push((name.to_ascii_lowercase(), value));
+        rest = remain.trim_start();
+    }
+    
+    if rest.is_empty() {
+        Some(params)
+    } else {
+        None
+    }
+}
+
+///
+/// 媒体类型`type/subtype;param=value`，类型和参数名都转换为小写
+///
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MediaType {
+    main: String,
+    sub: String,
+    params: Vec<(String, String)>
+}
+
+impl MediaType {
+    pub fn new<T, S>(main: T, sub: S) -> Self
+        where
+            T: ToString,
+            S: ToString
+    {
+        MediaType {
+            main: main.to_string().to_ascii_lowercase(),
+            sub: sub.to_string().to_ascii_lowercase(),
+            params: Vec::new()
+        }
+    }
+    
+    pub fn param<N, V>(self, name: N, value: V) -> Self
+        where
+            N: ToString,
+            V: ToString
+    {
+        let mut this = self;
+        this.params.push((name.to_string().to_ascii_lowercase(), value.to_string()));
+        this
+    }
+    
+    pub fn parse(raw: &str) -> Option<Self> {
+        let raw = raw.trim();
+        let end = raw.find(';').unwrap_or(raw.len());
+        let (main, sub) = raw[..end].trim_end().split_once('/')?;
+        if !is_token(main) || !is_token(sub) {
+            return None
+        }
+        
+        let mut media = MediaType::new(main, sub);
+        media.params = parse_parameters(&raw[end..])?;
+        Some(media)
+    }
+    
+    pub fn main_type(&self) -> &str {
+        &self.main
+    }
+    
+    pub fn sub_type(&self) -> &str {
+        &self.sub
+    }
+    
+    ///
+    /// 不带参数的`type/subtype`
+    ///
+    pub fn essence(&self) -> String {
+        format!("{}/{}", self.main, self.sub)
+    }
+    
+    pub fn get_param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+    
+    pub fn params(&self) -> &[(String, String)] {
+        &self.params
+    }
+    
+    pub fn charset(&self) -> Option<&str> {
+        self.get_param("charset")
+    }
+    
+    ///
+    /// 支持`*/*`和`type/*`通配符
+    ///
+    pub fn matches(&self, other: &MediaType) -> bool {
+        (self.main == "*" || other.main == "*" || self.main == other.main)
+            && (self.sub == "*" || other.sub == "*" || self.sub == other.sub)
+    }
+}
+
+impl Display for MediaType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.main, self.sub)?;
+        for (name, value) in &self.params {
+            write!(f, ";{}={}", name, quote(value))?;
+        }
+        Ok(())
+    }
+}
+
+///
+/// 带有权重(q参数)的值，权重是0到1000的整数
+///
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct QualityItem<T> {
+    item: T,
+    quality: u16
+}
+
+impl<T> QualityItem<T> {
+    pub fn new(item: T, quality: u16) -> Self {
+        QualityItem {
+            item,
+            quality: quality.min(1000)
+        }
+    }
+    
+    pub fn item(&self) -> &T {
+        &self.item
+    }
+    
+    pub fn quality(&self) -> u16 {
+        self.quality
+    }
+}
+
+///
+/// `0.8` -> 800
+///
+fn parse_quality(raw: &str) -> Option<u16> {
+    let (int, frac) = match raw.split_once('.') {
+        Some((int, frac)) => (int, frac),
+        None => (raw, "")
+    };
+    if frac.len() > 3 || !frac.bytes().all(|b| b.is_ascii_digit()) {
+        return None
+    }
+    let frac = format!("{:0<3}", frac).parse::<u16>().ok()?;
+    match int {
+        "0" => Some(frac),
+        "1" if frac == 0 => Some(1000),
+        _ => None
+    }
+}
+
+fn format_quality(quality: u16) -> String {
+    if quality >= 1000 {
+        return String::from("1")
+    }
+    let frac = format!("{:03}", quality);
+    format!("0.{}", frac.trim_end_matches('0')).trim_end_matches('.').to_string()
+}
+
+macro_rules! string_header {
+    ($(#[$doc:meta])* $typed:ident, $name:ident) => {
+        $(#[$doc])*
+        #[derive(Clone, Debug, PartialEq, Eq)]
+        pub struct $typed(pub String);
+        
+        impl TypedHeader for $typed {
+            fn name() -> HeaderName {
+                HeaderName::$name
+            }
+            
+            fn decode(values: &[HTTPHeadValue]) -> TypedHeaderResult<Self> {
+                match values {
+                    [value] if !value.is_empty() && value.bytes().all(is_field_value_char) => Ok($typed(value.clone())),
+                    _ => Err(TypedHeaderError::Invalid)
+                }
+            }
+            
+            fn encode(&self) -> Vec<HTTPHeadValue> {
+                vec![self.0.clone()]
+            }
+        }
+    }
+}
+
+macro_rules! date_header {
+    ($(#[$doc:meta])* $typed:ident, $name:ident) => {
+        $(#[$doc])*
+        #[derive(Copy, Clone, Debug, PartialEq, Eq)]
+        pub struct $typed(pub HttpDate);
+        
+        impl TypedHeader for $typed {
+            fn name() -> HeaderName {
+                HeaderName::$name
+            }
+            
+            fn decode(values: &[HTTPHeadValue]) -> TypedHeaderResult<Self> {
+                match values {
+                    [value] => HttpDate::parse(value).map($typed).ok_or(TypedHeaderError::Invalid),
+                    _ => Err(TypedHeaderError::Invalid)
+                }
+            }
+            
+            fn encode(&self) -> Vec<HTTPHeadValue> {
+                vec![self.0.to_string()]
+            }
+        }
+    }
+}
+
+macro_rules! token_list_header {
+    ($(#[$doc:meta])* $typed:ident, $name:ident) => {
+        $(#[$doc])*
+        #[derive(Clone, Debug, PartialEq, Eq)]
+        pub struct $typed(pub Vec<String>);
+        
+        impl $typed {
+            pub fn contains(&self, token: &str) -> bool {
+                self.0.iter().any(|item| item.eq_ignore_ascii_case(token))
+            }
+        }
+        
+        impl TypedHeader for $typed {
+            fn name() -> HeaderName {
+                HeaderName::$name
+            }
+            
+            fn decode(values: &[HTTPHeadValue]) -> TypedHeaderResult<Self> {
+                let items = split_values(values);
+                if items.is_empty() || !items.iter().all(|item| is_token(item)) {
+                    return Err(TypedHeaderError::Invalid)
+                }
+                Ok($typed(items.into_iter().map(|item| item.to_string()).collect()))
+            }
+            
+            fn encode(&self) -> Vec<HTTPHeadValue> {
+                vec![self.0.join(", ")]
+            }
+        }
+    }
+}
+
+string_header!(
+    /// `Location: /index.html`，可以是相对引用
+    Location, LOCATION
+);
+string_header!(UserAgent, USER_AGENT);
+string_header!(Server, SERVER);
+
+date_header!(Date, DATE);
+date_header!(LastModified, LAST_MODIFIED);
+date_header!(Expires, EXPIRES);
+date_header!(IfModifiedSince, IF_MODIFIED_SINCE);
+
+token_list_header!(
+    /// `Connection: keep-alive, Upgrade`
+    Connection, CONNECTION
+);
+token_list_header!(
+    /// `Transfer-Encoding: gzip, chunked`
+    TransferEncoding, TRANSFER_ENCODING
+);
+
+impl Connection {
+    pub fn close() -> Self {
+        Connection(vec![String::from("close")])
+    }
+    
+    pub fn keep_alive() -> Self {
+        Connection(vec![String::from("keep-alive")])
+    }
+    
+    pub fn is_close(&self) -> bool {
+        self.contains("close")
+    }
+    
+    pub fn is_keep_alive(&self) -> bool {
+        self.contains("keep-alive")
+    }
+}
+
+impl TransferEncoding {
+    pub fn chunked() -> Self {
+        TransferEncoding(vec![String::from("chunked")])
+    }
+    
+    ///
+    /// 最后一个编码是chunked
+    ///
+    pub fn is_chunked(&self) -> bool {
+        self.0
+            .last()
+            .map(|coding| coding.eq_ignore_ascii_case("chunked"))
+            .unwrap_or(false)
+    }
+}
+
+///
+/// `Content-Length: 42`
+///
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ContentLength(pub u64);
+
+impl TypedHeader for ContentLength {
+    fn name() -> HeaderName {
+        HeaderName::CONTENT_LENGTH
+    }
+    
+    fn decode(values: &[HTTPHeadValue]) -> TypedHeaderResult<Self> {
+        let mut length = None;
+        for value in values {
+            let value = parse_content_length(value)
+                .map_err(|_| TypedHeaderError::Invalid)?;
+            match length {
+                Some(length) if length != value => return Err(TypedHeaderError::Invalid),
+                _ => length = Some(value)
+            }
+        }
+        length.map(|length| ContentLength(length as u64))
+              .ok_or(TypedHeaderError::Missing)
+    }
+    
+    fn encode(&self) -> Vec<HTTPHeadValue> {
+        vec![self.0.to_string()]
+    }
+}
+
+///
+/// `Content-Type: text/html; charset=utf-8`
+///
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ContentType(pub MediaType);
+
+impl ContentType {
+    pub fn text() -> Self {
+        ContentType(MediaType::new("text", "plain").param("charset", "utf-8"))
+    }
+    
+    pub fn html() -> Self {
+        ContentType(MediaType::new("text", "html").param("charset", "utf-8"))
+    }
+    
+    pub fn json() -> Self {
+        ContentType(MediaType::new("application", "json"))
+    }
+    
+    pub fn form_url_encoded() -> Self {
+        ContentType(MediaType::new("application", "x-www-form-urlencoded"))
+    }
+    
+    pub fn octet_stream() -> Self {
+        ContentType(MediaType::new("application", "octet-stream"))
+    }
+    
+    pub fn multipart_form_data<T>(boundary: T) -> Self
+        where
+            T: ToString
+    {
+        ContentType(MediaType::new("multipart", "form-data").param("boundary", boundary))
+    }
+}
+
+impl TypedHeader for ContentType {
+    fn name() -> HeaderName {
+        HeaderName::CONTENT_TYPE
+    }
+    
+    fn decode(values: &[HTTPHeadValue]) -> TypedHeaderResult<Self> {
+        match values {
+            [value] => MediaType::parse(value).map(ContentType).ok_or(TypedHeaderError::Invalid),
+            _ => Err(TypedHeaderError::Invalid)
+        }
+    }
+    
+    fn encode(&self) -> Vec<HTTPHeadValue> {
+        vec![self.0.to_string()]
+    }
+}
+
+///
+/// `Accept: text/html, application/json;q=0.9, */*;q=0.1`
+///
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Accept(pub Vec<QualityItem<MediaType>>);
+
+impl Accept {
+    ///
+    /// 按照权重从高到低排序，权重相同时保持原来的顺序
+    ///
+    pub fn preferred(&self) -> Vec<&MediaType> {
+        let mut items = self.0.iter().collect::<Vec<_>>();
+        items.sort_by_key(|item| std::cmp::Reverse(item.quality));
+        items.into_iter()
+             .filter(|item| item.quality > 0)
+             .map(|item| &item.item)
+             .collect()
+    }
+    
+    ///
+    /// `media`能否被接受，返回匹配到的最高权重
+    ///
+    pub fn quality_of(&self, media: &MediaType) -> Option<u16> {
+        self.0
+            .iter()
+            .filter(|item| item.item.matches(media))
+            .map(|item| item.quality)
+            .max()
+            .filter(|quality| *quality > 0)
+    }
+}
+
+impl TypedHeader for Accept {
+    fn name() -> HeaderName {
+        HeaderName::ACCEPT
+    }
+    
+    fn decode(values: &[HTTPHeadValue]) -> TypedHeaderResult<Self> {
+        let mut items = Vec::new();
+        for item in split_values(values) {
+            let mut media = MediaType::parse(item).ok_or(TypedHeaderError::Invalid)?;
+            let mut quality = 1000;
+            //q之后的参数是accept-ext，这里不区分
+            if let Some(index) = media.params.iter().position(|(name, _)| name == "q") {
+                quality = parse_quality(&media.params[index].1).ok_or(TypedHeaderError::Invalid)?;
+                media.params.remove(index);
+            }
+            items.push(QualityItem::new(media, quality));
+        }
+        Ok(Accept(items))
+    }
+    
+    fn encode(&self) -> Vec<HTTPHeadValue> {
+        let items = self.0
+                        .iter()
+                        .map(|item| if item.quality >= 1000 {
+                            item.item.to_string()
+                        } else {
+                            format!("{};q={}", item.item, format_quality(item.quality))
+                        })
+                        .collect::<Vec<_>>();
+        vec![items.join(", ")]
+    }
+}
+
+///
+/// `Host: example.com:8080`，IPv6地址保留方括号
+///
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Host {
+    host: String,
+    port: Option<u16>
+}
+
+impl Host {
+    pub fn new<T>(host: T, port: Option<u16>) -> Self
+        where
+            T: ToString
+    {
+        Host {
+            host: host.to_string(),
+            port
+        }
+    }
+    
+    pub fn host(&self) -> &str {
+        &self.host
+    }
+    
+    pub fn port(&self) -> Option<u16> {
+        self.port
+    }
+}
+
+impl TypedHeader for Host {
+    fn name() -> HeaderName {
+        HeaderName::HOST
+    }
+    
+    fn decode(values: &[HTTPHeadValue]) -> TypedHeaderResult<Self> {
+        let value = match values {
+            [value] => value.trim(),
+            _ => return Err(TypedHeaderError::Invalid)
+        };
+        
+        //[::1]:8080
+        let (host, port) = if value.starts_with('[') {
+            let end = value.find(']').ok_or(TypedHeaderError::Invalid)?;
+            match &value[end + 1..] {
+                "" => (&value[..=end], None),
+                port => (&value[..=end], Some(port.strip_prefix(':').ok_or(TypedHeaderError::Invalid)?))
+            }
+        } else {
+            match value.split_once(':') {
+                Some((host, port)) => (host, Some(port)),
+                None => (value, None)
+            }
+        };
+        
+        if host.is_empty() || host.bytes().any(|b| b.is_ascii_whitespace() || b.is_ascii_control() || b == b'/' || b == b'@') {
+            return Err(TypedHeaderError::Invalid)
+        }
+        let port = match port {
+            Some(port) if !port.is_empty() && port.bytes().all(|b| b.is_ascii_digit()) => {
+                Some(port.parse().map_err(|_| TypedHeaderError::Invalid)?)
+            }
+            Some(_) => return Err(TypedHeaderError::Invalid),
+            None => None
+        };
+        
+        Ok(Host::new(host, port))
+    }
+    
+    fn encode(&self) -> Vec<HTTPHeadValue> {
+        match self.port {
+            Some(port) => vec![format!("{}:{}", self.host, port)],
+            None => vec![self.host.clone()]
+        }
+    }
+}
+
+///
+/// `Content-Disposition: form-data; name="file"; filename="a.txt"`(RFC 6266，RFC 7578)
+///
+/// 发送时参数值总是加上引号，引号和换行按照HTML的做法编码为`%22`、`%0D`、`%0A`，`\`转义为`\\`
+///
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ContentDisposition {
+    disposition: String,
+    params: Vec<(String, String)>
+}
+
+impl ContentDisposition {
+    pub fn new<T>(disposition: T) -> Self
+        where
+            T: ToString
+    {
+        ContentDisposition {
+            disposition: disposition.to_string().to_ascii_lowercase(),
+            params: Vec::new()
+        }
+    }
+    
+    ///
+    /// multipart/form-data中的一个字段
+    ///
+    pub fn form_data<T>(name: T) -> Self
+        where
+            T: ToString
+    {
+        ContentDisposition::new("form-data").param("name", name)
+    }
+    
+    pub fn param<N, V>(self, name: N, value: V) -> Self
+        where
+            N: ToString,
+            V: ToString
+    {
+        let mut this = self;
+        this.params.push((name.to_string().to_ascii_lowercase(), value.to_string()));
+        this
+    }
+    
+    ///
+    /// 小写的`inline`、`attachment`或者`form-data`
+    ///
+    pub fn disposition(&self) -> &str {
+        &self.disposition
+    }
+    
+    pub fn get_param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+    
+    pub fn name(&self) -> Option<&str> {
+        self.get_param("name")
+    }
+    
+    pub fn filename(&self) -> Option<&str> {
+        self.get_param("filename")
+    }
+}
+
+impl TypedHeader for ContentDisposition {
+    fn name() -> HeaderName {
+        HeaderName::CONTENT_DISPOSITION
+    }
+    
+    fn decode(values: &[HTTPHeadValue]) -> TypedHeaderResult<Self> {
+        let value = match values {
+            [value] => value.trim(),
+            _ => return Err(TypedHeaderError::Invalid)
+        };
+        let end = value.find(';').unwrap_or(value.len());
+        let disposition = value[..end].trim_end();
+        if !is_token(disposition) {
+            return Err(TypedHeaderError::Invalid)
+        }
+        
+        let mut header = ContentDisposition::new(disposition);
+        header.params = parse_parameters(&value[end..]).ok_or(TypedHeaderError::Invalid)?;
+        Ok(header)
+    }
+    
+    fn encode(&self) -> Vec<HTTPHeadValue> {
+        let mut value = self.disposition.clone();
+        for (name, param) in &self.params {
+            //解析时`\`是quoted-pair的转义符
+            let param = param.replace('\\', "\\\\")
+                             .replace('"', "%22")
+                             .replace('\r', "%0D")
+                             .replace('\n', "%0A");
+            value.push_str(&format!("; {}=\"{}\"", name, param));
+        }
+        vec![value]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::header::date::HttpDate;
+    use crate::header::typed::{Accept, Connection, ContentDisposition, ContentLength, ContentType, Date, Host, Location, MediaType, TransferEncoding, TypedHeader, TypedHeaderError};
+    use crate::map::HTTPHeadMap;
+    
+    #[test]
+    fn typed_get() {
+        let mut map = HTTPHeadMap::new();
+        map.append("content-length", "42".to_string());
+        map.append("Content-Type", "text/HTML; Charset=\"utf-8\"".to_string());
+        map.append("Connection", "keep-alive, Upgrade".to_string());
+        map.append("Transfer-Encoding", "gzip".to_string());
+        map.append("Transfer-Encoding", "chunked".to_string());
+        map.append("Host", "[::1]:8080".to_string());
+        map.append("Date", "Sun, 06 Nov 1994 08:49:37 GMT".to_string());
+        
+        assert_eq!(map.typed_get::<ContentLength>(), Some(ContentLength(42)));
+        let content_type = map.typed_get::<ContentType>().unwrap();
+        assert_eq!(content_type.0.essence(), "text/html");
+        assert_eq!(content_type.0.charset(), Some("utf-8"));
+        assert!(map.typed_get::<Connection>().unwrap().is_keep_alive());
+        assert!(map.typed_get::<TransferEncoding>().unwrap().is_chunked());
+        assert_eq!(map.typed_get::<Host>().unwrap(), Host::new("[::1]", Some(8080)));
+        assert_eq!(map.typed_get::<Date>().unwrap().0.unix(), 784111777);
+        assert_eq!(map.typed_try_get::<Location>(), Err(TypedHeaderError::Missing));
+        
+        map.insert("Content-Length", "4x".to_string());
+        assert_eq!(map.typed_try_get::<ContentLength>(), Err(TypedHeaderError::Invalid));
+    }
+    
+    #[test]
+    fn typed_insert() {
+        let mut map = HTTPHeadMap::new();
+        map.typed_insert(ContentType::json());
+        map.typed_insert(ContentLength(7));
+        map.typed_insert(Date(HttpDate::from_unix(0)));
+        map.typed_insert(ContentLength(8));
+        map.typed_insert(ContentType(MediaType::new("multipart", "form-data").param("boundary", "a b")));
+        
+        assert_eq!(map.get("Content-Type").unwrap(), "multipart/form-data;boundary=\"a b\"");
+        assert_eq!(map.get_all("Content-Length"), vec!["8"]);
+        assert_eq!(map.get("Date").unwrap(), "Thu, 01 Jan 1970 00:00:00 GMT");
+    }
+    
+    #[test]
+    fn accept() {
+        let mut map = HTTPHeadMap::new();
+        map.append("Accept", "text/*;q=0.5, application/json, */*;q=0".to_string());
+        
+        let accept = map.typed_get::<Accept>().unwrap();
+        let preferred = accept.preferred();
+        assert_eq!(preferred.len(), 2);
+        assert_eq!(preferred[0].essence(), "application/json");
+        assert_eq!(accept.quality_of(&MediaType::new("text", "html")), Some(500));
+        assert_eq!(accept.quality_of(&MediaType::new("image", "png")), None);
+        
+        map.typed_insert(accept);
+        assert_eq!(map.get("Accept").unwrap(), "text/*;q=0.5, application/json, */*;q=0");
+    }
+    
+    #[test]
+    fn content_disposition() {
+        let header = ContentDisposition::form_data("upload").param("filename", "C:\\dir\\a \"b\".txt\\");
+        let encoded = header.encode();
+        assert_eq!(encoded, vec!["form-data; name=\"upload\"; filename=\"C:\\\\dir\\\\a %22b%22.txt\\\\\"".to_string()]);
+        
+        let decoded = ContentDisposition::decode(&encoded).unwrap();
+        assert_eq!(decoded.filename(), Some("C:\\dir\\a %22b%22.txt\\"));
+    }
+}
@@ -1,3 +1,6 @@
+use std::fmt;
+use std::fmt::{Debug, Display, Formatter};
+
 use crate::prelude::HTTPBytes;
 
 #[derive(Copy, Clone, Debug)]
@@ -6,50 +9,152 @@ pub enum HTTPVersionMatchError {
     NotMatch
 }
 
-#[derive(Copy, Clone, Debug)]
-pub enum HTTPVersion {
-    HTTP1_0,
-    HTTP1_1,
-    HTTP2
+///
+/// `HTTP/x.y`，主版本号和次版本号都是一位数字(RFC 9112 2.3)
+///
+/// 比较按照版本号进行，`HTTP/2`和`HTTP/2.0`是同一个版本，常量可以直接用在`match`中
+///
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct HTTPVersion {
+    major: u8,
+    minor: u8
+}
+
+///
+/// 所有版本的写法，`HTTP/0.0`到`HTTP/9.9`
+///
+const TEXT: [[u8; 8]; 100] = {
+    let mut text = [[0; 8]; 100];
+    let mut index = 0;
+    while index < 100 {
+        text[index] = [b'H', b'T', b'T', b'P', b'/', b'0' + (index / 10) as u8, b'.', b'0' + (index % 10) as u8];
+        index += 1;
+    }
+    text
+};
+
+impl HTTPVersion {
+    pub const HTTP0_9: HTTPVersion = HTTPVersion::from_digits(0, 9);
+    pub const HTTP1_0: HTTPVersion = HTTPVersion::from_digits(1, 0);
+    pub const HTTP1_1: HTTPVersion = HTTPVersion::from_digits(1, 1);
+    pub const HTTP2: HTTPVersion = HTTPVersion::from_digits(2, 0);
+    pub const HTTP3: HTTPVersion = HTTPVersion::from_digits(3, 0);
+    
+    ///
+    /// 版本号都必须是一位数字
+    ///
+    pub fn new(major: u8, minor: u8) -> Result<Self, HTTPVersionMatchError> {
+        if major > 9 || minor > 9 {
+            return Err(HTTPVersionMatchError::NotMatch)
+        }
+        Ok(HTTPVersion::from_digits(major, minor))
+    }
+    
+    const fn from_digits(major: u8, minor: u8) -> Self {
+        HTTPVersion {
+            major,
+            minor
+        }
+    }
+    
+    ///
+    /// 接受`HTTP/1.1`，`HTTP/2`，`HTTP/2.0`这样的写法，协议名区分大小写
+    ///
+    pub fn from<T>(t: T) -> Result<Self, HTTPVersionMatchError>
+        where
+            T: HTTPBytes
+    {
+        let raw = t.string();
+        if !raw.is_ascii() {
+            return Err(HTTPVersionMatchError::UnknownChars)
+        }
+        
+        let number = raw.strip_prefix("HTTP/")
+                        .ok_or(HTTPVersionMatchError::NotMatch)?;
+        let (major, minor) = match number.as_bytes() {
+            [major] => (*major, b'0'),
+            [major, b'.', minor] => (*major, *minor),
+            _ => return Err(HTTPVersionMatchError::NotMatch)
+        };
+        if !major.is_ascii_digit() || !minor.is_ascii_digit() {
+            return Err(HTTPVersionMatchError::NotMatch)
+        }
+        //HTTP/1只能写成HTTP/1.x
+        let (major, minor) = (major - b'0', minor - b'0');
+        if major < 2 && number.len() == 1 {
+            return Err(HTTPVersionMatchError::NotMatch)
+        }
+        
+        Ok(HTTPVersion::from_digits(major, minor))
+    }
+    
+    pub fn major(&self) -> u8 {
+        self.major
+    }
+    
+    pub fn minor(&self) -> u8 {
+        self.minor
+    }
+    
+    pub fn as_str(&self) -> &'static str {
+        match std::str::from_utf8(self.as_bytes()) {
+            Ok(text) => text,
+            //只有ASCII字符
+            Err(_) => unreachable!()
+        }
+    }
+    
+    pub fn as_bytes(&self) -> &'static [u8] {
+        //HTTP/2之后不再写次版本号
+        let len = if self.major >= 2 && self.minor == 0 { 6 } else { 8 };
+        &TEXT[(self.major * 10 + self.minor) as usize][..len]
+    }
+    
+    ///
+    /// 主版本号相同的消息语法兼容，可以按照自己支持的次版本处理(RFC 9110 2.5)
+    ///
+    pub fn is_compatible(&self, other: &HTTPVersion) -> bool {
+        self.major == other.major
+    }
+    
+    ///
+    /// 没有Connection header时是否保持连接：HTTP/1.1及以后默认保持，HTTP/1.0以及之前默认关闭
+    ///
+    pub fn keep_alive_default(&self) -> bool {
+        *self >= HTTPVersion::HTTP1_1
+    }
+    
+    ///
+    /// 是否支持chunked编码，HTTP/1.0的对端不能理解Transfer-Encoding
+    ///
+    pub fn supports_chunked(&self) -> bool {
+        self.major == 1 && self.minor >= 1
+    }
 }
 
 #[allow(clippy::from_over_into)]
 impl Into<String> for HTTPVersion {
     fn into(self) -> String {
-        match self {
-            HTTPVersion::HTTP1_0 => String::from("HTTP/1.0"),
-            HTTPVersion::HTTP1_1 => String::from("HTTP/1.1"),
-            HTTPVersion::HTTP2 => String::from("HTTP/2")
-        }
+        self.as_str().to_string()
     }
 }
 
 #[allow(clippy::from_over_into)]
 impl<'a> Into<&'a str> for HTTPVersion {
     fn into(self) -> &'a str {
-        match self {
-            HTTPVersion::HTTP1_0 => "HTTP/1.0",
-            HTTPVersion::HTTP1_1 => "HTTP/1.1",
-            HTTPVersion::HTTP2 => "HTTP/2"
-        }
+        self.as_str()
     }
 }
 
-impl HTTPVersion {
-    pub fn from<T>(t: T) -> Result<Self, HTTPVersionMatchError>
-        where
-            T: HTTPBytes
-    {
-        match t.string().as_str() {
-            "HTTP/1.0" => Ok(HTTPVersion::HTTP1_0),
-            "HTTP/1.1" => Ok(HTTPVersion::HTTP1_1),
-            "HTTP/2" => Ok(HTTPVersion::HTTP2),
-            _ => Err(HTTPVersionMatchError::NotMatch)
-        }
+impl Display for HTTPVersion {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
     }
-    pub fn as_bytes(&self) -> &[u8] {
-        let method: &str = (*self).into();
-        method.as_bytes()
+}
+
+impl Debug for HTTPVersion {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
     }
 }
 
@@ -62,10 +167,54 @@ mod version_test {
         let byte = HTTPVersion::HTTP1_1.as_bytes();
         assert_eq!("HTTP/1.1".as_bytes(), byte);
     }
-}
-
-impl ToString for HTTPVersion {
-    fn to_string(&self) -> String {
-        (*self).into()
+    
+    #[test]
+    fn parse_test() {
+        assert_eq!(HTTPVersion::from("HTTP/2.0").unwrap(), HTTPVersion::HTTP2);
+        assert_eq!(HTTPVersion::from("HTTP/2").unwrap().to_string(), "HTTP/2");
+        assert_eq!(HTTPVersion::from("HTTP/3").unwrap(), HTTPVersion::HTTP3);
+        assert_eq!(HTTPVersion::from("HTTP/0.9").unwrap(), HTTPVersion::HTTP0_9);
+        
+        let version = HTTPVersion::from("HTTP/1.2").unwrap();
+        assert_eq!((version.major(), version.minor()), (1, 2));
+        assert_eq!(version.as_str(), "HTTP/1.2");
+        
+        assert!(HTTPVersion::from("HTTP/1").is_err());
+        assert!(HTTPVersion::from("http/1.1").is_err());
+        assert!(HTTPVersion::from("HTTP/1.10").is_err());
+        assert!(HTTPVersion::from("HTTP/1.x").is_err());
+        
+        assert_eq!(HTTPVersion::new(1, 1).unwrap(), HTTPVersion::HTTP1_1);
+        assert_eq!(HTTPVersion::new(9, 9).unwrap().as_str(), "HTTP/9.9");
+        assert!(HTTPVersion::new(10, 0).is_err());
+        assert!(HTTPVersion::new(1, 10).is_err());
     }
-}
\ No newline at end of file
+    
+    #[test]
+    fn match_test() {
+        //常量可以作为模式
+        let name = |version: HTTPVersion| match version {
+            HTTPVersion::HTTP1_0 => "1.0",
+            HTTPVersion::HTTP1_1 => "1.1",
+            HTTPVersion::HTTP2 => "2",
+            _ => "other"
+        };
+        assert_eq!(name(HTTPVersion::from("HTTP/1.1").unwrap()), "1.1");
+        assert_eq!(name(HTTPVersion::from("HTTP/2.0").unwrap()), "2");
+        assert_eq!(name(HTTPVersion::HTTP3), "other");
+        let text: &str = HTTPVersion::HTTP1_0.into();
+        assert_eq!(text, "HTTP/1.0");
+    }
+    
+    #[test]
+    fn order_test() {
+        assert!(HTTPVersion::HTTP0_9 < HTTPVersion::HTTP1_0);
+        assert!(HTTPVersion::HTTP1_1 < HTTPVersion::HTTP2);
+        assert!(HTTPVersion::HTTP1_0.is_compatible(&HTTPVersion::HTTP1_1));
+        assert!(!HTTPVersion::HTTP1_1.is_compatible(&HTTPVersion::HTTP2));
+        
+        assert!(HTTPVersion::HTTP1_1.keep_alive_default());
+        assert!(!HTTPVersion::HTTP1_0.keep_alive_default());
+        assert!(!HTTPVersion::HTTP1_0.supports_chunked());
+    }
+}
@@ -3,6 +3,13 @@ extern crate core;
 pub mod prelude;
 pub mod request;
 pub mod response;
+pub mod client;
+pub mod cookie;
 pub mod error;
+pub mod extensions;
 pub mod header;
-pub mod map;
\ No newline at end of file
+pub mod map;
+pub mod multipart;
+pub mod parser;
+pub mod query;
+pub mod uri;
\ No newline at end of file
@@ -1,22 +1,22 @@
-use std::cell::RefCell;
-use std::collections::HashMap;
+use std::iter::FromIterator;
+use std::slice;
+use std::vec;
 
-pub type HTTPHeadKey = String;
+use crate::header::name::HeaderName;
+
+pub type HTTPHeadKey = HeaderName;
 pub type HTTPHeadValue = String;
 
-#[derive(Clone, Debug)]
+///
+/// 按照插入顺序保存header，同一个名字可以有多个值(例如Set-Cookie)
+///
+/// header名不区分大小写，查找时可以使用`&str`、`String`或者[`HeaderName`]的常量
+///
+/// 没有内部可变性，可以在线程之间共享(`Send + Sync`)
+///
+#[derive(Clone, Debug, Default)]
 pub struct HTTPHeadMap {
-    map: RefCell<HashMap<HTTPHeadKey, HTTPHeadValue>>,
-    iter_count: RefCell<Option<usize>>
-}
-
-impl Default for HTTPHeadMap {
-    fn default() -> Self {
-        HTTPHeadMap {
-            map: RefCell::new(HashMap::new()),
-            iter_count: RefCell::new(None)
-        }
-    }
+    map: Vec<(HTTPHeadKey, HTTPHeadValue)>
 }
 
 impl HTTPHeadMap {
@@ -24,70 +24,314 @@ impl HTTPHeadMap {
         HTTPHeadMap::default()
     }
     
+    ///
+    /// 替换同名的所有header，新的值放在第一个同名header的位置，返回原来的第一个值
+    ///
+    pub fn insert<K>(&mut self, k: K, v: HTTPHeadValue) -> Option<HTTPHeadValue>
+        where
+            K: Into<HTTPHeadKey>
+    {
+        let k = k.into();
+        match self.map.iter().position(|(key, _)| *key == k) {
+            Some(index) => {
+                let old = std::mem::replace(&mut self.map[index], (k.clone(), v)).1;
+                //删除后面的同名header
+                let mut current = 0;
+                self.map.retain(|(key, _)| {
+                    current += 1;
+                    current - 1 <= index || *key != k
+                });
+                Some(old)
+            }
+            None => {
+                self.map.push((k, v));
+                None
+            }
+        }
+    }
+    
+    ///
+    /// 在末尾添加一个header，不影响已有的同名header
+    ///
     #[inline]
-    pub fn insert(&self, k: HTTPHeadKey, v: HTTPHeadValue) -> Option<HTTPHeadValue> {
-        self.map.borrow_mut()
-            .insert(k, v)
+    pub fn append<K>(&mut self, k: K, v: HTTPHeadValue)
+        where
+            K: Into<HTTPHeadKey>
+    {
+        self.map.push((k.into(), v))
     }
     
-    pub fn remove(&self, k: HTTPHeadKey) -> Option<HTTPHeadValue> {
-        self.map.borrow_mut()
-            .remove(&k)
+    ///
+    /// 第一个同名header的值
+    ///
+    pub fn get<K>(&self, k: K) -> Option<HTTPHeadValue>
+        where
+            K: AsRef<str>
+    {
+        self.get_ref(k)
+            .cloned()
     }
     
-    #[inline]
-    pub fn current_iter_count(&self) -> Option<usize> {
-        *self.iter_count.borrow()
+    ///
+    /// 第一个同名header的值，不复制
+    ///
+    pub fn get_ref<K>(&self, k: K) -> Option<&HTTPHeadValue>
+        where
+            K: AsRef<str>
+    {
+        self.map
+            .iter()
+            .find(|(key, _)| *key == k.as_ref())
+            .map(|(_, value)| value)
     }
     
-    #[inline]
-    pub fn current_iter_count_mut(&self, value: Option<usize>) {
-        *self.iter_count.borrow_mut() = value
+    ///
+    /// 所有同名header的值，按照插入顺序
+    ///
+    pub fn get_all<K>(&self, k: K) -> Vec<HTTPHeadValue>
+        where
+            K: AsRef<str>
+    {
+        self.map
+            .iter()
+            .filter(|(key, _)| *key == k.as_ref())
+            .map(|(_, value)| value.clone())
+            .collect()
+    }
+    
+    ///
+    /// 用", "连接所有同名header的值，只适用于列表形式的header(RFC 9110 5.3)，Set-Cookie不能这样合并
+    ///
+    pub fn get_combined<K>(&self, k: K) -> Option<HTTPHeadValue>
+        where
+            K: AsRef<str>
+    {
+        let values = self.get_all(k);
+        if values.is_empty() {
+            None
+        } else {
+            Some(values.join(", "))
+        }
+    }
+    
+    pub fn contains_key<K>(&self, k: K) -> bool
+        where
+            K: AsRef<str>
+    {
+        self.map
+            .iter()
+            .any(|(key, _)| *key == k.as_ref())
+    }
+    
+    ///
+    /// 删除所有同名header，返回第一个值
+    ///
+    pub fn remove<K>(&mut self, k: K) -> Option<HTTPHeadValue>
+        where
+            K: AsRef<str>
+    {
+        let k = k.as_ref();
+        let first = self.map
+                        .iter()
+                        .position(|(key, _)| *key == k)
+                        .map(|index| self.map[index].1.clone());
+        self.map.retain(|(key, _)| *key != k);
+        first
     }
     
     #[inline]
     pub fn len(&self) -> usize {
-        self.map.borrow()
-            .len()
+        self.map.len()
     }
     
     #[inline]
-    pub fn insert_tuple(&self, tuple: (HTTPHeadKey, HTTPHeadValue)) -> Option<HTTPHeadValue> {
+    pub fn insert_tuple(&mut self, tuple: (HTTPHeadKey, HTTPHeadValue)) -> Option<HTTPHeadValue> {
         self.insert(tuple.0, tuple.1)
     }
     
+    #[inline]
+    pub fn append_tuple(&mut self, tuple: (HTTPHeadKey, HTTPHeadValue)) {
+        self.append(tuple.0, tuple.1)
+    }
+    
     pub fn is_empty(&self) -> bool {
         self.len() == 0
     }
+    
+    pub fn clear(&mut self) {
+        self.map.clear()
+    }
+    
+    ///
+    /// 按照插入顺序借用每一个header
+    ///
+    pub fn iter(&self) -> Iter<'_> {
+        Iter {
+            inner: self.map.iter()
+        }
+    }
+    
+    ///
+    /// 按照插入顺序借用每一个header，可以修改值
+    ///
+    pub fn iter_mut(&mut self) -> IterMut<'_> {
+        IterMut {
+            inner: self.map.iter_mut()
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Iter<'a> {
+    inner: slice::Iter<'a, (HTTPHeadKey, HTTPHeadValue)>
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = (&'a HTTPHeadKey, &'a HTTPHeadValue);
+    
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner
+            .next()
+            .map(|(key, value)| (key, value))
+    }
+    
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'a> DoubleEndedIterator for Iter<'a> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner
+            .next_back()
+            .map(|(key, value)| (key, value))
+    }
+}
+
+impl<'a> ExactSizeIterator for Iter<'a> {}
+
+#[derive(Debug)]
+pub struct IterMut<'a> {
+    inner: slice::IterMut<'a, (HTTPHeadKey, HTTPHeadValue)>
+}
+
+impl<'a> Iterator for IterMut<'a> {
+    type Item = (&'a HTTPHeadKey, &'a mut HTTPHeadValue);
+    
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner
+            .next()
+            .map(|(key, value)| (&*key, value))
+    }
+    
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
 }
 
-impl Iterator for HTTPHeadMap {
+impl<'a> DoubleEndedIterator for IterMut<'a> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner
+            .next_back()
+            .map(|(key, value)| (&*key, value))
+    }
+}
+
+impl<'a> ExactSizeIterator for IterMut<'a> {}
+
+#[derive(Debug)]
+pub struct IntoIter {
+    inner: vec::IntoIter<(HTTPHeadKey, HTTPHeadValue)>
+}
+
+impl Iterator for IntoIter {
     type Item = (HTTPHeadKey, HTTPHeadValue);
     
+    #[inline]
     fn next(&mut self) -> Option<Self::Item> {
-        let iter_count = self.current_iter_count();
-        
-        //未初始化
-        if iter_count.is_none() {
-            self.current_iter_count_mut(Some(0));
-            //初始化了但是已经迭代完了
-        } else if iter_count.unwrap() >= self.len() {
-            self.current_iter_count_mut(None);         //改成None
-            return None
+        self.inner.next()
+    }
+    
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for IntoIter {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back()
+    }
+}
+
+impl ExactSizeIterator for IntoIter {}
+
+impl<'a> IntoIterator for &'a HTTPHeadMap {
+    type Item = (&'a HTTPHeadKey, &'a HTTPHeadValue);
+    type IntoIter = Iter<'a>;
+    
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a mut HTTPHeadMap {
+    type Item = (&'a HTTPHeadKey, &'a mut HTTPHeadValue);
+    type IntoIter = IterMut<'a>;
+    
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+impl IntoIterator for HTTPHeadMap {
+    type Item = (HTTPHeadKey, HTTPHeadValue);
+    type IntoIter = IntoIter;
+    
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter {
+            inner: self.map.into_iter()
         }
-        
-        let iter_count = self.current_iter_count().unwrap();
-        //自增操作
-        self.current_iter_count_mut(Some(iter_count + 1));
-        
-        self.map.borrow()
-            .iter()
-            .nth(iter_count)
-            .map(|(ref_k, ref_v)| (ref_k.clone(), ref_v.clone()))
     }
 }
 
-#[derive(Copy, Clone, Debug)]
+///
+/// 和`append`一样，保留同名的header
+///
+impl<K> Extend<(K, HTTPHeadValue)> for HTTPHeadMap
+    where
+        K: Into<HTTPHeadKey>
+{
+    fn extend<T>(&mut self, iter: T)
+        where
+            T: IntoIterator<Item=(K, HTTPHeadValue)>
+    {
+        self.map.extend(iter.into_iter().map(|(key, value)| (key.into(), value)))
+    }
+}
+
+impl<K> FromIterator<(K, HTTPHeadValue)> for HTTPHeadMap
+    where
+        K: Into<HTTPHeadKey>
+{
+    fn from_iter<T>(iter: T) -> Self
+        where
+            T: IntoIterator<Item=(K, HTTPHeadValue)>
+    {
+        let mut map = HTTPHeadMap::new();
+        map.extend(iter);
+        map
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum HeaderMappingError {
     UnknownChars,
     //不能转换为String
@@ -95,12 +339,108 @@ pub enum HeaderMappingError {
     //不能读取K,V
     EmptyRaw,
     //空buf
-    EmptyString
+    EmptyString,
     //空String
+    InvalidName,
+    //header名不是token
+    InvalidValue
+    //header值包含控制字符
 }
 
 pub type HeaderMappingResult<T> = Result<T, HeaderMappingError>;
 
+///
+/// RFC 9110 token中允许的字符
+///
+pub(crate) fn is_tchar(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&b)
+}
+
+///
+/// RFC 9110 field-value中允许的字符：VCHAR，obs-text，SP，HTAB
+///
+pub(crate) fn is_field_value_char(b: u8) -> bool {
+    b == b' ' || b == b'\t' || (0x21..=0x7e).contains(&b) || b >= 0x80
+}
+
+pub(crate) fn trim_ows(mut value: &[u8]) -> &[u8] {
+    while let Some((first, rest)) = value.split_first() {
+        if *first != b' ' && *first != b'\t' {
+            break
+        }
+        value = rest;
+    }
+    while let Some((last, rest)) = value.split_last() {
+        if *last != b' ' && *last != b'\t' {
+            break
+        }
+        value = rest;
+    }
+    value
+}
+
+///
+/// `field-name ":" OWS field-value OWS`
+///
+/// field-name必须是token，field-value去掉两边的OWS，内部的空白保留，
+/// obs-fold(CRLF之后跟着SP或HTAB)替换为一个SP
+///
+fn parse_field(raw: &[u8]) -> HeaderMappingResult<(HTTPHeadKey, HTTPHeadValue)> {
+    let colon = raw.iter()
+                   .position(|b| *b == b':')
+                   .ok_or(HeaderMappingError::UnknownString)?;
+    let (name, value) = (&raw[..colon], &raw[colon + 1..]);
+    
+    //header名和冒号之间不能有空白
+    if name.is_empty() || !name.iter().all(|b| is_tchar(*b)) {
+        return Err(HeaderMappingError::InvalidName)
+    }
+    
+    //obs-fold
+    let mut unfolded = Vec::with_capacity(value.len());
+    let mut index = 0;
+    while index < value.len() {
+        let fold = match &value[index..] {
+            [b'\r', b'\n', b' ' | b'\t', ..] => 2,
+            [b'\n', b' ' | b'\t', ..] => 1,
+            _ => 0
+        };
+        if fold > 0 {
+            unfolded.push(b' ');
+            index += fold;
+            while index < value.len() && (value[index] == b' ' || value[index] == b'\t') {
+                index += 1;
+            }
+        } else {
+            unfolded.push(value[index]);
+            index += 1;
+        }
+    }
+    
+    let value = trim_ows(&unfolded);
+    if !value.iter().all(|b| is_field_value_char(*b)) {
+        return Err(HeaderMappingError::InvalidValue)
+    }
+    
+    //token只有ASCII字符
+    let key = String::from_utf8(name.to_vec())
+        .map_err(|_| HeaderMappingError::InvalidName)?
+        .into();
+    let value = decode_field_value(value);
+    
+    Ok((key, value))
+}
+
+///
+/// 值是UTF-8时原样保存，否则obs-text按照ISO-8859-1解码(RFC 9110 5.5)，这样的值不会导致整个消息被拒绝
+///
+pub(crate) fn decode_field_value(value: &[u8]) -> HTTPHeadValue {
+    match std::str::from_utf8(value) {
+        Ok(value) => value.to_string(),
+        Err(_) => value.iter().map(|b| *b as char).collect()
+    }
+}
+
 pub trait HeaderMappingType {
     fn parse_key_value(&self) -> HeaderMappingResult<(HTTPHeadKey, HTTPHeadValue)>;
 }
@@ -111,27 +451,7 @@ impl HeaderMappingType for [u8] {
             return Err(HeaderMappingError::EmptyRaw)
         }
         
-        let str = String::from_utf8(self.to_vec());
-        if str.is_err() {
-            return Err(HeaderMappingError::UnknownChars)
-        }
-        
-        let str = str.unwrap();
-        if str.is_empty() {
-            return Err(HeaderMappingError::EmptyString)
-        }
-        
-        let str = str.replacen(':', " ", 1);
-        let mut sp = str.split_whitespace();
-        if sp.clone().count() < 2 {
-            return Err(HeaderMappingError::UnknownString)
-        }
-        
-        let key = sp.next().unwrap().to_string();
-        let value = sp.collect::<Vec<&str>>()
-                      .concat();
-        
-        Ok((key, value))
+        parse_field(self)
     }
 }
 
@@ -141,18 +461,7 @@ impl HeaderMappingType for String {
             return Err(HeaderMappingError::EmptyString)
         };
         
-        let str = self.replacen(':', " ", 1);
-        let mut sp = str.split_whitespace();
-        
-        if sp.clone().count() < 2 {
-            return Err(HeaderMappingError::UnknownString)
-        }
-        
-        let key = sp.next().unwrap().to_string();
-        let value = sp.collect::<Vec<&str>>()
-                      .concat();
-        
-        Ok((key, value))
+        parse_field(self.as_bytes())
     }
 }
 
@@ -162,26 +471,157 @@ impl HeaderMappingType for &str {
             return Err(HeaderMappingError::EmptyString)
         }
         
-        let str = self.replacen(':', " ", 1);
-        let mut sp = str.split_whitespace();
-        
-        if sp.clone().count() < 2 {
-            return Err(HeaderMappingError::UnknownString)
-        }
-        
-        let key = sp.next().unwrap().to_string();
-        let value = sp.collect::<Vec<&str>>()
-                      .concat();
-        
-        Ok((key, value))
+        parse_field(self.as_bytes())
     }
 }
 
+///
+/// 检查发送前的header：名字必须是token，值不能有控制字符(防止CR/LF注入)
+///
+pub(crate) fn validate_field(key: &HTTPHeadKey, value: &str) -> HeaderMappingResult<()> {
+    let name = key.as_str();
+    if name.is_empty() || !name.bytes().all(is_tchar) {
+        return Err(HeaderMappingError::InvalidName)
+    }
+    if !value.bytes().all(is_field_value_char) {
+        return Err(HeaderMappingError::InvalidValue)
+    }
+    Ok(())
+}
+
 impl HTTPHeadMap {
-    pub fn try_insert<T>(&self, t: T) -> HeaderMappingResult<Option<HTTPHeadValue>>
+    ///
+    /// 检查名字和值之后再`insert`，不合法时不做任何修改
+    ///
+    pub fn checked_insert<K>(&mut self, k: K, v: HTTPHeadValue) -> HeaderMappingResult<Option<HTTPHeadValue>>
+        where
+            K: Into<HTTPHeadKey>
+    {
+        let k = k.into();
+        validate_field(&k, &v)?;
+        Ok(self.insert(k, v))
+    }
+    
+    ///
+    /// 检查名字和值之后再`append`，不合法时不做任何修改
+    ///
+    pub fn checked_append<K>(&mut self, k: K, v: HTTPHeadValue) -> HeaderMappingResult<()>
+        where
+            K: Into<HTTPHeadKey>
+    {
+        let k = k.into();
+        validate_field(&k, &v)?;
+        self.append(k, v);
+        Ok(())
+    }
+    
+    ///
+    /// 检查所有header，返回第一个错误
+    ///
+    pub fn validate(&self) -> HeaderMappingResult<()> {
+        self.iter()
+            .try_for_each(|(key, value)| validate_field(key, value))
+    }
+    
+    pub fn try_insert<T>(&mut self, t: T) -> HeaderMappingResult<Option<HTTPHeadValue>>
         where
             T: HeaderMappingType
     {
         Ok(self.insert_tuple(t.parse_key_value()?))
     }
+    
+    pub fn try_append<T>(&mut self, t: T) -> HeaderMappingResult<()>
+        where
+            T: HeaderMappingType
+    {
+        self.append_tuple(t.parse_key_value()?);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+    use std::thread;
+    
+    use crate::header::name::HeaderName;
+    use crate::map::{HeaderMappingError, HeaderMappingType, HTTPHeadMap};
+    
+    #[test]
+    fn parse_value() {
+        let (key, value) = "User-Agent: Mozilla/5.0 (X11; Linux) \t".parse_key_value().unwrap();
+        assert_eq!(key, "User-Agent");
+        assert_eq!(value, "Mozilla/5.0 (X11; Linux)");
+        
+        let (_, value) = String::from("Empty:").parse_key_value().unwrap();
+        assert_eq!(value, "");
+        
+        let (_, value) = b"X-Folded: first\r\n  second"[..].parse_key_value().unwrap();
+        assert_eq!(value, "first second");
+    }
+    
+    #[test]
+    fn multi_value() {
+        let mut map = HTTPHeadMap::new();
+        map.append("Set-Cookie", "a=1".to_string());
+        map.append(HeaderName::VARY, "Accept".to_string());
+        map.append("set-cookie", "b=2".to_string());
+        map.append("Vary", "Cookie".to_string());
+        
+        assert_eq!(map.len(), 4);
+        assert_eq!(map.get("Set-Cookie").unwrap(), "a=1");
+        assert_eq!(map.get_all("SET-COOKIE"), vec!["a=1", "b=2"]);
+        assert_eq!(map.get_combined("Vary").unwrap(), "Accept, Cookie");
+        
+        assert_eq!(map.insert("vary", "*".to_string()).unwrap(), "Accept");
+        let keys = map.iter().map(|(k, _)| k.to_string()).collect::<Vec<_>>();
+        assert_eq!(keys, vec!["Set-Cookie", "vary", "set-cookie"]);
+        
+        assert_eq!(map.remove(HeaderName::SET_COOKIE).unwrap(), "a=1");
+        assert!(!map.contains_key("set-cookie"));
+        assert_eq!(map.len(), 1);
+    }
+    
+    #[test]
+    fn iter() {
+        let mut map = vec![("Accept", "*/*".to_string()), ("Vary", "Accept".to_string())]
+            .into_iter()
+            .collect::<HTTPHeadMap>();
+        map.extend(vec![(HeaderName::VARY, "Cookie".to_string())]);
+        
+        for (key, value) in &mut map {
+            if *key == HeaderName::VARY {
+                value.make_ascii_uppercase();
+            }
+        }
+        let pairs = map.iter().map(|(k, v)| format!("{}={}", k, v)).collect::<Vec<_>>();
+        assert_eq!(pairs, vec!["Accept=*/*", "Vary=ACCEPT", "Vary=COOKIE"]);
+        assert_eq!(map.iter().len(), 3);
+        //迭代不改变map
+        assert_eq!((&map).into_iter().count(), 3);
+        
+        let shared = Arc::new(map);
+        let worker = {
+            let shared = Arc::clone(&shared);
+            thread::spawn(move || shared.get_all("vary").len())
+        };
+        assert_eq!(worker.join().unwrap(), 2);
+        
+        let owned = Arc::try_unwrap(shared).unwrap().into_iter().next_back().unwrap();
+        assert_eq!(owned.1, "COOKIE");
+    }
+    
+    #[test]
+    fn invalid() {
+        assert_eq!("Bad Name: x".parse_key_value(), Err(HeaderMappingError::InvalidName));
+        assert_eq!("Name : x".parse_key_value(), Err(HeaderMappingError::InvalidName));
+        assert_eq!(": x".parse_key_value(), Err(HeaderMappingError::InvalidName));
+        assert_eq!("Name: a\r\nInjected: b".parse_key_value(), Err(HeaderMappingError::InvalidValue));
+        assert_eq!("Name: a\0b".parse_key_value(), Err(HeaderMappingError::InvalidValue));
+        //不是UTF-8的obs-text按照ISO-8859-1解码
+        assert_eq!(b"Name: caf\xe9"[..].parse_key_value().unwrap().1, "caf\u{e9}");
+        assert_eq!(b"Name: caf\xc3\xa9"[..].parse_key_value().unwrap().1, "caf\u{e9}");
+        assert_eq!("NoColon".parse_key_value(), Err(HeaderMappingError::UnknownString));
+        assert_eq!("".parse_key_value(), Err(HeaderMappingError::EmptyString));
+    }
 }
\ No newline at end of file
@@ -0,0 +1,380 @@
+use std::collections::hash_map::RandomState;
+use std::fmt;
+use std::fmt::{Debug, Formatter};
+use std::fs::File;
+use std::hash::{BuildHasher, Hasher};
+use std::io;
+use std::io::{Cursor, ErrorKind, Read, Take, Write};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::header::name::HeaderName;
+use crate::header::typed::{ContentDisposition, ContentLength, ContentType, MediaType, TypedHeader};
+use crate::map::HTTPHeadMap;
+use crate::multipart::{is_valid_boundary, MultipartError};
+use crate::response::client::HTTPClientResponse;
+
+struct BuilderPart {
+    head: Vec<u8>,
+    body: Box<dyn Read + Send>,
+    len: Option<u64>
+}
+
+///
+/// 客户端multipart/form-data body的构建器
+///
+/// 文件只记录打开的句柄，在`into_body`返回的reader被读取时才从磁盘读取
+///
+pub struct MultipartBuilder {
+    boundary: String,
+    parts: Vec<BuilderPart>
+}
+
+impl MultipartBuilder {
+    ///
+    /// 使用随机的boundary
+    ///
+    pub fn new() -> Self {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_nanos())
+            .unwrap_or_default();
+        let random = |salt: u8| {
+            let mut hasher = RandomState::new().build_hasher();
+            hasher.write_u128(nanos);
+            hasher.write_u8(salt);
+            hasher.finish()
+        };
+        
+        MultipartBuilder {
+            boundary: format!("----http-rs-plus-{:016x}{:016x}", random(0), random(1)),
+            parts: Vec::new()
+        }
+    }
+    
+    pub fn boundary<T>(self, boundary: T) -> Result<Self, MultipartError>
+        where
+            T: ToString
+    {
+        let boundary = boundary.to_string();
+        if !is_valid_boundary(&boundary) {
+            return Err(MultipartError::InvalidBoundary)
+        }
+        let mut this = self;
+        this.boundary = boundary;
+        Ok(this)
+    }
+    
+    ///
+    /// 普通的文本字段
+    ///
+    pub fn text<N, V>(self, name: N, value: V) -> Self
+        where
+            N: ToString,
+            V: ToString
+    {
+        let value = value.to_string().into_bytes();
+        let len = value.len() as u64;
+        self.part(ContentDisposition::form_data(name), None, Box::new(Cursor::new(value)), Some(len))
+    }
+    
+    ///
+    /// 内存中的文件
+    ///
+    pub fn bytes<N, F>(self, name: N, filename: F, content_type: MediaType, data: Vec<u8>) -> Self
+        where
+            N: ToString,
+            F: ToString
+    {
+        let len = data.len() as u64;
+        let disposition = ContentDisposition::form_data(name).param("filename", filename);
+        self.part(disposition, Some(content_type), Box::new(Cursor::new(data)), Some(len))
+    }
+    
+    ///
+    /// 任意来源的文件，`len`未知时发送的body只能使用chunked编码
+    ///
+    /// `len`已知时只读取`len`个字节，`reader`提前结束时读取body返回`UnexpectedEof`
+    ///
+    pub fn reader<N, F, R>(self, name: N, filename: F, content_type: MediaType, reader: R, len: Option<u64>) -> Self
+        where
+            N: ToString,
+            F: ToString,
+            R: Read + Send + 'static
+    {
+        let disposition = ContentDisposition::form_data(name).param("filename", filename);
+        self.part(disposition, Some(content_type), Box::new(reader), len)
+    }
+    
+    ///
+    /// 磁盘上的文件，文件名取路径的最后一部分，类型是`application/octet-stream`
+    ///
+    pub fn file<N, P>(self, name: N, path: P) -> io::Result<Self>
+        where
+            N: ToString,
+            P: AsRef<Path>
+    {
+        let path = path.as_ref();
+        let file = File::open(path)?;
+        let len = file.metadata()?.len();
+        let filename = path.file_name()
+                           .map(|name| name.to_string_lossy().into_owned())
+                           .unwrap_or_default();
+        Ok(self.reader(name, filename, ContentType::octet_stream().0, file, Some(len)))
+    }
+    
+    fn part(self, disposition: ContentDisposition, content_type: Option<MediaType>, body: Box<dyn Read + Send>, len: Option<u64>) -> Self {
+        let mut head = format!("--{}\r\n", self.boundary);
+        for value in disposition.encode() {
+            head.push_str(&format!("{}: {}\r\n", HeaderName::CONTENT_DISPOSITION, value));
+        }
+        if let Some(content_type) = content_type {
+            head.push_str(&format!("{}: {}\r\n", HeaderName::CONTENT_TYPE, content_type));
+        }
+        head.push_str("\r\n");
+        
+        //长度已知时body必须正好是这个长度，否则和Content-Length不一致
+        let body: Box<dyn Read + Send> = match len {
+            Some(len) => Box::new(ExactReader {
+                inner: body.take(len)
+            }),
+            None => body
+        };
+        
+        let mut this = self;
+        this.parts.push(BuilderPart {
+            head: head.into_bytes(),
+            body,
+            len
+        });
+        this
+    }
+    
+    ///
+    /// `multipart/form-data; boundary=...`
+    ///
+    pub fn content_type(&self) -> ContentType {
+        ContentType::multipart_form_data(&self.boundary)
+    }
+    
+    ///
+    /// 所有部分的长度都已知时body的总长度
+    ///
+    pub fn content_length(&self) -> Option<u64> {
+        let mut total = self.closing().len() as u64;
+        for part in &self.parts {
+            //内容之后的"\r\n"
+            total += part.head.len() as u64 + part.len? + 2;
+        }
+        Some(total)
+    }
+    
+    fn closing(&self) -> String {
+        format!("--{}--\r\n", self.boundary)
+    }
+    
+    pub fn into_body(self) -> MultipartBody {
+        let closing = self.closing();
+        let mut readers: Vec<Box<dyn Read + Send>> = Vec::new();
+        for part in self.parts {
+            readers.push(Box::new(Cursor::new(part.head)));
+            readers.push(part.body);
+            readers.push(Box::new(&b"\r\n"[..]));
+        }
+        readers.push(Box::new(Cursor::new(closing.into_bytes())));
+        
+        MultipartBody {
+            readers,
+            current: 0
+        }
+    }
+    
+    ///
+    /// 写入`request`的请求行和header，然后流式写入body，`request`原有的body被忽略
+    ///
+    /// 长度已知时使用Content-Length，否则使用chunked编码
+    ///
+    pub fn send<W>(self, request: HTTPClientResponse, w: &mut W) -> io::Result<()>
+        where
+            W: Write
+    {
+        let mut request = request;
+        request.body_mut().clear();
+        let length = self.content_length();
+        let header = request.header_mut();
+        header.typed_insert(self.content_type());
+        header.remove(HeaderName::CONTENT_LENGTH);
+        header.remove(HeaderName::TRANSFER_ENCODING);
+        
+        let mut body = self.into_body();
+        match length {
+            Some(length) => {
+                request.header_mut().typed_insert(ContentLength(length));
+                request.write_head_to(w)?;
+                io::copy(&mut body, w)?;
+            }
+            None => {
+                let mut encoder = request.stream_to(&mut *w)?;
+                io::copy(&mut body, &mut encoder)?;
+                encoder.finish(&HTTPHeadMap::new())?;
+            }
+        }
+        w.flush()
+    }
+}
+
+impl Default for MultipartBuilder {
+    fn default() -> Self {
+        MultipartBuilder::new()
+    }
+}
+
+impl Debug for MultipartBuilder {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MultipartBuilder")
+         .field("boundary", &self.boundary)
+         .field("parts", &self.parts.len())
+         .finish()
+    }
+}
+
+///
+/// 正好读取`Take`的长度，不足时返回`UnexpectedEof`
+///
+struct ExactReader {
+    inner: Take<Box<dyn Read + Send>>
+}
+
+impl Read for ExactReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        if read == 0 && !buf.is_empty() && self.inner.limit() > 0 {
+            return Err(io::Error::new(ErrorKind::UnexpectedEof, "multipart part is shorter than its length"))
+        }
+        Ok(read)
+    }
+}
+
+///
+/// 按顺序读取各个部分的body
+///
+pub struct MultipartBody {
+    readers: Vec<Box<dyn Read + Send>>,
+    current: usize
+}
+
+impl Read for MultipartBody {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while let Some(reader) = self.readers.get_mut(self.current) {
+            let read = reader.read(buf)?;
+            if read > 0 || buf.is_empty() {
+                return Ok(read)
+            }
+            self.current += 1;
+        }
+        Ok(0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+    use std::io::{ErrorKind, Read};
+    
+    use crate::header::method::HTTPClientMethod;
+    use crate::header::typed::MediaType;
+    use crate::multipart::{Multipart, MultipartBuilder};
+    use crate::parser::client::HTTPClientParser;
+    use crate::response::client::HTTPClientResponseBuilder;
+    
+    #[test]
+    fn round_trip() {
+        let path = std::env::temp_dir().join(format!("http-rs-plus-multipart-{}.bin", std::process::id()));
+        fs::write(&path, b"\x00\x01binary\r\n--not-it\r\n").unwrap();
+        
+        let builder = MultipartBuilder::new()
+            .boundary("not-it-either")
+            .unwrap()
+            .text("title", "a \"quoted\"\r\nname")
+            .file("upload", &path)
+            .unwrap();
+        let length = builder.content_length().unwrap();
+        let mut body = Vec::new();
+        builder.into_body().read_to_end(&mut body).unwrap();
+        assert_eq!(body.len() as u64, length);
+        assert!(body.ends_with(b"\r\n--not-it-either--\r\n"));
+        
+        let mut multipart = Multipart::new(&body[..], "not-it-either").unwrap();
+        let mut part = multipart.next_part().unwrap().unwrap();
+        assert_eq!(part.name().unwrap(), "title");
+        assert_eq!(part.text().unwrap(), "a \"quoted\"\r\nname");
+        let mut part = multipart.next_part().unwrap().unwrap();
+        assert_eq!(part.filename().unwrap(), path.file_name().unwrap().to_str().unwrap());
+        assert_eq!(part.content_type().essence(), "application/octet-stream");
+        assert_eq!(part.content().unwrap(), b"\x00\x01binary\r\n--not-it\r\n");
+        assert!(multipart.next_part().unwrap().is_none());
+        
+        fs::remove_file(&path).unwrap();
+    }
+    
+    #[test]
+    fn exact_length() {
+        //比长度长的部分被截断
+        let builder = MultipartBuilder::new()
+            .boundary("b")
+            .unwrap()
+            .reader("f", "f.txt", MediaType::new("text", "plain"), &b"abcdef"[..], Some(3));
+        let length = builder.content_length().unwrap();
+        let mut body = Vec::new();
+        builder.into_body().read_to_end(&mut body).unwrap();
+        assert_eq!(body.len() as u64, length);
+        let mut multipart = Multipart::new(&body[..], "b").unwrap();
+        assert_eq!(multipart.next_part().unwrap().unwrap().text().unwrap(), "abc");
+        
+        //比长度短时不会发送和Content-Length不一致的body
+        let request = HTTPClientResponseBuilder::new()
+            .method(HTTPClientMethod::POST)
+            .resource("/upload")
+            .build();
+        let mut buf = Vec::new();
+        let error = MultipartBuilder::new()
+            .reader("f", "f.txt", MediaType::new("text", "plain"), &b"abc"[..], Some(10))
+            .send(request, &mut buf)
+            .unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::UnexpectedEof);
+    }
+    
+    #[test]
+    fn send() {
+        let request = || HTTPClientResponseBuilder::new()
+            .method(HTTPClientMethod::POST)
+            .resource("/upload")
+            .build();
+        
+        let mut buf = Vec::new();
+        MultipartBuilder::new()
+            .text("a", "1")
+            .send(request(), &mut buf)
+            .unwrap();
+        let mut parser = HTTPClientParser::new();
+        assert!(parser.feed(&buf).unwrap().is_complete());
+        let received = parser.take().unwrap();
+        let mut multipart = Multipart::from_header(&received.body()[..], received.header()).unwrap();
+        assert_eq!(multipart.next_part().unwrap().unwrap().text().unwrap(), "1");
+        
+        //长度未知时使用chunked编码
+        let mut buf = Vec::new();
+        MultipartBuilder::new()
+            .reader("f", "f.txt", MediaType::new("text", "plain"), &b"streamed"[..], None)
+            .send(request(), &mut buf)
+            .unwrap();
+        let mut parser = HTTPClientParser::new();
+        assert!(parser.feed(&buf).unwrap().is_complete());
+        let received = parser.take().unwrap();
+        assert_eq!(received.header().get("Transfer-Encoding").unwrap(), "chunked");
+        let mut multipart = Multipart::from_header(&received.body()[..], received.header()).unwrap();
+        let mut part = multipart.next_part().unwrap().unwrap();
+        assert_eq!(part.filename().unwrap(), "f.txt");
+        assert_eq!(part.text().unwrap(), "streamed");
+    }
+}
\ No newline at end of file
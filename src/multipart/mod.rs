@@ -0,0 +1,51 @@
+//!
+//! multipart/form-data(RFC 7578)
+//!
+//! [`Multipart`]从任意的`Read`中逐个读取部分，每个部分的内容也是`Read`，不会把整个body读入内存；
+//! [`MultipartBuilder`]生成客户端的body，文件在发送时才从磁盘读取
+//!
+
+use std::io;
+use std::io::ErrorKind;
+
+pub use builder::{MultipartBody, MultipartBuilder};
+pub use reader::{Multipart, Part};
+
+pub mod builder;
+pub mod reader;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum MultipartError {
+    //Content-Type不是multipart或者没有boundary参数
+    MissingBoundary,
+    //boundary为空、超过70个字符或者包含不允许的字符
+    InvalidBoundary,
+    //分隔行后面既不是"--"也不是换行
+    InvalidDelimiter,
+    //部分的header行格式错误
+    InvalidHeader,
+    //部分的header超过限制
+    HeaderTooLarge,
+    //没有读到结束的分隔行
+    UnexpectedEof
+}
+
+impl MultipartError {
+    pub(crate) fn io(self) -> io::Error {
+        let kind = match self {
+            MultipartError::UnexpectedEof => ErrorKind::UnexpectedEof,
+            _ => ErrorKind::InvalidData
+        };
+        io::Error::new(kind, self)
+    }
+}
+
+///
+/// bchars(RFC 2046 5.1.1)，最后一个字符不能是空格
+///
+pub(crate) fn is_valid_boundary(boundary: &str) -> bool {
+    let valid_char = |b: u8| b.is_ascii_alphanumeric() || b"'()+_,-./:=? ".contains(&b);
+    (1..=70).contains(&boundary.len())
+        && boundary.bytes().all(valid_char)
+        && !boundary.ends_with(' ')
+}
\ No newline at end of file
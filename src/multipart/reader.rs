@@ -0,0 +1,377 @@
+use std::cmp;
+use std::io;
+use std::io::Read;
+
+use crate::header::typed::{ContentDisposition, ContentType, MediaType};
+use crate::map::HTTPHeadMap;
+use crate::multipart::{is_valid_boundary, MultipartError};
+
+//每个部分的header总大小
+const MAX_HEADER_SIZE: usize = 16 * 1024;
+const READ_SIZE: usize = 8 * 1024;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum State {
+    //第一个分隔行之前的内容，被丢弃
+    Preamble,
+    Content,
+    //刚读过分隔行
+    Delimiter,
+    Done
+}
+
+///
+/// multipart body的流式解析器，用`next_part`依次取出部分
+///
+/// 取下一个部分时，上一个部分没有读完的内容会被跳过
+///
+#[derive(Debug)]
+pub struct Multipart<R> {
+    reader: R,
+    //"\r\n--boundary"
+    delimiter: Vec<u8>,
+    buf: Vec<u8>,
+    pos: usize,
+    state: State
+}
+
+impl<R> Multipart<R>
+    where
+        R: Read
+{
+    pub fn new(reader: R, boundary: &str) -> Result<Self, MultipartError> {
+        if !is_valid_boundary(boundary) {
+            return Err(MultipartError::InvalidBoundary)
+        }
+        
+        //第一个分隔行前面没有换行，补上之后所有分隔行的格式都一样
+        Ok(Multipart {
+            reader,
+            delimiter: format!("\r\n--{}", boundary).into_bytes(),
+            buf: b"\r\n".to_vec(),
+            pos: 0,
+            state: State::Preamble
+        })
+    }
+    
+    ///
+    /// 从`Content-Type: multipart/form-data; boundary=...`中取出boundary
+    ///
+    pub fn from_header(reader: R, header: &HTTPHeadMap) -> Result<Self, MultipartError> {
+        let content_type = header.typed_get::<ContentType>()
+                                 .ok_or(MultipartError::MissingBoundary)?;
+        if content_type.0.main_type() != "multipart" {
+            return Err(MultipartError::MissingBoundary)
+        }
+        let boundary = content_type.0
+                                   .get_param("boundary")
+                                   .ok_or(MultipartError::MissingBoundary)?;
+        Multipart::new(reader, boundary)
+    }
+    
+    ///
+    /// 下一个部分，读到结束的分隔行之后返回None
+    ///
+    pub fn next_part(&mut self) -> io::Result<Option<Part<'_, R>>> {
+        if matches!(self.state, State::Preamble | State::Content) {
+            let mut scratch = [0; 1024];
+            while self.read_content(&mut scratch)? > 0 {}
+        }
+        if self.state == State::Done {
+            return Ok(None)
+        }
+        
+        //"--"表示结束，之后的内容被丢弃
+        self.fill_to(2)?;
+        if self.buf[self.pos..].starts_with(b"--") {
+            self.state = State::Done;
+            return Ok(None)
+        }
+        
+        let header = self.read_header()?;
+        self.state = State::Content;
+        Ok(Some(Part {
+            multipart: self,
+            header
+        }))
+    }
+    
+    fn read_header(&mut self) -> io::Result<HTTPHeadMap> {
+        let mut header = HTTPHeadMap::new();
+        let mut size = 0;
+        let mut first = true;
+        
+        loop {
+            let line_end = loop {
+                if let Some(index) = self.buf[self.pos..].iter().position(|b| *b == b'\n') {
+                    break self.pos + index
+                }
+                if size + self.buf.len() - self.pos > MAX_HEADER_SIZE {
+                    return Err(MultipartError::HeaderTooLarge.io())
+                }
+                if !self.fill()? {
+                    return Err(MultipartError::UnexpectedEof.io())
+                }
+            };
+            
+            let line = &self.buf[self.pos..line_end];
+            let line = line.strip_suffix(b"\r").unwrap_or(line);
+            size += line_end + 1 - self.pos;
+            if size > MAX_HEADER_SIZE {
+                return Err(MultipartError::HeaderTooLarge.io())
+            }
+            
+            if first {
+                //分隔行后面可以有空白(RFC 2046 transport-padding)
+                if line.iter().any(|b| *b != b' ' && *b != b'\t') {
+                    return Err(MultipartError::InvalidDelimiter.io())
+                }
+                first = false;
+            } else if line.is_empty() {
+                self.pos = line_end + 1;
+                return Ok(header)
+            } else {
+                let line = std::str::from_utf8(line)
+                    .map_err(|_| MultipartError::InvalidHeader.io())?;
+                header.try_append(line)
+                      .map_err(|_| MultipartError::InvalidHeader.io())?;
+            }
+            self.pos = line_end + 1;
+        }
+    }
+    
+    ///
+    /// 读取当前部分的内容，遇到分隔行时返回0
+    ///
+    fn read_content(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        if !matches!(self.state, State::Preamble | State::Content) || out.is_empty() {
+            return Ok(0)
+        }
+        
+        loop {
+            let available = &self.buf[self.pos..];
+            if let Some(index) = find(available, &self.delimiter) {
+                if index == 0 {
+                    self.pos += self.delimiter.len();
+                    self.state = State::Delimiter;
+                    return Ok(0)
+                }
+                let n = cmp::min(index, out.len());
+                out[..n].copy_from_slice(&available[..n]);
+                self.pos += n;
+                return Ok(n)
+            }
+            
+            //末尾可能是分隔行的开头，先保留
+            let safe = available.len().saturating_sub(self.delimiter.len() - 1);
+            if safe > 0 {
+                let n = cmp::min(safe, out.len());
+                out[..n].copy_from_slice(&available[..n]);
+                self.pos += n;
+                return Ok(n)
+            }
+            if !self.fill()? {
+                return Err(MultipartError::UnexpectedEof.io())
+            }
+        }
+    }
+    
+    fn fill_to(&mut self, len: usize) -> io::Result<()> {
+        while self.buf.len() - self.pos < len {
+            if !self.fill()? {
+                return Err(MultipartError::UnexpectedEof.io())
+            }
+        }
+        Ok(())
+    }
+    
+    ///
+    /// 丢弃已经用掉的字节并读取更多，没有更多数据时返回false
+    ///
+    fn fill(&mut self) -> io::Result<bool> {
+        self.buf.drain(..self.pos);
+        self.pos = 0;
+        
+        let len = self.buf.len();
+        self.buf.resize(len + READ_SIZE, 0);
+        let read = loop {
+            match self.reader.read(&mut self.buf[len..]) {
+                Ok(read) => break read,
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+                Err(error) => {
+                    self.buf.truncate(len);
+                    return Err(error)
+                }
+            }
+        };
+        self.buf.truncate(len + read);
+        Ok(read > 0)
+    }
+}
+
+///
+/// 一个部分，内容通过`Read`读取
+///
+#[derive(Debug)]
+pub struct Part<'a, R> {
+    multipart: &'a mut Multipart<R>,
+    header: HTTPHeadMap
+}
+
+impl<'a, R> Part<'a, R>
+    where
+        R: Read
+{
+    pub fn header(&self) -> &HTTPHeadMap {
+        &self.header
+    }
+    
+    pub fn content_disposition(&self) -> Option<ContentDisposition> {
+        self.header.typed_get::<ContentDisposition>()
+    }
+    
+    ///
+    /// Content-Disposition中的字段名
+    ///
+    pub fn name(&self) -> Option<String> {
+        self.content_disposition()?
+            .name()
+            .map(String::from)
+    }
+    
+    ///
+    /// 上传的文件名，普通字段没有
+    ///
+    pub fn filename(&self) -> Option<String> {
+        self.content_disposition()?
+            .filename()
+            .map(String::from)
+    }
+    
+    ///
+    /// 没有Content-Type时默认是`text/plain`(RFC 7578 4.4)
+    ///
+    pub fn content_type(&self) -> MediaType {
+        self.header
+            .typed_get::<ContentType>()
+            .map(|content_type| content_type.0)
+            .unwrap_or_else(|| MediaType::new("text", "plain"))
+    }
+    
+    pub fn is_file(&self) -> bool {
+        self.filename().is_some()
+    }
+    
+    ///
+    /// 读取剩下的全部内容
+    ///
+    pub fn content(&mut self) -> io::Result<Vec<u8>> {
+        let mut buf = Vec::new();
+        self.read_to_end(&mut buf)?;
+        Ok(buf)
+    }
+    
+    pub fn text(&mut self) -> io::Result<String> {
+        String::from_utf8(self.content()?)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+    }
+}
+
+impl<'a, R> Read for Part<'a, R>
+    where
+        R: Read
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.multipart.read_content(buf)
+    }
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len())
+            .position(|window| window == needle)
+}
+
+#[cfg(test)]
+mod test {
+    use std::io;
+    use std::io::{ErrorKind, Read};
+    
+    use crate::map::HTTPHeadMap;
+    use crate::multipart::{Multipart, MultipartError};
+    
+    const BODY: &[u8] = b"preamble\r\n\
+                          --XyZ\r\n\
+                          Content-Disposition: form-data; name=\"title\"\r\n\
+                          \r\n\
+                          hello\r\n\
+                          --XyZ \r\n\
+                          Content-Disposition: form-data; name=\"file\"; filename=\"a.txt\"\r\n\
+                          Content-Type: text/plain; charset=utf-8\r\n\
+                          \r\n\
+                          line 1\r\n--XyOther\r\n\
+                          \r\n\
+                          --XyZ\r\n\
+                          Content-Disposition: form-data; name=\"skipped\"\r\n\
+                          \r\n\
+                          ignored\r\n\
+                          --XyZ--\r\n\
+                          epilogue";
+    
+    ///
+    /// 每次只返回一个字节，检查跨越读取边界的分隔行
+    ///
+    struct Trickle<'a>(&'a [u8]);
+    
+    impl<'a> Read for Trickle<'a> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.0.is_empty() || buf.is_empty() {
+                return Ok(0)
+            }
+            buf[0] = self.0[0];
+            self.0 = &self.0[1..];
+            Ok(1)
+        }
+    }
+    
+    #[test]
+    fn parse() {
+        let mut header = HTTPHeadMap::new();
+        header.insert("Content-Type", "multipart/form-data; boundary=XyZ".to_string());
+        let mut multipart = Multipart::from_header(Trickle(BODY), &header).unwrap();
+        
+        let mut part = multipart.next_part().unwrap().unwrap();
+        assert_eq!(part.name().unwrap(), "title");
+        assert!(!part.is_file());
+        assert_eq!(part.content_type().essence(), "text/plain");
+        assert_eq!(part.text().unwrap(), "hello");
+        
+        let mut part = multipart.next_part().unwrap().unwrap();
+        assert_eq!(part.name().unwrap(), "file");
+        assert_eq!(part.filename().unwrap(), "a.txt");
+        assert_eq!(part.content_type().charset(), Some("utf-8"));
+        assert_eq!(part.content().unwrap(), b"line 1\r\n--XyOther\r\n");
+        
+        //没有读取的内容被跳过
+        assert_eq!(multipart.next_part().unwrap().unwrap().name().unwrap(), "skipped");
+        assert!(multipart.next_part().unwrap().is_none());
+        assert!(multipart.next_part().unwrap().is_none());
+    }
+    
+    #[test]
+    fn invalid() {
+        assert_eq!(Multipart::new(&b""[..], "").unwrap_err(), MultipartError::InvalidBoundary);
+        assert_eq!(Multipart::new(&b""[..], "a b ").unwrap_err(), MultipartError::InvalidBoundary);
+        assert_eq!(Multipart::from_header(&b""[..], &HTTPHeadMap::new()).unwrap_err(), MultipartError::MissingBoundary);
+        
+        let mut multipart = Multipart::new(&b"--b\r\nX-A: 1\r\n\r\ntruncated"[..], "b").unwrap();
+        let mut part = multipart.next_part().unwrap().unwrap();
+        assert_eq!(part.header().get("X-A").unwrap(), "1");
+        assert_eq!(part.content().unwrap_err().kind(), ErrorKind::UnexpectedEof);
+        
+        let mut multipart = Multipart::new(&b"--b garbage\r\n\r\n--b--"[..], "b").unwrap();
+        assert_eq!(multipart.next_part().unwrap_err().kind(), ErrorKind::InvalidData);
+        
+        let mut multipart = Multipart::new(&b"--b\r\nnot a header\r\n\r\n--b--"[..], "b").unwrap();
+        assert_eq!(multipart.next_part().unwrap_err().kind(), ErrorKind::InvalidData);
+    }
+}
\ No newline at end of file
@@ -0,0 +1,378 @@
+use std::mem;
+
+use crate::header::name::HeaderName;
+use crate::map::{HeaderMappingType, HTTPHeadMap, trim_ows};
+use crate::parser::{HTTPParseError, insert_field, ParseResult, ParserLimits};
+
+///
+/// Transfer-Encoding的最后一个编码是chunked，多个Transfer-Encoding字段按顺序合并之后再取最后一个(RFC 9112 6.3)
+///
+pub(crate) fn is_chunked(header: &HTTPHeadMap) -> bool {
+    match header.get_combined(HeaderName::TRANSFER_ENCODING) {
+        Some(value) => value.rsplit(',')
+                            .next()
+                            .map(|coding| coding.trim().eq_ignore_ascii_case("chunked"))
+                            .unwrap_or(false),
+        None => false
+    }
+}
+
+///
+/// 所有Transfer-Encoding字段中chunked出现的次数，chunked只能使用一次(RFC 9112 6.1)
+///
+pub(crate) fn chunked_count<'a, I>(values: I) -> usize
+    where
+        I: IntoIterator<Item = &'a [u8]>
+{
+    values.into_iter()
+          .flat_map(|value| value.split(|b| *b == b','))
+          .filter(|coding| trim_ows(coding).eq_ignore_ascii_case(b"chunked"))
+          .count()
+}
+
+pub type ChunkExtension = (String, Option<String>);
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum State {
+    //chunk-size [ chunk-ext ] CRLF
+    Size,
+    //chunk-data，还剩多少字节
+    Data(usize),
+    //chunk-data之后的CRLF
+    DataEnd,
+    //trailer-section CRLF
+    Trailer,
+    Complete
+}
+
+impl Default for State {
+    fn default() -> Self {
+        State::Size
+    }
+}
+
+///
+/// chunked编码的增量解码器
+///
+/// 解码后的body、chunk扩展以及trailer分开保存
+///
+#[derive(Clone, Debug, Default)]
+pub struct ChunkedDecoder {
+    state: State,
+    line: Vec<u8>,
+    body: Vec<u8>,
+    extensions: Vec<ChunkExtension>,
+    trailer: HTTPHeadMap,
+    trailer_count: usize,
+    trailer_size: usize,
+    //所有chunk扩展的字节数，和trailer一样受head_size_limit限制
+    extension_size: usize,
+    //解码后的body长度，只检查格式时body不保存
+    body_len: usize,
+    discard: bool,
+    size: usize,
+    //当前行或者chunk-data开始的位置
+    line_start: usize,
+    limits: ParserLimits
+}
+
+impl ChunkedDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    pub fn with_limits(limits: ParserLimits) -> Self {
+        ChunkedDecoder {
+            limits,
+            ..Self::default()
+        }
+    }
+    
+    ///
+    /// 只检查编码格式和大小限制，不保存body和chunk扩展
+    ///
+    pub(crate) fn validating(limits: ParserLimits) -> Self {
+        ChunkedDecoder {
+            limits,
+            discard: true,
+            ..Self::default()
+        }
+    }
+    
+    ///
+    /// 返回用掉的字节数，最后一个chunk之后的字节不会被使用
+    ///
+    pub fn feed(&mut self, buf: &[u8]) -> ParseResult<usize> {
+        let mut consumed = 0;
+        
+        while self.state != State::Complete && consumed < buf.len() {
+            let rest = &buf[consumed..];
+            if let State::Data(remaining) = self.state {
+                let len = remaining.min(rest.len());
+                if !self.discard {
+                    self.body.extend_from_slice(&rest[..len]);
+                }
+                self.body_len += len;
+                consumed += len;
+                self.size += len;
+                self.line_start = self.size;
+                self.state = if remaining == len {
+                    State::DataEnd
+                } else {
+                    State::Data(remaining - len)
+                };
+                continue
+            }
+            
+            let (used, end) = match rest.iter().position(|b| *b == b'\n') {
+                Some(index) => (index + 1, true),
+                None => (rest.len(), false)
+            };
+            
+            self.line.extend_from_slice(&rest[..if end { used - 1 } else { used }]);
+            if self.line.len().saturating_sub(1) > self.limits.header_line_limit() {
+                return Err(HTTPParseError::HeaderTooLarge)
+            }
+            if self.state == State::Trailer {
+                self.trailer_size += used;
+                if self.trailer_size > self.limits.head_size_limit() {
+                    return Err(HTTPParseError::HeaderTooLarge)
+                }
+            }
+            
+            consumed += used;
+            self.size += used;
+            if end {
+                self.end_line()?;
+                self.line_start = self.size;
+            }
+        }
+        
+        Ok(consumed)
+    }
+    
+    fn end_line(&mut self) -> ParseResult<()> {
+        //chunked的每一行都必须以CRLF结束，接受单独的LF会和其他实现对消息边界的理解不同
+        let mut line = mem::take(&mut self.line);
+        if line.pop() != Some(b'\r') {
+            return Err(HTTPParseError::InvalidChunk)
+        }
+        
+        match self.state {
+            State::Size => {
+                let size = self.parse_size_line(&line)?;
+                if size > self.limits.body_size_limit().saturating_sub(self.body_len) {
+                    return Err(HTTPParseError::BodyTooLarge)
+                }
+                self.state = if size == 0 {
+                    State::Trailer
+                } else {
+                    State::Data(size)
+                };
+            }
+            State::DataEnd => {
+                if !line.is_empty() {
+                    return Err(HTTPParseError::InvalidChunk)
+                }
+                self.state = State::Size;
+            }
+            State::Trailer => {
+                if line.is_empty() {
+                    self.state = State::Complete;
+                } else {
+                    self.trailer_count += 1;
+                    if self.trailer_count > self.limits.header_count_limit() {
+                        return Err(HTTPParseError::TooManyHeaders)
+                    }
+                    
+                    let tuple = line.as_slice()
+                                    .parse_key_value()
+                                    .map_err(|_| HTTPParseError::InvalidHeader)?;
+                    insert_field(&mut self.trailer, tuple)?;
+                }
+            }
+            State::Data(_) | State::Complete => {}
+        }
+        
+        Ok(())
+    }
+    
+    fn parse_size_line(&mut self, line: &[u8]) -> ParseResult<usize> {
+        let line = std::str::from_utf8(line)
+            .map_err(|_| HTTPParseError::InvalidChunk)?;
+        let (size, extensions) = match line.find(';') {
+            Some(index) => (&line[..index], &line[index + 1..]),
+            None => (line, "")
+        };
+        
+        let size = size.trim_end_matches([' ', '\t']);
+        if size.is_empty() || !size.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(HTTPParseError::InvalidChunk)
+        }
+        let size = usize::from_str_radix(size, 16)
+            .map_err(|_| HTTPParseError::InvalidChunk)?;
+        
+        if !extensions.is_empty() {
+            self.extension_size += extensions.len();
+            if self.extension_size > self.limits.head_size_limit() {
+                return Err(HTTPParseError::HeaderTooLarge)
+            }
+            let extensions = parse_extensions(extensions)?;
+            if !self.discard {
+                self.extensions.extend(extensions);
+            }
+        }
+        
+        Ok(size)
+    }
+    
+    pub fn is_complete(&self) -> bool {
+        self.state == State::Complete
+    }
+    
+    ///
+    /// 已经读取的字节数，出错时是出错的行开始的位置
+    ///
+    pub fn offset(&self) -> usize {
+        self.line_start
+    }
+    
+    pub fn body(&self) -> &Vec<u8> {
+        &self.body
+    }
+    
+    ///
+    /// 所有chunk的扩展，按照出现的顺序
+    ///
+    pub fn extensions(&self) -> &[ChunkExtension] {
+        &self.extensions
+    }
+    
+    pub fn trailer(&self) -> &HTTPHeadMap {
+        &self.trailer
+    }
+    
+    pub fn take_body(&mut self) -> Vec<u8> {
+        mem::take(&mut self.body)
+    }
+    
+    pub fn take_trailer(&mut self) -> HTTPHeadMap {
+        mem::take(&mut self.trailer)
+    }
+}
+
+///
+/// `;name=value;name="quoted value";name`，分号之后的部分
+///
+fn parse_extensions(raw: &str) -> ParseResult<Vec<ChunkExtension>> {
+    let mut extensions = Vec::new();
+    let mut chars = raw.chars().peekable();
+    
+    loop {
+        let mut name = String::new();
+        let mut value = None;
+        
+        while let Some(c) = chars.next_if(|c| *c != '=' && *c != ';') {
+            name.push(c);
+        }
+        if chars.next_if_eq(&'=').is_some() {
+            while chars.next_if(|c| *c == ' ' || *c == '\t').is_some() {}
+            let mut text = String::new();
+            if chars.next_if_eq(&'"').is_some() {
+                //quoted-string
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => text.push(chars.next().ok_or(HTTPParseError::InvalidChunk)?),
+                        Some(c) => text.push(c),
+                        None => return Err(HTTPParseError::InvalidChunk)
+                    }
+                }
+                while chars.next_if(|c| *c == ' ' || *c == '\t').is_some() {}
+            } else {
+                while let Some(c) = chars.next_if(|c| *c != ';') {
+                    text.push(c);
+                }
+                text = text.trim_end().to_string();
+            }
+            value = Some(text);
+        }
+        
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(HTTPParseError::InvalidChunk)
+        }
+        extensions.push((name.to_string(), value));
+        
+        match chars.next() {
+            Some(';') => continue,
+            None => break,
+            Some(_) => return Err(HTTPParseError::InvalidChunk)
+        }
+    }
+    
+    Ok(extensions)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::parser::chunked::ChunkedDecoder;
+    use crate::parser::{HTTPParseError, ParserLimits};
+    
+    #[test]
+    fn decode() {
+        let buf = b"5;name=\"a;b\"\r\nhello\r\n6\r\n world\r\n0\r\nExpires: never\r\n\r\nnext";
+        let mut decoder = ChunkedDecoder::new();
+        
+        let mut consumed = 0;
+        for piece in buf.chunks(3) {
+            if decoder.is_complete() {
+                break
+            }
+            consumed += decoder.feed(piece).unwrap();
+        }
+        
+        assert!(decoder.is_complete());
+        assert_eq!(&buf[consumed..], b"next");
+        assert_eq!(decoder.body(), b"hello world");
+        assert_eq!(decoder.extensions(), &[("name".to_string(), Some("a;b".to_string()))]);
+        assert_eq!(decoder.trailer().get("expires").unwrap(), "never");
+    }
+    
+    #[test]
+    fn invalid() {
+        let mut decoder = ChunkedDecoder::new();
+        assert_eq!(decoder.feed(b"x\r\n"), Err(HTTPParseError::InvalidChunk));
+        
+        let mut decoder = ChunkedDecoder::new();
+        assert_eq!(decoder.feed(b"3\r\nabcd\r\n"), Err(HTTPParseError::InvalidChunk));
+        
+        //只有LF的行
+        let mut decoder = ChunkedDecoder::new();
+        assert_eq!(decoder.feed(b"3\nabc\n0\n\n"), Err(HTTPParseError::InvalidChunk));
+        let mut decoder = ChunkedDecoder::new();
+        assert_eq!(decoder.feed(b"3\r\nabc\n0\r\n\r\n"), Err(HTTPParseError::InvalidChunk));
+        let mut decoder = ChunkedDecoder::new();
+        assert_eq!(decoder.feed(b"0\r\n\n"), Err(HTTPParseError::InvalidChunk));
+    }
+    
+    #[test]
+    fn extension_limit() {
+        let mut chunk = format!("1;{}\r\na\r\n", "x".repeat(8000)).into_bytes();
+        chunk.extend_from_slice(&chunk.clone());
+        
+        //扩展的总长度不能超过head_size_limit
+        let limits = ParserLimits::default().max_head_size(12000);
+        let mut decoder = ChunkedDecoder::with_limits(limits);
+        assert_eq!(decoder.feed(&chunk), Err(HTTPParseError::HeaderTooLarge));
+        
+        let mut decoder = ChunkedDecoder::with_limits(ParserLimits::default());
+        decoder.feed(&chunk).unwrap();
+        assert_eq!(decoder.extensions().len(), 2);
+        
+        //只检查格式时不保存扩展
+        let mut decoder = ChunkedDecoder::validating(ParserLimits::default());
+        decoder.feed(&chunk).unwrap();
+        assert!(decoder.extensions().is_empty());
+    }
+}
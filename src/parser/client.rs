@@ -0,0 +1,411 @@
+use std::mem;
+
+use crate::error::Error;
+use crate::header::method::HTTPClientMethod;
+use crate::header::name::HeaderName;
+use crate::header::version::HTTPVersion;
+use crate::map::HTTPHeadMap;
+use crate::parser::{BodyReader, check_host, HeadReader, HTTPParseError, ParseProgress, ParseResult, ParseStatus, ParserLimits};
+use crate::response::client::{HTTPClientResponse, is_valid_form};
+use crate::response::HTTPResponseBuilder;
+use crate::uri::Uri;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum State {
+    Head,
+    Body,
+    Complete
+}
+
+impl Default for State {
+    fn default() -> Self {
+        State::Head
+    }
+}
+
+///
+/// 客户端请求(HTTPClientResponse)的增量解析器
+///
+/// 每次`feed`只会用掉属于当前消息的字节，消息完成后用`take`取出，
+/// 解析器会被重置，剩下的字节可以继续喂给它
+///
+#[derive(Clone, Debug, Default)]
+pub struct HTTPClientParser {
+    state: State,
+    head: HeadReader,
+    request_line: Option<(HTTPClientMethod, String, Uri, HTTPVersion)>,
+    body: BodyReader,
+    limits: ParserLimits
+}
+
+impl HTTPClientParser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    pub fn limits(self, limits: ParserLimits) -> Self {
+        let mut this = self;
+        this.limits = limits;
+        this.head = HeadReader::new(limits);
+        this
+    }
+    
+    ///
+    /// 和`feed`相同，错误带上出错的位置
+    ///
+    pub fn try_feed(&mut self, buf: &[u8]) -> crate::error::Result<ParseProgress> {
+        self.feed(buf)
+            .map_err(|error| Error::parse_at(error, self.offset()))
+    }
+    
+    pub fn feed(&mut self, buf: &[u8]) -> ParseResult<ParseProgress> {
+        let mut consumed = 0;
+        
+        if self.state == State::Head {
+            consumed += self.head.feed(buf)?;
+            if self.head.is_complete() {
+                let request_line = Self::parse_request_line(self.head.start_line())
+                    .map_err(|error| {
+                        let token = match error {
+                            HTTPParseError::InvalidTarget => 1,
+                            HTTPParseError::InvalidVersion => 2,
+                            _ => 0
+                        };
+                        self.head.start_line_error(error, token)
+                    })?;
+                //请求没有Content-Length和Transfer-Encoding就没有body
+                self.body = BodyReader::from_header(self.head.header(), true, self.limits)
+                    .map_err(|error| self.head.field_error(error))?
+                    .unwrap_or_default();
+                check_host(request_line.3, self.head.header().get_all(HeaderName::HOST).len())
+                    .map_err(|error| self.head.field_error(error))?;
+                self.request_line = Some(request_line);
+                self.state = State::Body;
+            }
+        }
+        
+        if self.state == State::Body {
+            consumed += self.body.feed(&buf[consumed..])?;
+            if self.body.is_complete() {
+                self.state = State::Complete;
+            }
+        }
+        
+        Ok(ParseProgress::new(self.status(), consumed))
+    }
+    
+    fn parse_request_line(line: &str) -> ParseResult<(HTTPClientMethod, String, Uri, HTTPVersion)> {
+        //三个部分之间正好是一个空格(RFC 9112 3)，和RequestView相同
+        let mut sp = line.split(' ');
+        let (method, resource, version) = match (sp.next(), sp.next(), sp.next(), sp.next()) {
+            (Some(method), Some(resource), Some(version), None) if !resource.is_empty() => (method, resource, version),
+            _ => return Err(HTTPParseError::InvalidStartLine)
+        };
+        
+        let method = HTTPClientMethod::from(method)
+            .map_err(|_| HTTPParseError::InvalidMethod)?;
+        let uri = Uri::parse_target(resource)
+            .ok()
+            .filter(|uri| is_valid_form(method.as_str(), uri.form()))
+            .ok_or(HTTPParseError::InvalidTarget)?;
+        let resource = resource.to_string();
+        let version = HTTPVersion::from(version)
+            .map_err(|_| HTTPParseError::InvalidVersion)?;
+        
+        Ok((method, resource, uri, version))
+    }
+    
+    pub fn status(&self) -> ParseStatus {
+        match self.state {
+            State::Head => ParseStatus::Partial,
+            State::Body => ParseStatus::HeadersComplete,
+            State::Complete => ParseStatus::Complete
+        }
+    }
+    
+    pub fn is_complete(&self) -> bool {
+        self.state == State::Complete
+    }
+    
+    ///
+    /// 当前消息已经读取的字节数，`feed`出错之后是出错的行或者起始行中出错的部分开始的位置，
+    /// `try_feed`返回的错误已经带上了这个位置
+    ///
+    pub fn offset(&self) -> usize {
+        match self.state {
+            State::Head => self.head.offset(),
+            _ => self.head.offset() + self.body.offset()
+        }
+    }
+    
+    ///
+    /// header读取完毕之后可用
+    ///
+    pub fn header(&self) -> Option<&HTTPHeadMap> {
+        if self.state == State::Head {
+            None
+        } else {
+            Some(self.head.header())
+        }
+    }
+    
+    ///
+    /// 取出解析完成的消息并重置解析器，消息未完成时返回None
+    ///
+    pub fn take(&mut self) -> Option<HTTPClientResponse> {
+        if !self.is_complete() {
+            return None
+        }
+        
+        let mut this = mem::replace(self, Self::new().limits(self.limits));
+        let (method, resource, uri, version) = this.request_line.take()?;
+        let mut response = HTTPResponseBuilder::new(
+            version,
+            this.head.take_header(),
+            this.body.take_body()
+        );
+        *response.trailer_mut() = this.body.take_trailer();
+        
+        Some(HTTPClientResponse::with_uri(response, method, resource, uri))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::header::method::HTTPClientMethod;
+    use crate::parser::{BodyReader, HTTPParseError, ParserLimits, ParseStatus};
+    use crate::parser::client::HTTPClientParser;
+    use crate::parser::view::RequestView;
+    
+    #[test]
+    fn start_line() {
+        //两个请求解析器对请求行的理解必须相同，否则代理和后端看到的是不同的请求
+        let lines: [&[u8]; 6] = [
+            b"GET / HTTP/1.1\r\nHost: a\r\n\r\n",
+            b"GET\t/\tHTTP/1.1\r\nHost: a\r\n\r\n",
+            b"GET  /  HTTP/1.1\r\nHost: a\r\n\r\n",
+            b"GET / HTTP/1.1 \r\nHost: a\r\n\r\n",
+            b" GET / HTTP/1.1\r\nHost: a\r\n\r\n",
+            b"GET /\x0bHTTP/1.1\r\nHost: a\r\n\r\n"
+        ];
+        for (index, raw) in lines.iter().enumerate() {
+            let parser = HTTPClientParser::new().feed(raw);
+            let view = RequestView::parse(raw);
+            assert_eq!(parser.is_ok(), view.is_ok(), "{:?}", String::from_utf8_lossy(raw));
+            assert_eq!(parser.is_ok(), index == 0);
+        }
+        assert_eq!(HTTPClientParser::new().feed(b"GET  /  HTTP/1.1\r\n\r\n").unwrap_err(), HTTPParseError::InvalidStartLine);
+    }
+    
+    #[test]
+    fn target_form() {
+        let accepted = |line: &str| {
+            let raw = format!("{}\r\nHost: a\r\n\r\n", line);
+            let parser = HTTPClientParser::new().feed(raw.as_bytes());
+            let view = RequestView::parse(raw.as_bytes());
+            assert_eq!(parser.is_ok(), view.is_ok(), "{}", line);
+            if let Err(error) = parser {
+                assert_eq!(error, HTTPParseError::InvalidTarget);
+                assert_eq!(view.unwrap_err(), HTTPParseError::InvalidTarget);
+            }
+            parser.is_ok()
+        };
+        assert!(accepted("CONNECT example.com:443 HTTP/1.1"));
+        assert!(accepted("OPTIONS * HTTP/1.1"));
+        assert!(accepted("OPTIONS /a HTTP/1.1"));
+        assert!(accepted("GET http://example.com/ HTTP/1.1"));
+        //authority-form只能用于CONNECT，asterisk-form只能用于OPTIONS
+        assert!(!accepted("GET example.com:80 HTTP/1.1"));
+        assert!(!accepted("DELETE * HTTP/1.1"));
+        assert!(!accepted("CONNECT /x HTTP/1.1"));
+        assert!(!accepted("CONNECT http://example.com/ HTTP/1.1"));
+    }
+    
+    #[test]
+    fn host() {
+        let parse = |raw: &[u8]| {
+            let parser = HTTPClientParser::new().feed(raw).map(|_| ());
+            let view = RequestView::parse(raw).map(|_| ());
+            assert_eq!(parser, view);
+            parser
+        };
+        assert_eq!(parse(b"GET / HTTP/1.1\r\nHost: a\r\n\r\n"), Ok(()));
+        //HTTP/1.0的请求可以没有Host
+        assert_eq!(parse(b"GET / HTTP/1.0\r\n\r\n"), Ok(()));
+        assert_eq!(parse(b"GET / HTTP/1.1\r\n\r\n"), Err(HTTPParseError::MissingHost));
+        assert_eq!(parse(b"GET / HTTP/1.1\r\nHost: a\r\nHost: b\r\n\r\n"), Err(HTTPParseError::DuplicateHost));
+        assert_eq!(parse(b"GET / HTTP/1.0\r\nHost: a\r\nhost: a\r\n\r\n"), Err(HTTPParseError::DuplicateHost));
+        assert_eq!(HTTPParseError::MissingHost.status_code(), 400);
+        
+        //chunked只能使用一次，无论是在一个字段还是分开的字段
+        assert_eq!(parse(b"POST / HTTP/1.1\r\nHost: a\r\nTransfer-Encoding: chunked, chunked\r\n\r\n"), Err(HTTPParseError::RepeatedChunked));
+        assert_eq!(parse(b"POST / HTTP/1.1\r\nHost: a\r\nTransfer-Encoding: chunked\r\nTransfer-Encoding: chunked\r\n\r\n"), Err(HTTPParseError::RepeatedChunked));
+    }
+    
+    #[test]
+    fn obs_text() {
+        //obs-text不会导致请求被拒绝，两个解析器得到相同的值
+        let raw = b"GET / HTTP/1.1\r\nHost: a\r\nX-Name: caf\xe9\r\n\r\n";
+        let mut parser = HTTPClientParser::new();
+        assert!(parser.feed(raw).unwrap().is_complete());
+        let parsed = parser.take().unwrap();
+        let (view, _) = RequestView::parse(raw).unwrap().unwrap();
+        let owned = view.into_owned().unwrap();
+        assert_eq!(parsed.header().get("X-Name").unwrap(), "caf\u{e9}");
+        assert_eq!(owned.header().get("X-Name"), parsed.header().get("X-Name"));
+    }
+    
+    #[test]
+    fn feed_in_pieces() {
+        let mut parser = HTTPClientParser::new();
+        
+        let progress = parser.feed(b"POST /api HT").unwrap();
+        assert_eq!(progress.status(), ParseStatus::Partial);
+        assert_eq!(progress.consumed(), 12);
+        
+        let progress = parser.feed(b"TP/1.1\r\nHost: 127.0.0.1\r\nContent-Length: 5\r\n\r\nab").unwrap();
+        assert_eq!(progress.status(), ParseStatus::HeadersComplete);
+        assert_eq!(parser.header().unwrap().get("host").unwrap(), "127.0.0.1");
+        
+        let progress = parser.feed(b"cde").unwrap();
+        assert_eq!(progress.status(), ParseStatus::Complete);
+        assert_eq!(progress.consumed(), 3);
+        
+        let request = parser.take().unwrap();
+        assert!(matches!(request.method(), HTTPClientMethod::POST));
+        assert_eq!(request.resource(), "/api");
+        assert_eq!(request.uri().unwrap().path(), "/api");
+        assert_eq!(request.body(), b"abcde");
+    }
+    
+    #[test]
+    fn fields() {
+        let mut parser = HTTPClientParser::new();
+        parser.feed(b"GET / HTTP/1.1\r\nHost: x\r\nUser-Agent: Mozilla/5.0 (X11; Linux)\r\nX-Folded: a\r\n\tb\r\n\r\n").unwrap();
+        let request = parser.take().unwrap();
+        assert_eq!(request.header().get("User-Agent").unwrap(), "Mozilla/5.0 (X11; Linux)");
+        assert_eq!(request.header().get("X-Folded").unwrap(), "a b");
+        
+        let mut parser = HTTPClientParser::new();
+        assert_eq!(parser.feed(b"GET / HTTP/1.1\r\n Host: x\r\n\r\n"), Err(HTTPParseError::InvalidHeader));
+        
+        let mut parser = HTTPClientParser::new();
+        assert_eq!(parser.feed(b"GET / HTTP/1.1\r\nHost : x\r\n\r\n"), Err(HTTPParseError::InvalidHeader));
+    }
+    
+    #[test]
+    fn chunked() {
+        let mut parser = HTTPClientParser::new();
+        let progress = parser.feed(b"POST / HTTP/1.1\r\nHost: x\r\nTransfer-Encoding: chunked\r\n\r\n3\r\nabc\r\n0\r\nDigest: x\r\n\r\n").unwrap();
+        assert!(progress.is_complete());
+        
+        let request = parser.take().unwrap();
+        assert_eq!(request.body(), b"abc");
+        assert_eq!(request.trailer().get("Digest").unwrap(), "x");
+    }
+    
+    #[test]
+    fn limits() {
+        let limits = ParserLimits::new()
+            .max_start_line(16)
+            .max_header_count(2)
+            .max_body_size(4);
+        
+        let mut parser = HTTPClientParser::new().limits(limits);
+        let error = parser.feed(b"GET /very/long/target").unwrap_err();
+        assert_eq!(error, HTTPParseError::StartLineTooLong);
+        assert_eq!(error.status_code(), 414);
+        
+        let mut parser = HTTPClientParser::new().limits(limits);
+        let error = parser.feed(b"GET / HTTP/1.1\r\nA: 1\r\nB: 2\r\nC: 3\r\n\r\n").unwrap_err();
+        assert_eq!(error.status_code(), 431);
+        
+        let mut parser = HTTPClientParser::new().limits(limits);
+        let error = parser.feed(b"POST / HTTP/1.1\r\nHost: x\r\nContent-Length: 5\r\n\r\n").unwrap_err();
+        assert_eq!(error.status_code(), 413);
+        
+        //只发送header不会让解析器按照Content-Length分配内存
+        assert!(BodyReader::with_length(16 * 1024 * 1024).body.capacity() <= 8 * 1024);
+        
+        let mut parser = HTTPClientParser::new().limits(limits);
+        let error = parser.feed(b"POST / HTTP/1.1\r\nHost: x\r\nTransfer-Encoding: chunked\r\n\r\n3\r\nabc\r\n3\r\nabc\r\n").unwrap_err();
+        assert_eq!(error, HTTPParseError::BodyTooLarge);
+        
+        //重置之后仍然使用同样的限制
+        let mut parser = HTTPClientParser::new().limits(limits);
+        parser.feed(b"GET / HTTP/1.1\r\nHost: x\r\n\r\n").unwrap();
+        parser.take().unwrap();
+        assert!(parser.feed(b"GET /very/long/target").is_err());
+    }
+    
+    #[test]
+    fn smuggling() {
+        let mut parser = HTTPClientParser::new();
+        let error = parser.feed(b"POST / HTTP/1.1\r\nContent-Length: 3\r\nTransfer-Encoding: chunked\r\n\r\n").unwrap_err();
+        assert_eq!(error, HTTPParseError::ConflictingLength);
+        assert_eq!(error.status_code(), 400);
+        
+        let mut parser = HTTPClientParser::new();
+        let error = parser.feed(b"POST / HTTP/1.1\r\nContent-Length: 3\r\nContent-Length: 4\r\n\r\n").unwrap_err();
+        assert_eq!(error, HTTPParseError::DuplicateContentLength);
+        
+        let mut parser = HTTPClientParser::new();
+        let error = parser.feed(b"POST / HTTP/1.1\r\nContent-Length: +3\r\n\r\n").unwrap_err();
+        assert_eq!(error, HTTPParseError::InvalidContentLength);
+        
+        let mut parser = HTTPClientParser::new();
+        let error = parser.feed(b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked, gzip\r\n\r\n").unwrap_err();
+        assert_eq!(error, HTTPParseError::UnsupportedTransferEncoding);
+        
+        //分开的字段按顺序合并，最后的编码是gzip
+        let mut parser = HTTPClientParser::new();
+        let error = parser.feed(b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\nTransfer-Encoding: gzip\r\n\r\n0\r\n\r\n").unwrap_err();
+        assert_eq!(error, HTTPParseError::UnsupportedTransferEncoding);
+        assert_eq!(error.status_code(), 400);
+        
+        let mut parser = HTTPClientParser::new();
+        let progress = parser.feed(b"POST / HTTP/1.1\r\nHost: x\r\nTransfer-Encoding: gzip\r\nTransfer-Encoding: chunked\r\n\r\n0\r\n\r\n").unwrap();
+        assert!(progress.is_complete());
+        
+        let mut parser = HTTPClientParser::new();
+        let progress = parser.feed(b"POST / HTTP/1.1\r\nHost: x\r\nContent-Length: 3\r\nContent-Length: 3\r\n\r\nabc").unwrap();
+        assert!(progress.is_complete());
+    }
+    
+    #[test]
+    fn pipelined() {
+        let buf = b"GET /a HTTP/1.1\r\nHost: x\r\n\r\nGET /b HTTP/1.1\r\nHost: x\r\n\r\n";
+        let mut parser = HTTPClientParser::new();
+        
+        let progress = parser.feed(buf).unwrap();
+        assert!(progress.is_complete());
+        assert_eq!(parser.take().unwrap().resource(), "/a");
+        
+        let rest = &buf[progress.consumed()..];
+        let progress = parser.feed(rest).unwrap();
+        assert!(progress.is_complete());
+        assert_eq!(progress.consumed(), rest.len());
+        assert_eq!(parser.take().unwrap().resource(), "/b");
+    }
+    
+    #[test]
+    fn invalid() {
+        let mut parser = HTTPClientParser::new();
+        assert_eq!(parser.feed(b"GET(x) / HTTP/1.1\r\n\r\n"), Err(HTTPParseError::InvalidMethod));
+        
+        let mut parser = HTTPClientParser::new();
+        parser.feed(b"PURGE /cache HTTP/1.1\r\nHost: x\r\n\r\n").unwrap();
+        assert_eq!(parser.take().unwrap().method().as_str(), "PURGE");
+        
+        let mut parser = HTTPClientParser::new();
+        assert_eq!(parser.feed(b"GET / HTTP/1.1\r\nContent-Length: x\r\n\r\n"), Err(HTTPParseError::InvalidContentLength));
+        assert!(parser.take().is_none());
+        
+        let mut parser = HTTPClientParser::new();
+        assert_eq!(parser.feed(b"GET /a%zz HTTP/1.1\r\n\r\n"), Err(HTTPParseError::InvalidTarget));
+        
+        let mut parser = HTTPClientParser::new();
+        parser.feed(b"CONNECT example.com:443 HTTP/1.1\r\nHost: example.com:443\r\n\r\n").unwrap();
+        assert_eq!(parser.take().unwrap().resource(), "example.com:443");
+    }
+}
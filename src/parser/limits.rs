@@ -0,0 +1,104 @@
+///
+/// 解析器的大小限制，默认值适合直接暴露在公网上的服务器
+///
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ParserLimits {
+    max_start_line: usize,
+    max_header_line: usize,
+    max_header_count: usize,
+    max_head_size: usize,
+    max_body_size: usize
+}
+
+impl Default for ParserLimits {
+    fn default() -> Self {
+        ParserLimits {
+            max_start_line: 8 * 1024,
+            max_header_line: 8 * 1024,
+            max_header_count: 100,
+            max_head_size: 64 * 1024,
+            max_body_size: 16 * 1024 * 1024
+        }
+    }
+}
+
+impl ParserLimits {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    ///
+    /// 没有任何限制，只应该用于可信的对端
+    ///
+    pub fn unlimited() -> Self {
+        ParserLimits {
+            max_start_line: usize::MAX,
+            max_header_line: usize::MAX,
+            max_header_count: usize::MAX,
+            max_head_size: usize::MAX,
+            max_body_size: usize::MAX
+        }
+    }
+    
+    ///
+    /// 请求行或者状态行的最大长度，不包括CRLF
+    ///
+    pub fn max_start_line(self, max: usize) -> Self {
+        let mut this = self;
+        this.max_start_line = max;
+        this
+    }
+    
+    ///
+    /// 单个header行(以及chunk-size行)的最大长度，不包括CRLF
+    ///
+    pub fn max_header_line(self, max: usize) -> Self {
+        let mut this = self;
+        this.max_header_line = max;
+        this
+    }
+    
+    pub fn max_header_count(self, max: usize) -> Self {
+        let mut this = self;
+        this.max_header_count = max;
+        this
+    }
+    
+    ///
+    /// 起始行加上所有header的最大字节数，trailer也使用这个限制
+    ///
+    pub fn max_head_size(self, max: usize) -> Self {
+        let mut this = self;
+        this.max_head_size = max;
+        this
+    }
+    
+    ///
+    /// 解码之后body的最大字节数
+    ///
+    pub fn max_body_size(self, max: usize) -> Self {
+        let mut this = self;
+        this.max_body_size = max;
+        this
+    }
+    
+    pub fn start_line_limit(&self) -> usize {
+        self.max_start_line
+    }
+    
+    pub fn header_line_limit(&self) -> usize {
+        self.max_header_line
+    }
+    
+    pub fn header_count_limit(&self) -> usize {
+        self.max_header_count
+    }
+    
+    pub fn head_size_limit(&self) -> usize {
+        self.max_head_size
+    }
+    
+    pub fn body_size_limit(&self) -> usize {
+        self.max_body_size
+    }
+}
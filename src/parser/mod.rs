@@ -0,0 +1,553 @@
+//!
+//! 增量式HTTP/1.x解析器
+//!
+//! 可以分多次喂入从socket读取到的字节，每次返回解析进度以及用掉的字节数，
+//! 没有用掉的字节属于下一个消息(pipelining)
+//!
+
+use std::mem;
+
+use crate::header::method::{HTTPServerMethod, ServerMethodCode};
+use crate::header::name::HeaderName;
+use crate::header::version::HTTPVersion;
+use crate::map::{HeaderMappingType, HTTPHeadKey, HTTPHeadMap, HTTPHeadValue, trim_ows};
+use crate::parser::chunked::{ChunkedDecoder, chunked_count, is_chunked};
+
+pub use limits::ParserLimits;
+
+pub mod chunked;
+pub mod client;
+pub mod server;
+pub mod limits;
+pub mod view;
+
+//按照Content-Length预先分配的body容量上限
+const INITIAL_BODY_CAPACITY: usize = 8 * 1024;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum HTTPParseError {
+    //起始行格式错误
+    InvalidStartLine,
+    //未知的方法
+    InvalidMethod,
+    //请求目标不是合法的URI(RFC 9112 3.2)
+    InvalidTarget,
+    //状态码不是三位数字
+    InvalidStatus,
+    //未知的HTTP版本
+    InvalidVersion,
+    //header行格式错误
+    InvalidHeader,
+    //Content-Length不是合法的长度
+    InvalidContentLength,
+    //chunked编码格式错误
+    InvalidChunk,
+    //消息没有读取完连接就关闭了
+    UnexpectedEof,
+    //起始行超过限制
+    StartLineTooLong,
+    //header行数超过限制
+    TooManyHeaders,
+    //单个header行或者header总大小超过限制
+    HeaderTooLarge,
+    //body超过限制
+    BodyTooLarge,
+    //同时存在Content-Length和Transfer-Encoding
+    ConflictingLength,
+    //多个不同的Content-Length
+    DuplicateContentLength,
+    //请求的Transfer-Encoding最后不是chunked
+    UnsupportedTransferEncoding,
+    //Transfer-Encoding中chunked出现了不止一次
+    RepeatedChunked,
+    //HTTP/1.1的请求没有Host
+    MissingHost,
+    //请求有多个Host
+    DuplicateHost
+}
+
+impl HTTPParseError {
+    ///
+    /// 服务器拒绝这个请求时应该使用的状态码(RFC 9112)
+    ///
+    pub fn status_code(&self) -> ServerMethodCode {
+        match self {
+            HTTPParseError::StartLineTooLong => 414,
+            HTTPParseError::TooManyHeaders | HTTPParseError::HeaderTooLarge => 431,
+            HTTPParseError::BodyTooLarge => 413,
+            _ => 400
+        }
+    }
+    
+    pub fn server_method(&self) -> HTTPServerMethod {
+        let reason = match self.status_code() {
+            414 => "URI Too Long",
+            431 => "Request Header Fields Too Large",
+            413 => "Content Too Large",
+            _ => "Bad Request"
+        };
+        HTTPServerMethod::from_code(self.status_code(), reason)
+    }
+}
+
+pub type ParseResult<T> = Result<T, HTTPParseError>;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ParseStatus {
+    //需要更多数据
+    Partial,
+    //header已经读取完毕，body还没有读完
+    HeadersComplete,
+    //整个消息读取完毕
+    Complete
+}
+
+///
+/// 一次`feed`的结果
+///
+/// `consumed`之后的字节没有被解析器使用，需要留给下一个消息
+///
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ParseProgress {
+    status: ParseStatus,
+    consumed: usize
+}
+
+impl ParseProgress {
+    pub fn new(status: ParseStatus, consumed: usize) -> Self {
+        ParseProgress {
+            status,
+            consumed
+        }
+    }
+    
+    pub fn status(&self) -> ParseStatus {
+        self.status
+    }
+    
+    pub fn consumed(&self) -> usize {
+        self.consumed
+    }
+    
+    pub fn is_complete(&self) -> bool {
+        self.status == ParseStatus::Complete
+    }
+}
+
+///
+/// 读取起始行以及header，直到遇到空行
+///
+#[derive(Clone, Debug, Default)]
+pub(crate) struct HeadReader {
+    line: Vec<u8>,
+    start_line: Option<String>,
+    start_line_start: usize,
+    header: HTTPHeadMap,
+    //每个header行的名字和开始的位置，用来定位根据header得出的错误
+    fields: Vec<(HTTPHeadKey, usize)>,
+    //还没有插入的header行，后面可能还有obs-fold
+    field: Option<Vec<u8>>,
+    field_start: usize,
+    header_count: usize,
+    size: usize,
+    //当前行开始的位置
+    line_start: usize,
+    complete: bool,
+    limits: ParserLimits
+}
+
+impl HeadReader {
+    pub(crate) fn new(limits: ParserLimits) -> Self {
+        HeadReader {
+            limits,
+            ..Self::default()
+        }
+    }
+    
+    pub(crate) fn feed(&mut self, buf: &[u8]) -> ParseResult<usize> {
+        let mut consumed = 0;
+        
+        while !self.complete && consumed < buf.len() {
+            let rest = &buf[consumed..];
+            let (used, end) = match rest.iter().position(|b| *b == b'\n') {
+                Some(index) => (index + 1, true),
+                //半行，等待下一次feed
+                None => (rest.len(), false)
+            };
+            
+            self.line.extend_from_slice(&rest[..if end { used - 1 } else { used }]);
+            self.check_line()?;
+            
+            consumed += used;
+            self.size += used;
+            if self.size > self.limits.head_size_limit() {
+                return Err(HTTPParseError::HeaderTooLarge)
+            }
+            
+            if end {
+                self.end_line()?;
+                self.line_start = self.size;
+            }
+        }
+        
+        Ok(consumed)
+    }
+    
+    ///
+    /// 已经读取的字节数，出错时是出错的行开始的位置
+    ///
+    pub(crate) fn offset(&self) -> usize {
+        self.line_start
+    }
+    
+    fn check_line(&self) -> ParseResult<()> {
+        //CR还没有去掉，多留一个字节
+        let len = self.line.len().saturating_sub(1);
+        if self.start_line.is_none() {
+            if len > self.limits.start_line_limit() {
+                return Err(HTTPParseError::StartLineTooLong)
+            }
+        } else if len > self.limits.header_line_limit() {
+            return Err(HTTPParseError::HeaderTooLarge)
+        }
+        Ok(())
+    }
+    
+    fn end_line(&mut self) -> ParseResult<()> {
+        let mut line = mem::take(&mut self.line);
+        if line.last() == Some(&b'\r') {
+            line.pop();
+        }
+        
+        if self.start_line.is_none() {
+            //起始行之前的空行忽略
+            if line.is_empty() {
+                return Ok(())
+            }
+            let line = String::from_utf8(line)
+                .map_err(|_| HTTPParseError::InvalidStartLine)?;
+            self.start_line = Some(line);
+            self.start_line_start = self.line_start;
+        } else if line.is_empty() {
+            self.insert_field()?;
+            self.complete = true;
+        } else if line[0] == b' ' || line[0] == b'\t' {
+            //obs-fold，替换为一个SP接到上一行后面
+            let field = self.field
+                            .as_mut()
+                            .ok_or(HTTPParseError::InvalidHeader)?;
+            field.push(b' ');
+            field.extend_from_slice(trim_ows(&line));
+        } else {
+            self.insert_field()?;
+            
+            self.header_count += 1;
+            if self.header_count > self.limits.header_count_limit() {
+                return Err(HTTPParseError::TooManyHeaders)
+            }
+            self.field = Some(line);
+            self.field_start = self.line_start;
+        }
+        
+        Ok(())
+    }
+    
+    fn insert_field(&mut self) -> ParseResult<()> {
+        if let Some(field) = self.field.take() {
+            //出错的位置是header行开始的位置，而不是后面的行
+            let result = field.as_slice()
+                              .parse_key_value()
+                              .map_err(|_| HTTPParseError::InvalidHeader)
+                              .and_then(|tuple| {
+                                  let name = tuple.0.clone();
+                                  insert_field(&mut self.header, tuple).map(|_| name)
+                              });
+            match result {
+                Ok(name) => self.fields.push((name, self.field_start)),
+                Err(error) => {
+                    self.line_start = self.field_start;
+                    return Err(error)
+                }
+            }
+        }
+        Ok(())
+    }
+    
+    pub(crate) fn is_complete(&self) -> bool {
+        self.complete
+    }
+    
+    ///
+    /// 起始行的第`token`个部分出错，`offset`指向这个部分
+    ///
+    pub(crate) fn start_line_error(&mut self, error: HTTPParseError, token: usize) -> HTTPParseError {
+        self.line_start = self.start_line_start + token_offset(self.start_line(), token);
+        error
+    }
+    
+    ///
+    /// 根据header决定body长度时出错，`offset`指向引起错误的header行
+    ///
+    pub(crate) fn field_error(&mut self, error: HTTPParseError) -> HTTPParseError {
+        let first = |name: HeaderName| self.fields
+                                           .iter()
+                                           .find(|(key, _)| *key == name)
+                                           .map(|(_, offset)| *offset);
+        let offset = match error {
+            //后出现的那一个造成冲突
+            HTTPParseError::ConflictingLength => first(HeaderName::CONTENT_LENGTH).max(first(HeaderName::TRANSFER_ENCODING)),
+            HTTPParseError::UnsupportedTransferEncoding
+            | HTTPParseError::RepeatedChunked => self.fields
+                                                      .iter()
+                                                      .rev()
+                                                      .find(|(key, _)| *key == HeaderName::TRANSFER_ENCODING)
+                                                      .map(|(_, offset)| *offset),
+            HTTPParseError::InvalidContentLength
+            | HTTPParseError::DuplicateContentLength
+            | HTTPParseError::BodyTooLarge => first(HeaderName::CONTENT_LENGTH),
+            //第二个Host
+            HTTPParseError::DuplicateHost => self.fields
+                                                 .iter()
+                                                 .filter(|(key, _)| *key == HeaderName::HOST)
+                                                 .nth(1)
+                                                 .map(|(_, offset)| *offset),
+            _ => None
+        };
+        if let Some(offset) = offset {
+            self.line_start = offset;
+        }
+        error
+    }
+    
+    pub(crate) fn start_line(&self) -> &str {
+        self.start_line.as_deref().unwrap_or_default()
+    }
+    
+    pub(crate) fn header(&self) -> &HTTPHeadMap {
+        &self.header
+    }
+    
+    pub(crate) fn take_header(&mut self) -> HTTPHeadMap {
+        mem::take(&mut self.header)
+    }
+}
+
+///
+/// 第`index`个空白分隔的部分在行中开始的位置，不存在时是行尾
+///
+fn token_offset(line: &str, index: usize) -> usize {
+    let mut count = 0;
+    let mut after_space = true;
+    for (offset, c) in line.char_indices() {
+        let space = c.is_whitespace();
+        if after_space && !space {
+            if count == index {
+                return offset
+            }
+            count += 1;
+        }
+        after_space = space;
+    }
+    line.len()
+}
+
+///
+/// 添加一个header，拒绝值不相同的重复Content-Length
+///
+pub(crate) fn insert_field(header: &mut HTTPHeadMap, tuple: (HTTPHeadKey, HTTPHeadValue)) -> ParseResult<()> {
+    if tuple.0 == HeaderName::CONTENT_LENGTH {
+        if let Some(value) = header.get(HeaderName::CONTENT_LENGTH) {
+            if parse_content_length(&value)? != parse_content_length(&tuple.1)? {
+                return Err(HTTPParseError::DuplicateContentLength)
+            }
+            return Ok(())
+        }
+    }
+    header.append_tuple(tuple);
+    Ok(())
+}
+
+///
+/// HTTP/1.1的请求必须有且只有一个Host(RFC 9112 3.2)，HTTP/1.0可以没有
+///
+pub(crate) fn check_host(version: HTTPVersion, hosts: usize) -> ParseResult<()> {
+    match hosts {
+        0 if version >= HTTPVersion::HTTP1_1 => Err(HTTPParseError::MissingHost),
+        0 | 1 => Ok(()),
+        _ => Err(HTTPParseError::DuplicateHost)
+    }
+}
+
+///
+/// `Content-Length: 42`，或者值都相同的列表`Content-Length: 42, 42`
+///
+pub(crate) fn parse_content_length(value: &str) -> ParseResult<usize> {
+    let mut length = None;
+    for item in value.split(',') {
+        let item = item.trim();
+        if item.is_empty() || !item.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(HTTPParseError::InvalidContentLength)
+        }
+        let item = item.parse::<usize>()
+                       .map_err(|_| HTTPParseError::InvalidContentLength)?;
+        match length {
+            Some(length) if length != item => return Err(HTTPParseError::DuplicateContentLength),
+            _ => length = Some(item)
+        }
+    }
+    length.ok_or(HTTPParseError::InvalidContentLength)
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum BodyLength {
+    //还剩多少字节
+    Fixed(usize),
+    //一直读取到连接关闭
+    UntilClose,
+    //Transfer-Encoding: chunked
+    Chunked
+}
+
+impl Default for BodyLength {
+    fn default() -> Self {
+        BodyLength::Fixed(0)
+    }
+}
+
+///
+/// 按照Content-Length、chunked或者连接关闭读取body
+///
+#[derive(Clone, Debug, Default)]
+pub(crate) struct BodyReader {
+    length: BodyLength,
+    body: Vec<u8>,
+    decoder: ChunkedDecoder,
+    limits: ParserLimits
+}
+
+impl BodyReader {
+    pub(crate) fn with_length(length: usize) -> Self {
+        //Content-Length来自对端，在body真正到达之前只预留一小部分
+        BodyReader {
+            length: BodyLength::Fixed(length),
+            body: Vec::with_capacity(length.min(INITIAL_BODY_CAPACITY)),
+            ..Self::default()
+        }
+    }
+    
+    pub(crate) fn until_close(limits: ParserLimits) -> Self {
+        BodyReader {
+            length: BodyLength::UntilClose,
+            limits,
+            ..Self::default()
+        }
+    }
+    
+    pub(crate) fn chunked(limits: ParserLimits) -> Self {
+        BodyReader {
+            length: BodyLength::Chunked,
+            decoder: ChunkedDecoder::with_limits(limits),
+            limits,
+            ..Self::default()
+        }
+    }
+    
+    ///
+    /// 按照header决定body的长度(RFC 9112 6.3)，两者都没有时返回None
+    ///
+    /// 同时存在Transfer-Encoding和Content-Length的消息可能是请求走私，直接拒绝；
+    /// 请求的Transfer-Encoding最后不是chunked时无法确定长度，也拒绝，响应则读取到连接关闭
+    ///
+    pub(crate) fn from_header(header: &HTTPHeadMap, request: bool, limits: ParserLimits) -> ParseResult<Option<Self>> {
+        let content_length = match header.get(HeaderName::CONTENT_LENGTH) {
+            Some(value) => Some(parse_content_length(&value)?),
+            None => None
+        };
+        
+        if header.contains_key(HeaderName::TRANSFER_ENCODING) {
+            if content_length.is_some() {
+                return Err(HTTPParseError::ConflictingLength)
+            }
+            let codings = header.get_all(HeaderName::TRANSFER_ENCODING);
+            if chunked_count(codings.iter().map(|value| value.as_bytes())) > 1 {
+                return Err(HTTPParseError::RepeatedChunked)
+            }
+            if is_chunked(header) {
+                return Ok(Some(Self::chunked(limits)))
+            }
+            if request {
+                return Err(HTTPParseError::UnsupportedTransferEncoding)
+            }
+            return Ok(Some(Self::until_close(limits)))
+        }
+        
+        match content_length {
+            Some(length) if length > limits.body_size_limit() => Err(HTTPParseError::BodyTooLarge),
+            Some(length) => Ok(Some(Self::with_length(length))),
+            None => Ok(None)
+        }
+    }
+    
+    pub(crate) fn feed(&mut self, buf: &[u8]) -> ParseResult<usize> {
+        match self.length {
+            BodyLength::Fixed(remaining) => {
+                let len = remaining.min(buf.len());
+                self.body.extend_from_slice(&buf[..len]);
+                self.length = BodyLength::Fixed(remaining - len);
+                Ok(len)
+            }
+            BodyLength::UntilClose => {
+                if self.body.len() + buf.len() > self.limits.body_size_limit() {
+                    return Err(HTTPParseError::BodyTooLarge)
+                }
+                self.body.extend_from_slice(buf);
+                Ok(buf.len())
+            }
+            BodyLength::Chunked => self.decoder.feed(buf)
+        }
+    }
+    
+    pub(crate) fn is_complete(&self) -> bool {
+        match self.length {
+            BodyLength::Chunked => self.decoder.is_complete(),
+            length => length == BodyLength::Fixed(0)
+        }
+    }
+    
+    ///
+    /// 连接关闭，只有读取到连接关闭的body才算完整
+    ///
+    pub(crate) fn close(&mut self) -> ParseResult<()> {
+        match self.length {
+            BodyLength::Fixed(0) => Ok(()),
+            BodyLength::Fixed(_) => Err(HTTPParseError::UnexpectedEof),
+            BodyLength::Chunked if self.decoder.is_complete() => Ok(()),
+            BodyLength::Chunked => Err(HTTPParseError::UnexpectedEof),
+            BodyLength::UntilClose => {
+                self.length = BodyLength::Fixed(0);
+                Ok(())
+            }
+        }
+    }
+    
+    pub(crate) fn take_body(&mut self) -> Vec<u8> {
+        match self.length {
+            BodyLength::Chunked => self.decoder.take_body(),
+            _ => mem::take(&mut self.body)
+        }
+    }
+    
+    ///
+    /// body已经读取的字节数，chunked编码时是编码后的字节数
+    ///
+    pub(crate) fn offset(&self) -> usize {
+        match self.length {
+            BodyLength::Chunked => self.decoder.offset(),
+            _ => self.body.len()
+        }
+    }
+    
+    pub(crate) fn take_trailer(&mut self) -> HTTPHeadMap {
+        self.decoder.take_trailer()
+    }
+}
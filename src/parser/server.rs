@@ -0,0 +1,278 @@
+use std::mem;
+
+use crate::error::Error;
+use crate::header::method::{HTTPClientMethod, HTTPServerMethod, ServerMethodCode};
+use crate::header::version::HTTPVersion;
+use crate::map::HTTPHeadMap;
+use crate::parser::{BodyReader, HeadReader, HTTPParseError, ParseProgress, ParseResult, ParseStatus, ParserLimits};
+use crate::response::HTTPResponseBuilder;
+use crate::response::server::HTTPServerResponse;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum State {
+    Head,
+    Body,
+    Complete
+}
+
+impl Default for State {
+    fn default() -> Self {
+        State::Head
+    }
+}
+
+///
+/// 服务器响应(HTTPServerResponse)的增量解析器
+///
+/// 没有Content-Length的响应body一直读取到连接关闭，此时需要调用`finish`
+///
+#[derive(Clone, Debug, Default)]
+pub struct HTTPServerParser {
+    state: State,
+    head: HeadReader,
+    status_line: Option<(HTTPVersion, HTTPServerMethod)>,
+    body: BodyReader,
+    head_request: bool,
+    connect_request: bool,
+    limits: ParserLimits
+}
+
+impl HTTPServerParser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    pub fn limits(self, limits: ParserLimits) -> Self {
+        let mut this = self;
+        this.limits = limits;
+        this.head = HeadReader::new(limits);
+        this
+    }
+    
+    ///
+    /// 对应请求的方法，HEAD请求的响应没有body，CONNECT请求的2xx响应之后是隧道
+    ///
+    pub fn request_method(self, method: HTTPClientMethod) -> Self {
+        let mut this = self;
+        this.head_request = method == HTTPClientMethod::HEAD;
+        this.connect_request = method == HTTPClientMethod::CONNECT;
+        this
+    }
+    
+    ///
+    /// 和`feed`相同，错误带上出错的位置
+    ///
+    pub fn try_feed(&mut self, buf: &[u8]) -> crate::error::Result<ParseProgress> {
+        self.feed(buf)
+            .map_err(|error| Error::parse_at(error, self.offset()))
+    }
+    
+    pub fn feed(&mut self, buf: &[u8]) -> ParseResult<ParseProgress> {
+        let mut consumed = 0;
+        
+        if self.state == State::Head {
+            consumed += self.head.feed(buf)?;
+            if self.head.is_complete() {
+                let (version, method) = Self::parse_status_line(self.head.start_line())
+                    .map_err(|error| {
+                        let token = if error == HTTPParseError::InvalidStatus { 1 } else { 0 };
+                        self.head.start_line_error(error, token)
+                    })?;
+                self.body = self.body_reader(method.code())
+                    .map_err(|error| self.head.field_error(error))?;
+                self.status_line = Some((version, method));
+                self.state = State::Body;
+            }
+        }
+        
+        if self.state == State::Body {
+            consumed += self.body.feed(&buf[consumed..])?;
+            if self.body.is_complete() {
+                self.state = State::Complete;
+            }
+        }
+        
+        Ok(ParseProgress::new(self.status(), consumed))
+    }
+    
+    ///
+    /// 连接已经关闭
+    ///
+    pub fn finish(&mut self) -> ParseResult<ParseProgress> {
+        match self.state {
+            State::Head => return Err(HTTPParseError::UnexpectedEof),
+            State::Body => self.body.close()?,
+            State::Complete => {}
+        }
+        self.state = State::Complete;
+        
+        Ok(ParseProgress::new(self.status(), 0))
+    }
+    
+    ///
+    /// 和`finish`相同，错误带上出错的位置
+    ///
+    pub fn try_finish(&mut self) -> crate::error::Result<ParseProgress> {
+        self.finish()
+            .map_err(|error| Error::parse_at(error, self.offset()))
+    }
+    
+    fn body_reader(&self, code: ServerMethodCode) -> ParseResult<BodyReader> {
+        //HEAD的响应，1xx，204，304都没有body
+        if self.head_request || (100..200).contains(&code) || code == 204 || code == 304 {
+            return Ok(BodyReader::with_length(0))
+        }
+        //CONNECT成功之后的字节属于隧道(RFC 9112 6.3)
+        if self.connect_request && (200..300).contains(&code) {
+            return Ok(BodyReader::with_length(0))
+        }
+        
+        Ok(BodyReader::from_header(self.head.header(), false, self.limits)?
+            .unwrap_or_else(|| BodyReader::until_close(self.limits)))
+    }
+    
+    fn parse_status_line(line: &str) -> ParseResult<(HTTPVersion, HTTPServerMethod)> {
+        //HTTP/1.1 200 OK，原因短语可以为空也可以包含空格
+        let mut sp = line.splitn(3, ' ');
+        let version = sp.next().unwrap_or_default();
+        let code = sp.next().ok_or(HTTPParseError::InvalidStartLine)?;
+        let reason = sp.next().unwrap_or_default();
+        
+        let version = HTTPVersion::from(version)
+            .map_err(|_| HTTPParseError::InvalidVersion)?;
+        if code.len() != 3 || !code.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(HTTPParseError::InvalidStatus)
+        }
+        let code = code.parse::<ServerMethodCode>()
+                       .map_err(|_| HTTPParseError::InvalidStatus)?;
+        
+        Ok((version, HTTPServerMethod::from_code(code, reason.trim())))
+    }
+    
+    pub fn status(&self) -> ParseStatus {
+        match self.state {
+            State::Head => ParseStatus::Partial,
+            State::Body => ParseStatus::HeadersComplete,
+            State::Complete => ParseStatus::Complete
+        }
+    }
+    
+    pub fn is_complete(&self) -> bool {
+        self.state == State::Complete
+    }
+    
+    ///
+    /// 当前消息已经读取的字节数，`feed`出错之后是出错的行或者起始行中出错的部分开始的位置，
+    /// `try_feed`返回的错误已经带上了这个位置
+    ///
+    pub fn offset(&self) -> usize {
+        match self.state {
+            State::Head => self.head.offset(),
+            _ => self.head.offset() + self.body.offset()
+        }
+    }
+    
+    ///
+    /// header读取完毕之后可用
+    ///
+    pub fn header(&self) -> Option<&HTTPHeadMap> {
+        if self.state == State::Head {
+            None
+        } else {
+            Some(self.head.header())
+        }
+    }
+    
+    ///
+    /// 取出解析完成的消息并重置解析器，消息未完成时返回None
+    ///
+    pub fn take(&mut self) -> Option<HTTPServerResponse> {
+        if !self.is_complete() {
+            return None
+        }
+        
+        let mut this = mem::replace(self, Self::new().limits(self.limits));
+        self.head_request = this.head_request;
+        self.connect_request = this.connect_request;
+        
+        let (version, method) = this.status_line.take()?;
+        let mut response = HTTPResponseBuilder::new(
+            version,
+            this.head.take_header(),
+            this.body.take_body()
+        );
+        *response.trailer_mut() = this.body.take_trailer();
+        
+        Some(HTTPServerResponse::new(response, method))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::header::method::{HTTPClientMethod, HTTPServerMethod};
+    use crate::parser::{HTTPParseError, ParseStatus};
+    use crate::parser::server::HTTPServerParser;
+    
+    #[test]
+    fn content_length() {
+        let mut parser = HTTPServerParser::new();
+        let buf = b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nhelloHTTP/1.1";
+        
+        let progress = parser.feed(buf).unwrap();
+        assert!(progress.is_complete());
+        assert_eq!(&buf[progress.consumed()..], b"HTTP/1.1");
+        
+        let response = parser.take().unwrap();
+        assert!(matches!(response.method(), HTTPServerMethod::OK));
+        assert_eq!(response.body(), b"hello");
+    }
+    
+    #[test]
+    fn other_status() {
+        let mut parser = HTTPServerParser::new();
+        parser.feed(b"HTTP/1.1 418 I'm a teapot\r\nContent-Length: 0\r\n\r\n").unwrap();
+        
+        match parser.take().unwrap().method() {
+            HTTPServerMethod::Other(code, reason) => {
+                assert_eq!(code, 418);
+                assert_eq!(reason, "I'm a teapot");
+            }
+            method => panic!("{:?}", method)
+        }
+        
+        let mut parser = HTTPServerParser::new();
+        assert_eq!(parser.feed(b"HTTP/1.1 2000 OK\r\n\r\n"), Err(HTTPParseError::InvalidStatus));
+    }
+    
+    #[test]
+    fn until_close() {
+        let mut parser = HTTPServerParser::new();
+        let progress = parser.feed(b"HTTP/1.0 200 OK\r\n\r\nhello ").unwrap();
+        assert_eq!(progress.status(), ParseStatus::HeadersComplete);
+        parser.feed(b"world").unwrap();
+        
+        assert!(parser.finish().unwrap().is_complete());
+        assert_eq!(parser.take().unwrap().body(), b"hello world");
+    }
+    
+    #[test]
+    fn no_body() {
+        let mut parser = HTTPServerParser::new()
+            .request_method(HTTPClientMethod::HEAD);
+        assert!(parser.feed(b"HTTP/1.1 200 OK\r\nContent-Length: 10\r\n\r\n").unwrap().is_complete());
+        
+        let mut parser = HTTPServerParser::new();
+        assert!(parser.feed(b"HTTP/1.1 204 No Content\r\n\r\n").unwrap().is_complete());
+        
+        //隧道里的字节不属于响应
+        let mut parser = HTTPServerParser::new()
+            .request_method(HTTPClientMethod::CONNECT);
+        let progress = parser.feed(b"HTTP/1.1 200 Connection Established\r\n\r\n\x16\x03\x01").unwrap();
+        assert!(progress.is_complete());
+        assert_eq!(progress.consumed(), 39);
+        
+        let mut parser = HTTPServerParser::new();
+        parser.feed(b"HTTP/1.1 200 OK\r\nContent-Length: 10\r\n\r\nabc").unwrap();
+        assert_eq!(parser.finish(), Err(HTTPParseError::UnexpectedEof));
+    }
+}
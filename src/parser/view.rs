@@ -0,0 +1,347 @@
+use std::str;
+
+use crate::header::method::HTTPClientMethod;
+use crate::header::name::HeaderName;
+use crate::header::version::HTTPVersion;
+use crate::map::{decode_field_value, HTTPHeadMap, is_field_value_char, is_tchar, trim_ows};
+use crate::parser::{check_host, HTTPParseError, insert_field, parse_content_length, ParseResult, ParserLimits};
+use crate::parser::chunked::{ChunkedDecoder, chunked_count};
+use crate::response::client::{HTTPClientResponse, is_valid_form};
+use crate::response::HTTPResponseBuilder;
+use crate::uri::Uri;
+
+pub type HeaderView<'a> = (&'a [u8], &'a [u8]);
+
+///
+/// 借用缓存的请求视图，解析时只有请求目标被解析为Uri，header和body不复制
+///
+/// 需要时再用`into_owned`转换为HTTPClientResponse，chunked编码的body只有在转换时才会解码
+///
+#[derive(Clone, Debug)]
+pub struct RequestView<'a> {
+    method: &'a [u8],
+    target: &'a [u8],
+    uri: Uri,
+    version: &'a [u8],
+    headers: Vec<HeaderView<'a>>,
+    body: &'a [u8],
+    chunked: bool
+}
+
+impl<'a> RequestView<'a> {
+    ///
+    /// 缓存中的消息不完整时返回`Ok(None)`，完整时返回视图以及消息占用的字节数
+    ///
+    pub fn parse(buf: &'a [u8]) -> ParseResult<Option<(RequestView<'a>, usize)>> {
+        Self::parse_with_limits(buf, ParserLimits::default())
+    }
+    
+    pub fn parse_with_limits(buf: &'a [u8], limits: ParserLimits) -> ParseResult<Option<(RequestView<'a>, usize)>> {
+        let mut lines = Lines {
+            buf,
+            pos: 0
+        };
+        
+        //起始行之前的空行忽略
+        let start_line = loop {
+            match lines.next_line(limits.start_line_limit(), HTTPParseError::StartLineTooLong)? {
+                Some([]) => continue,
+                Some(line) => break line,
+                None => return Ok(None)
+            }
+        };
+        let head_start = lines.pos - start_line.len();
+        
+        let mut sp = start_line.split(|b| *b == b' ');
+        let (method, target, version) = match (sp.next(), sp.next(), sp.next(), sp.next()) {
+            (Some(method), Some(target), Some(version), None) => (method, target, version),
+            _ => return Err(HTTPParseError::InvalidStartLine)
+        };
+        if method.is_empty() || !method.iter().all(|b| is_tchar(*b)) {
+            return Err(HTTPParseError::InvalidMethod)
+        }
+        if target.is_empty() {
+            return Err(HTTPParseError::InvalidStartLine)
+        }
+        //请求目标的检查和HTTPClientParser相同
+        let uri = str::from_utf8(target)
+            .ok()
+            .and_then(|target| Uri::parse_target(target).ok())
+            .filter(|uri| is_valid_form(str::from_utf8(method).unwrap_or_default(), uri.form()))
+            .ok_or(HTTPParseError::InvalidTarget)?;
+        let parsed_version = str::from_utf8(version)
+            .ok()
+            .and_then(|version| HTTPVersion::from(version).ok())
+            .ok_or(HTTPParseError::InvalidVersion)?;
+        
+        let mut headers = Vec::new();
+        loop {
+            let line = match lines.next_line(limits.header_line_limit(), HTTPParseError::HeaderTooLarge)? {
+                Some(line) => line,
+                None => return Ok(None)
+            };
+            if lines.pos - head_start > limits.head_size_limit() {
+                return Err(HTTPParseError::HeaderTooLarge)
+            }
+            if line.is_empty() {
+                break
+            }
+            if headers.len() == limits.header_count_limit() {
+                return Err(HTTPParseError::TooManyHeaders)
+            }
+            
+            let colon = line.iter()
+                            .position(|b| *b == b':')
+                            .ok_or(HTTPParseError::InvalidHeader)?;
+            let name = &line[..colon];
+            if name.is_empty() || !name.iter().all(|b| is_tchar(*b)) {
+                return Err(HTTPParseError::InvalidHeader)
+            }
+            //视图不能改写字节，obs-fold以及控制字符直接拒绝
+            let value = trim_ows(&line[colon + 1..]);
+            if !value.iter().all(|b| is_field_value_char(*b)) {
+                return Err(HTTPParseError::InvalidHeader)
+            }
+            headers.push((name, value));
+        }
+        
+        let mut view = RequestView {
+            method,
+            target,
+            uri,
+            version,
+            headers,
+            body: &[],
+            chunked: false
+        };
+        
+        //body的长度，规则和HTTPClientParser相同
+        let body_start = lines.pos;
+        let rest = &buf[body_start..];
+        let length = view.content_length()?;
+        //多个Transfer-Encoding字段合并之后的最后一个编码就是最后一个字段的最后一个编码
+        let transfer_encoding = view.field_values(HeaderName::TRANSFER_ENCODING).last();
+        if let Some(coding) = transfer_encoding {
+            if length.is_some() {
+                return Err(HTTPParseError::ConflictingLength)
+            }
+            if chunked_count(view.field_values(HeaderName::TRANSFER_ENCODING)) > 1 {
+                return Err(HTTPParseError::RepeatedChunked)
+            }
+            let last = coding.rsplit(|b| *b == b',').next().map(trim_ows).unwrap_or_default();
+            if !last.eq_ignore_ascii_case(b"chunked") {
+                return Err(HTTPParseError::UnsupportedTransferEncoding)
+            }
+        }
+        check_host(parsed_version, view.field_values(HeaderName::HOST).count())?;
+        let body_len = match transfer_encoding {
+            Some(_) => {
+                view.chunked = true;
+                
+                //这里只检查格式，body在into_owned时才解码
+                let mut decoder = ChunkedDecoder::validating(limits);
+                let used = decoder.feed(rest)?;
+                if !decoder.is_complete() {
+                    return Ok(None)
+                }
+                used
+            }
+            None => {
+                let length = length.unwrap_or(0);
+                if length > limits.body_size_limit() {
+                    return Err(HTTPParseError::BodyTooLarge)
+                }
+                if rest.len() < length {
+                    return Ok(None)
+                }
+                length
+            }
+        };
+        view.body = &rest[..body_len];
+        
+        Ok(Some((view, body_start + body_len)))
+    }
+    
+    fn field_values<'b>(&'b self, name: HeaderName) -> impl Iterator<Item = &'a [u8]> + 'b {
+        self.headers
+            .iter()
+            .filter(move |(key, _)| key.eq_ignore_ascii_case(name.as_str().as_bytes()))
+            .map(|(_, value)| *value)
+    }
+    
+    fn content_length(&self) -> ParseResult<Option<usize>> {
+        let mut length = None;
+        for (name, value) in &self.headers {
+            if !name.eq_ignore_ascii_case(HeaderName::CONTENT_LENGTH.as_str().as_bytes()) {
+                continue
+            }
+            let value = str::from_utf8(value)
+                .map_err(|_| HTTPParseError::InvalidContentLength)?;
+            let value = parse_content_length(value)?;
+            match length {
+                Some(length) if length != value => return Err(HTTPParseError::DuplicateContentLength),
+                _ => length = Some(value)
+            }
+        }
+        Ok(length)
+    }
+    
+    pub fn method(&self) -> &'a [u8] {
+        self.method
+    }
+    
+    pub fn target(&self) -> &'a [u8] {
+        self.target
+    }
+    
+    pub fn uri(&self) -> &Uri {
+        &self.uri
+    }
+    
+    pub fn version(&self) -> &'a [u8] {
+        self.version
+    }
+    
+    pub fn headers(&self) -> &[HeaderView<'a>] {
+        &self.headers
+    }
+    
+    ///
+    /// 第一个名字相同的header，不区分大小写
+    ///
+    pub fn header(&self, name: &[u8]) -> Option<&'a [u8]> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| *value)
+    }
+    
+    ///
+    /// chunked编码时是编码后的原始字节
+    ///
+    pub fn body(&self) -> &'a [u8] {
+        self.body
+    }
+    
+    pub fn is_chunked(&self) -> bool {
+        self.chunked
+    }
+    
+    pub fn into_owned(self) -> ParseResult<HTTPClientResponse> {
+        let method = str::from_utf8(self.method)
+            .map_err(|_| HTTPParseError::InvalidMethod)?;
+        let method = HTTPClientMethod::from(method)
+            .map_err(|_| HTTPParseError::InvalidMethod)?;
+        let version = str::from_utf8(self.version)
+            .map_err(|_| HTTPParseError::InvalidVersion)?;
+        let version = HTTPVersion::from(version)
+            .map_err(|_| HTTPParseError::InvalidVersion)?;
+        //parse已经检查过请求目标是UTF-8
+        let resource = String::from_utf8_lossy(self.target).into_owned();
+        
+        let mut header = HTTPHeadMap::new();
+        for (name, value) in &self.headers {
+            let name = String::from_utf8(name.to_vec())
+                .map_err(|_| HTTPParseError::InvalidHeader)?
+                .into();
+            let value = decode_field_value(value);
+            insert_field(&mut header, (name, value))?;
+        }
+        
+        let (body, trailer) = if self.chunked {
+            let mut decoder = ChunkedDecoder::with_limits(ParserLimits::unlimited());
+            decoder.feed(self.body)?;
+            (decoder.take_body(), decoder.take_trailer())
+        } else {
+            (self.body.to_vec(), HTTPHeadMap::new())
+        };
+        
+        let mut response = HTTPResponseBuilder::new(version, header, body);
+        *response.trailer_mut() = trailer;
+        
+        Ok(HTTPClientResponse::with_uri(response, method, resource, self.uri))
+    }
+}
+
+struct Lines<'a> {
+    buf: &'a [u8],
+    pos: usize
+}
+
+impl<'a> Lines<'a> {
+    ///
+    /// 下一行，去掉CRLF或者LF，没有完整的一行时返回None
+    ///
+    fn next_line(&mut self, limit: usize, error: HTTPParseError) -> ParseResult<Option<&'a [u8]>> {
+        let rest = &self.buf[self.pos..];
+        let index = match rest.iter().position(|b| *b == b'\n') {
+            Some(index) => index,
+            None if rest.len() > limit => return Err(error),
+            None => return Ok(None)
+        };
+        
+        let mut line = &rest[..index];
+        if line.last() == Some(&b'\r') {
+            line = &line[..line.len() - 1];
+        }
+        if line.len() > limit {
+            return Err(error)
+        }
+        
+        self.pos += index + 1;
+        Ok(Some(line))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::header::method::HTTPClientMethod;
+    use crate::parser::{HTTPParseError, ParserLimits};
+    use crate::parser::view::RequestView;
+    
+    #[test]
+    fn view() {
+        let buf = b"POST /api?x=1 HTTP/1.1\r\nHost: example.com\r\nContent-Length: 4\r\n\r\nbodyGET";
+        let (view, used) = RequestView::parse(buf).unwrap().unwrap();
+        
+        assert_eq!(used, buf.len() - 3);
+        assert_eq!(view.method(), b"POST");
+        assert_eq!(view.target(), b"/api?x=1");
+        assert_eq!(view.header(b"host").unwrap(), b"example.com");
+        assert_eq!(view.body(), b"body");
+        
+        let request = view.into_owned().unwrap();
+        assert!(matches!(request.method(), HTTPClientMethod::POST));
+        assert_eq!(request.uri().unwrap().path(), "/api");
+        assert_eq!(request.body(), b"body");
+    }
+    
+    #[test]
+    fn partial() {
+        let buf = b"GET / HTTP/1.1\r\nHost: x\r\nTransfer-Encoding: chunked\r\n\r\n3\r\nabc\r\n0\r\n\r\n";
+        for len in 0..buf.len() {
+            assert!(RequestView::parse(&buf[..len]).unwrap().is_none());
+        }
+        
+        let (view, used) = RequestView::parse(buf).unwrap().unwrap();
+        assert_eq!(used, buf.len());
+        assert!(view.is_chunked());
+        //解析时不解码，body借用编码后的字节
+        assert_eq!(view.body(), b"3\r\nabc\r\n0\r\n\r\n");
+        assert_eq!(view.into_owned().unwrap().body(), b"abc");
+        
+        let limits = ParserLimits::new().max_body_size(2);
+        assert_eq!(RequestView::parse_with_limits(buf, limits).unwrap_err(), HTTPParseError::BodyTooLarge);
+    }
+    
+    #[test]
+    fn invalid() {
+        assert_eq!(RequestView::parse(b"GET / HTTP/1.1\r\nBad Name: x\r\n\r\n").unwrap_err(), HTTPParseError::InvalidHeader);
+        assert_eq!(RequestView::parse(b"GET / HTTP/1.1\r\nA: x\r\n folded\r\n\r\n").unwrap_err(), HTTPParseError::InvalidHeader);
+        assert_eq!(RequestView::parse(b"GET / HTTP/1.1\r\nContent-Length: 1\r\nContent-Length: 2\r\n\r\n").unwrap_err(), HTTPParseError::DuplicateContentLength);
+        assert_eq!(
+            RequestView::parse(b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\nTransfer-Encoding: gzip\r\n\r\n0\r\n\r\n").unwrap_err(),
+            HTTPParseError::UnsupportedTransferEncoding
+        );
+    }
+}
@@ -0,0 +1,329 @@
+//!
+//! 查询字符串以及`application/x-www-form-urlencoded`的编码和解码
+//!
+//! 两者格式相同：`&`分隔的`name=value`，`+`表示空格，其余字符使用百分号编码
+//!
+
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::iter::FromIterator;
+use std::slice;
+use std::vec;
+
+///
+/// 按照出现顺序保存参数，同一个名字可以有多个值，例如`tag=a&tag=b`
+///
+/// 名字区分大小写，保存的是解码之后的文本，`Display`输出编码之后的结果
+///
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct QueryMap {
+    pairs: Vec<(String, String)>
+}
+
+impl QueryMap {
+    pub fn new() -> Self {
+        QueryMap::default()
+    }
+    
+    ///
+    /// 不会失败：不完整的百分号编码原样保留，不是UTF-8的字节替换为U+FFFD，
+    /// 空的片段被忽略，没有`=`的片段值为空
+    ///
+    pub fn parse<T>(raw: T) -> Self
+        where
+            T: AsRef<[u8]>
+    {
+        let pairs = raw.as_ref()
+                       .split(|b| *b == b'&')
+                       .filter(|pair| !pair.is_empty())
+                       .map(|pair| {
+                           match pair.iter().position(|b| *b == b'=') {
+                               Some(index) => (decode(&pair[..index]), decode(&pair[index + 1..])),
+                               None => (decode(pair), String::new())
+                           }
+                       })
+                       .collect();
+        QueryMap {
+            pairs
+        }
+    }
+    
+    ///
+    /// 第一个同名参数的值
+    ///
+    pub fn get<K>(&self, k: K) -> Option<&str>
+        where
+            K: AsRef<str>
+    {
+        self.pairs
+            .iter()
+            .find(|(key, _)| key == k.as_ref())
+            .map(|(_, value)| value.as_str())
+    }
+    
+    ///
+    /// 所有同名参数的值，按照出现顺序
+    ///
+    pub fn get_all<K>(&self, k: K) -> Vec<&str>
+        where
+            K: AsRef<str>
+    {
+        self.pairs
+            .iter()
+            .filter(|(key, _)| key == k.as_ref())
+            .map(|(_, value)| value.as_str())
+            .collect()
+    }
+    
+    pub fn contains_key<K>(&self, k: K) -> bool
+        where
+            K: AsRef<str>
+    {
+        self.pairs
+            .iter()
+            .any(|(key, _)| key == k.as_ref())
+    }
+    
+    ///
+    /// 在末尾添加一个参数，不影响已有的同名参数
+    ///
+    pub fn append<K, V>(&mut self, k: K, v: V)
+        where
+            K: ToString,
+            V: ToString
+    {
+        self.pairs.push((k.to_string(), v.to_string()))
+    }
+    
+    ///
+    /// 替换同名的所有参数，新的值放在第一个同名参数的位置，返回原来的第一个值
+    ///
+    pub fn insert<K, V>(&mut self, k: K, v: V) -> Option<String>
+        where
+            K: ToString,
+            V: ToString
+    {
+        let k = k.to_string();
+        match self.pairs.iter().position(|(key, _)| *key == k) {
+            Some(index) => {
+                let old = std::mem::replace(&mut self.pairs[index].1, v.to_string());
+                //删除后面的同名参数
+                let mut current = 0;
+                self.pairs.retain(|(key, _)| {
+                    current += 1;
+                    current - 1 <= index || *key != k
+                });
+                Some(old)
+            }
+            None => {
+                self.pairs.push((k, v.to_string()));
+                None
+            }
+        }
+    }
+    
+    ///
+    /// 删除所有同名参数，返回第一个值
+    ///
+    pub fn remove<K>(&mut self, k: K) -> Option<String>
+        where
+            K: AsRef<str>
+    {
+        let k = k.as_ref();
+        let first = self.pairs
+                        .iter()
+                        .position(|(key, _)| key == k)
+                        .map(|index| self.pairs[index].1.clone());
+        self.pairs.retain(|(key, _)| key != k);
+        first
+    }
+    
+    pub fn len(&self) -> usize {
+        self.pairs.len()
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.pairs.is_empty()
+    }
+    
+    pub fn clear(&mut self) {
+        self.pairs.clear()
+    }
+    
+    ///
+    /// 按照出现顺序借用每一个参数
+    ///
+    pub fn iter(&self) -> Iter<'_> {
+        Iter {
+            inner: self.pairs.iter()
+        }
+    }
+}
+
+pub struct Iter<'a> {
+    inner: slice::Iter<'a, (String, String)>
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = (&'a str, &'a str);
+    
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner
+            .next()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+    }
+    
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'a> ExactSizeIterator for Iter<'a> {}
+
+impl<'a> IntoIterator for &'a QueryMap {
+    type Item = (&'a str, &'a str);
+    type IntoIter = Iter<'a>;
+    
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl IntoIterator for QueryMap {
+    type Item = (String, String);
+    type IntoIter = vec::IntoIter<(String, String)>;
+    
+    fn into_iter(self) -> Self::IntoIter {
+        self.pairs.into_iter()
+    }
+}
+
+impl<K, V> FromIterator<(K, V)> for QueryMap
+    where
+        K: ToString,
+        V: ToString
+{
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = QueryMap::new();
+        map.extend(iter);
+        map
+    }
+}
+
+impl<K, V> Extend<(K, V)> for QueryMap
+    where
+        K: ToString,
+        V: ToString
+{
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (k, v) in iter {
+            self.append(k, v);
+        }
+    }
+}
+
+impl Display for QueryMap {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for (index, (key, value)) in self.pairs.iter().enumerate() {
+            if index > 0 {
+                f.write_str("&")?;
+            }
+            write!(f, "{}={}", encode(key), encode(value))?;
+        }
+        Ok(())
+    }
+}
+
+///
+/// 解码一个名字或者值，`+`解码为空格
+///
+pub fn decode<T>(raw: T) -> String
+    where
+        T: AsRef<[u8]>
+{
+    let raw = raw.as_ref();
+    let mut buf = Vec::with_capacity(raw.len());
+    let mut index = 0;
+    while index < raw.len() {
+        match raw[index] {
+            b'+' => buf.push(b' '),
+            b'%' => {
+                let hex = raw.get(index + 1..index + 3)
+                             .and_then(|hex| std::str::from_utf8(hex).ok())
+                             .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+                match hex {
+                    //from_str_radix接受"+1"这样的写法
+                    Some(byte) if raw[index + 1] != b'+' => {
+                        buf.push(byte);
+                        index += 2;
+                    }
+                    _ => buf.push(b'%')
+                }
+            }
+            byte => buf.push(byte)
+        }
+        index += 1;
+    }
+    String::from_utf8_lossy(&buf).into_owned()
+}
+
+///
+/// 编码一个名字或者值，只保留字母数字和`*-._`，空格编码为`+`，与浏览器提交表单的结果相同
+///
+pub fn encode<T>(raw: T) -> String
+    where
+        T: AsRef<str>
+{
+    let mut buf = String::with_capacity(raw.as_ref().len());
+    for byte in raw.as_ref().bytes() {
+        match byte {
+            b' ' => buf.push('+'),
+            b'*' | b'-' | b'.' | b'_' => buf.push(byte as char),
+            byte if byte.is_ascii_alphanumeric() => buf.push(byte as char),
+            byte => buf.push_str(&format!("%{:02X}", byte))
+        }
+    }
+    buf
+}
+
+#[cfg(test)]
+mod test {
+    use crate::query::{decode, encode, QueryMap};
+    
+    #[test]
+    fn parse() {
+        let query = QueryMap::parse("q=rust+http&tag=a&tag=b%26c&&flag&name=%E4%BD%A0%E5%A5%BD");
+        assert_eq!(query.len(), 5);
+        assert_eq!(query.get("q"), Some("rust http"));
+        assert_eq!(query.get_all("tag"), vec!["a", "b&c"]);
+        assert_eq!(query.get("flag"), Some(""));
+        assert_eq!(query.get("name"), Some("你好"));
+        assert_eq!(query.get("Q"), None);
+        
+        let keys = query.iter().map(|(key, _)| key).collect::<Vec<_>>();
+        assert_eq!(keys, vec!["q", "tag", "tag", "flag", "name"]);
+        
+        //不完整的编码原样保留
+        assert_eq!(decode("100%"), "100%");
+        assert_eq!(decode("%+1%zz"), "% 1%zz");
+        assert_eq!(decode("%FF"), "\u{FFFD}");
+    }
+    
+    #[test]
+    fn serialize() {
+        let mut query = QueryMap::new();
+        query.append("q", "a b&c=d");
+        query.append("tag", "x");
+        query.append("tag", "y");
+        assert_eq!(query.to_string(), "q=a+b%26c%3Dd&tag=x&tag=y");
+        assert_eq!(QueryMap::parse(query.to_string()), query);
+        
+        assert_eq!(query.insert("tag", "z"), Some(String::from("x")));
+        assert_eq!(query.to_string(), "q=a+b%26c%3Dd&tag=z");
+        assert_eq!(query.remove("q"), Some(String::from("a b&c=d")));
+        
+        assert_eq!(encode("~/你"), "%7E%2F%E4%BD%A0");
+        let query = vec![("a", 1), ("b", 2)].into_iter().collect::<QueryMap>();
+        assert_eq!(query.to_string(), "a=1&b=2");
+    }
+}
\ No newline at end of file
@@ -0,0 +1,425 @@
+use std::io;
+use std::io::Write;
+
+use crate::error::ProtocolError;
+use crate::extensions::Extensions;
+use crate::header::method::HTTPClientMethod;
+use crate::header::typed::ContentType;
+use crate::header::version::HTTPVersion;
+use crate::map::{HeaderMappingType, HTTPHeadMap};
+use crate::multipart::{Multipart, MultipartError};
+use crate::query::QueryMap;
+use crate::response::{HTTPBytes, HTTPResponse, invalid_input, is_keep_alive, validate_fields, validate_framing, write_chunked_fields, write_fields};
+use crate::response::chunked::ChunkedEncoder;
+use crate::response::client::{HTTPClientResponse, is_valid_target};
+use crate::uri::{Uri, UriError};
+
+///
+/// 客户端发给服务器的请求
+///
+/// 以前的代码使用[`HTTPClientResponse`]表示请求，两者之间可以互相转换，转换为`HTTPClientResponse`时extensions会被丢弃
+///
+#[derive(Clone, Debug)]
+pub struct HTTPRequest {
+    method: HTTPClientMethod,
+    uri: Uri,
+    version: HTTPVersion,
+    header: HTTPHeadMap,
+    body: Vec<u8>,
+    trailer: HTTPHeadMap,
+    extensions: Extensions
+}
+
+impl HTTPRequest {
+    pub fn new(method: HTTPClientMethod, uri: Uri, version: HTTPVersion, header: HTTPHeadMap, body: Vec<u8>) -> Self {
+        HTTPRequest {
+            method,
+            uri,
+            version,
+            header,
+            body,
+            trailer: HTTPHeadMap::new(),
+            extensions: Extensions::new()
+        }
+    }
+    
+    pub fn builder() -> HTTPRequestBuilder {
+        HTTPRequestBuilder::new()
+    }
+    
+    pub fn method(&self) -> &HTTPClientMethod {
+        &self.method
+    }
+    
+    pub fn uri(&self) -> &Uri {
+        &self.uri
+    }
+    
+    pub fn uri_mut(&mut self) -> &mut Uri {
+        &mut self.uri
+    }
+    
+    ///
+    /// 解码之后的查询参数，没有查询字符串时为空
+    ///
+    pub fn query(&self) -> QueryMap {
+        self.uri
+            .query()
+            .map(QueryMap::parse)
+            .unwrap_or_default()
+    }
+    
+    ///
+    /// Content-Type是`application/x-www-form-urlencoded`时解码body，否则返回None
+    ///
+    pub fn form(&self) -> Option<QueryMap> {
+        let content_type = self.header.typed_get::<ContentType>()?;
+        if content_type.0.essence() != "application/x-www-form-urlencoded" {
+            return None
+        }
+        Some(QueryMap::parse(&self.body))
+    }
+    
+    ///
+    /// 按照Content-Type中的boundary逐个读取multipart body的部分
+    ///
+    pub fn multipart(&self) -> Result<Multipart<&[u8]>, MultipartError> {
+        Multipart::from_header(&self.body[..], &self.header)
+    }
+    
+    pub fn version(&self) -> HTTPVersion {
+        self.version
+    }
+    
+    pub fn header(&self) -> &HTTPHeadMap {
+        &self.header
+    }
+    
+    pub fn header_mut(&mut self) -> &mut HTTPHeadMap {
+        &mut self.header
+    }
+    
+    pub fn body(&self) -> &Vec<u8> {
+        &self.body
+    }
+    
+    pub fn body_mut(&mut self) -> &mut Vec<u8> {
+        &mut self.body
+    }
+    
+    pub fn trailer(&self) -> &HTTPHeadMap {
+        &self.trailer
+    }
+    
+    pub fn trailer_mut(&mut self) -> &mut HTTPHeadMap {
+        &mut self.trailer
+    }
+    
+    ///
+    /// 附加数据，不会被发送
+    ///
+    pub fn extensions(&self) -> &Extensions {
+        &self.extensions
+    }
+    
+    pub fn extensions_mut(&mut self) -> &mut Extensions {
+        &mut self.extensions
+    }
+    
+    ///
+    /// 处理完这个请求之后是否保持连接
+    ///
+    pub fn is_keep_alive(&self) -> bool {
+        is_keep_alive(self.version, &self.header)
+    }
+    
+    ///
+    /// 检查方法、请求目标和所有header，发送时不会产生被注入的行
+    ///
+    /// 相对引用和带有fragment的URI不能作为请求目标
+    ///
+    pub fn validate(&self) -> Result<(), ProtocolError> {
+        self.method.validate()?;
+        if !is_valid_target(&self.uri.to_string()) {
+            return Err(ProtocolError::InvalidTarget)
+        }
+        validate_fields(&self.header, &self.trailer)
+    }
+    
+    ///
+    /// 把请求行、header以及原样的body直接写入`w`，消息不合法时返回`InvalidInput`并且不写入任何东西
+    ///
+    pub fn write_to<W>(&self, w: &mut W) -> io::Result<()>
+        where
+            W: Write
+    {
+        self.validate()
+            .and_then(|_| validate_framing(self.version, &self.header, &self.body, None))
+            .map_err(invalid_input)?;
+        write!(w, "{} {} {}\r\n", self.method, self.uri, self.version)?;
+        write_fields(w, &self.header, &self.body, &self.trailer, false)
+    }
+    
+    ///
+    /// 写入请求行和header，body长度未知时使用返回的编码器分段写入，body字段会被忽略
+    ///
+    /// 请求不能以连接关闭结束，HTTP/1.0的请求返回`InvalidInput`
+    ///
+    pub fn stream_to<W>(&self, w: W) -> io::Result<ChunkedEncoder<W>>
+        where
+            W: Write
+    {
+        self.validate().map_err(invalid_input)?;
+        if !self.version.supports_chunked() {
+            return Err(invalid_input(ProtocolError::UnsupportedTransferEncoding))
+        }
+        let mut w = w;
+        write!(w, "{} {} {}\r\n", self.method, self.uri, self.version)?;
+        write_chunked_fields(w, &self.header, self.version)
+    }
+    
+    ///
+    /// 消息不合法时返回`InvalidInput`
+    ///
+    pub fn http_bytes(&self) -> io::Result<Vec<u8>> {
+        let mut buf = Vec::with_capacity(self.body.len() + 128);
+        self.write_to(&mut buf)?;
+        Ok(buf)
+    }
+}
+
+///
+/// 解析器已经检查过请求目标，只有手动构建的`HTTPClientResponse`才可能失败
+///
+impl TryFrom<HTTPClientResponse> for HTTPRequest {
+    type Error = UriError;
+    
+    fn try_from(request: HTTPClientResponse) -> Result<Self, Self::Error> {
+        let (response, method, uri) = request.into_parts();
+        let uri = uri?;
+        let (version, header, body, trailer) = response.into_parts();
+        let mut request = HTTPRequest::new(method, uri, version, header, body);
+        request.trailer = trailer;
+        Ok(request)
+    }
+}
+
+impl From<HTTPRequest> for HTTPClientResponse {
+    fn from(request: HTTPRequest) -> Self {
+        let mut response = HTTPResponse::new(request.version, request.header, request.body);
+        *response.trailer_mut() = request.trailer;
+        HTTPClientResponse::with_uri(response, request.method, request.uri.to_string(), request.uri)
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct HTTPRequestBuilder {
+    method: Option<HTTPClientMethod>,
+    uri: Option<Uri>,
+    version: Option<HTTPVersion>,
+    header: Option<HTTPHeadMap>,
+    body: Option<Vec<u8>>,
+    trailer: Option<HTTPHeadMap>,
+    extensions: Extensions
+}
+
+impl HTTPRequestBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    pub fn method(self, method: HTTPClientMethod) -> Self {
+        let mut this = self;
+        this.method = Some(method);
+        this
+    }
+    
+    pub fn uri(self, uri: Uri) -> Self {
+        let mut this = self;
+        this.uri = Some(uri);
+        this
+    }
+    
+    ///
+    /// 按照请求目标解析`uri`，四种形式都可以
+    ///
+    pub fn try_uri<T>(self, uri: T) -> Result<Self, UriError>
+        where
+            T: AsRef<str>
+    {
+        let uri = Uri::parse_target(uri.as_ref())?;
+        Ok(self.uri(uri))
+    }
+    
+    pub fn version(self, version: HTTPVersion) -> Self {
+        let mut this = self;
+        this.version = Some(version);
+        this
+    }
+    
+    pub fn header(self, header: HTTPHeadMap) -> Self {
+        let mut this = self;
+        this.header = Some(header);
+        this
+    }
+    
+    pub fn header_insert<T>(self, t: T) -> Self
+        where
+            T: HeaderMappingType
+    {
+        let mut this = self;
+        let mut header = this.header.unwrap_or_default();
+        
+        //无法解析的header直接忽略
+        if let Ok(tuple) = t.parse_key_value() {
+            header.insert_tuple(tuple);
+        }
+        
+        this.header = Some(header);
+        this
+    }
+    
+    pub fn body<T>(self, body: T) -> Self
+        where
+            T: HTTPBytes
+    {
+        let mut this = self;
+        this.body = Some(body.vec_u8());
+        this
+    }
+    
+    ///
+    /// 编码之后作为body，同时设置Content-Type
+    ///
+    pub fn form(self, form: &QueryMap) -> Self {
+        let mut this = self;
+        let mut header = this.header.unwrap_or_default();
+        header.typed_insert(ContentType::form_url_encoded());
+        this.header = Some(header);
+        this.body = Some(form.to_string().into_bytes());
+        this
+    }
+    
+    pub fn trailer(self, trailer: HTTPHeadMap) -> Self {
+        let mut this = self;
+        this.trailer = Some(trailer);
+        this
+    }
+    
+    pub fn extension<T>(self, extension: T) -> Self
+        where
+            T: Clone + Send + Sync + 'static
+    {
+        let mut this = self;
+        this.extensions.insert(extension);
+        this
+    }
+    
+    ///
+    /// 默认是`GET / HTTP/1.1`
+    ///
+    pub fn build(self) -> HTTPRequest {
+        let method = self.method.unwrap_or(HTTPClientMethod::GET);
+        let uri = self.uri.unwrap_or_default();
+        let version = self.version.unwrap_or(HTTPVersion::HTTP1_1);
+        
+        let mut request = HTTPRequest::new(method, uri, version, self.header.unwrap_or_default(), self.body.unwrap_or_default());
+        request.trailer = self.trailer.unwrap_or_default();
+        request.extensions = self.extensions;
+        request
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::ErrorKind;
+    
+    use crate::header::method::HTTPClientMethod;
+    use crate::parser::client::HTTPClientParser;
+    use crate::query::QueryMap;
+    use crate::request::HTTPRequest;
+    use crate::response::client::{HTTPClientResponse, HTTPClientResponseBuilder};
+    use crate::uri::{TargetForm, Uri, UriError};
+    
+    #[derive(Clone, Debug, PartialEq)]
+    struct RequestId(u64);
+    
+    #[test]
+    fn build() {
+        let request = HTTPRequest::builder()
+            .method(HTTPClientMethod::POST)
+            .try_uri("/api/items?page=2")
+            .unwrap()
+            .header_insert("Host: example.com")
+            .header_insert("Content-Type: application/json")
+            .body("{}")
+            .extension(RequestId(7))
+            .build();
+        
+        assert_eq!(request.extensions().get::<RequestId>(), Some(&RequestId(7)));
+        assert!(request.is_keep_alive());
+        let http = request.http_bytes().unwrap();
+        assert_eq!(http, b"POST /api/items?page=2 HTTP/1.1\r\nHost: example.com\r\nContent-Type: application/json\r\nContent-Length: 2\r\n\r\n{}");
+        
+        let mut parser = HTTPClientParser::new();
+        parser.feed(&http).unwrap();
+        let parsed = HTTPRequest::try_from(parser.take().unwrap()).unwrap();
+        assert_eq!(parsed.method(), &HTTPClientMethod::POST);
+        assert_eq!(parsed.uri().path(), "/api/items");
+        assert_eq!(parsed.uri().query(), Some("page=2"));
+        assert_eq!(parsed.body(), b"{}");
+        
+        let legacy = HTTPClientResponse::from(parsed);
+        assert_eq!(legacy.resource(), "/api/items?page=2");
+        
+        assert_eq!(HTTPRequest::builder().try_uri("/a b").unwrap_err(), UriError::InvalidChar);
+        let legacy = HTTPClientResponseBuilder::new().resource("/a b").build();
+        assert!(HTTPRequest::try_from(legacy).is_err());
+        
+        //长度字段互相矛盾的请求不会被发送
+        let mut request = HTTPRequest::builder()
+            .header_insert("Transfer-Encoding: chunked")
+            .body("{}")
+            .build();
+        assert!(request.http_bytes().is_ok());
+        request.header_mut().insert("Content-Length", String::from("2"));
+        assert_eq!(request.http_bytes().unwrap_err().kind(), ErrorKind::InvalidInput);
+    }
+    
+    #[test]
+    fn target() {
+        let request = HTTPRequest::builder()
+            .method(HTTPClientMethod::CONNECT)
+            .try_uri("example.com:443")
+            .unwrap()
+            .build();
+        assert_eq!(request.uri().form(), TargetForm::Authority);
+        assert!(request.http_bytes().unwrap().starts_with(b"CONNECT example.com:443 HTTP/1.1\r\n"));
+        
+        //带有fragment的URI不能作为请求目标
+        let uri = Uri::parse("http://example.com/a#top").unwrap();
+        assert!(HTTPRequest::builder().uri(uri).build().http_bytes().is_err());
+    }
+    
+    #[test]
+    fn form() {
+        let request = HTTPRequest::builder()
+            .try_uri("/search?q=rust+http&page=2&tag=a&tag=b")
+            .unwrap()
+            .build();
+        let query = request.query();
+        assert_eq!(query.get("q"), Some("rust http"));
+        assert_eq!(query.get_all("tag"), vec!["a", "b"]);
+        assert!(request.form().is_none());
+        
+        let form = vec![("user", "ann lee"), ("note", "a&b")].into_iter().collect::<QueryMap>();
+        let request = HTTPRequest::builder()
+            .method(HTTPClientMethod::POST)
+            .form(&form)
+            .build();
+        assert!(request.query().is_empty());
+        assert_eq!(request.body(), b"user=ann+lee&note=a%26b");
+        assert_eq!(request.header().get("Content-Type").unwrap(), "application/x-www-form-urlencoded");
+        assert_eq!(request.form(), Some(form));
+    }
+}
\ No newline at end of file
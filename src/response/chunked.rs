@@ -0,0 +1,149 @@
+use std::io;
+use std::io::Write;
+
+use crate::error::ProtocolError;
+use crate::map::{HTTPHeadMap, is_field_value_char, is_tchar};
+use crate::response::invalid_input;
+
+///
+/// chunked编码器，用于事先不知道长度的body
+///
+/// 每次`write`都会写出一个chunk，最后需要调用`finish`写出结束chunk以及trailer
+///
+/// 发给HTTP/1.0对端的响应不能使用chunked，此时数据按原样写出，body在连接关闭时结束，扩展和trailer被丢弃
+///
+#[derive(Debug)]
+pub struct ChunkedEncoder<W>
+    where
+        W: Write
+{
+    writer: W,
+    chunked: bool
+}
+
+impl<W> ChunkedEncoder<W>
+    where
+        W: Write
+{
+    pub fn new(writer: W) -> Self {
+        ChunkedEncoder {
+            writer,
+            chunked: true
+        }
+    }
+    
+    pub(crate) fn close_delimited(writer: W) -> Self {
+        ChunkedEncoder {
+            writer,
+            chunked: false
+        }
+    }
+    
+    ///
+    /// 写出一个带有扩展的chunk，空数据不会写出任何东西
+    ///
+    pub fn write_chunk(&mut self, data: &[u8], extensions: &[(&str, Option<&str>)]) -> io::Result<()> {
+        if data.is_empty() {
+            return Ok(())
+        }
+        if !self.chunked {
+            return self.writer.write_all(data)
+        }
+        
+        write!(self.writer, "{:X}", data.len())?;
+        self.write_extensions(extensions)?;
+        self.writer.write_all(b"\r\n")?;
+        self.writer.write_all(data)?;
+        self.writer.write_all(b"\r\n")
+    }
+    
+    fn write_extensions(&mut self, extensions: &[(&str, Option<&str>)]) -> io::Result<()> {
+        //扩展名必须是token，quoted-string内也不能有CR、LF
+        let valid = extensions.iter().all(|(name, value)| {
+            !name.is_empty() && name.bytes().all(is_tchar) && value.map(|value| value.bytes().all(is_field_value_char)).unwrap_or(true)
+        });
+        if !valid {
+            return Err(invalid_input(ProtocolError::InvalidField))
+        }
+        
+        for (name, value) in extensions {
+            match value {
+                Some(value) if !value.is_empty() && value.bytes().all(is_tchar) => {
+                    write!(self.writer, ";{}={}", name, value)?
+                }
+                Some(value) => {
+                    let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+                    write!(self.writer, ";{}=\"{}\"", name, value)?
+                }
+                None => write!(self.writer, ";{}", name)?
+            }
+        }
+        Ok(())
+    }
+    
+    ///
+    /// 写出结束chunk以及trailer，返回内部的writer
+    ///
+    pub fn finish(self, trailer: &HTTPHeadMap) -> io::Result<W> {
+        self.finish_with_extensions(trailer, &[])
+    }
+    
+    pub fn finish_with_extensions(self, trailer: &HTTPHeadMap, extensions: &[(&str, Option<&str>)]) -> io::Result<W> {
+        trailer.validate()
+               .map_err(|_| invalid_input(ProtocolError::InvalidField))?;
+        let mut this = self;
+        if !this.chunked {
+            this.writer.flush()?;
+            return Ok(this.writer)
+        }
+        this.writer.write_all(b"0")?;
+        this.write_extensions(extensions)?;
+        this.writer.write_all(b"\r\n")?;
+        for (key, value) in trailer {
+            write!(this.writer, "{}: {}\r\n", key, value)?;
+        }
+        this.writer.write_all(b"\r\n")?;
+        this.writer.flush()?;
+        Ok(this.writer)
+    }
+}
+
+impl<W> Write for ChunkedEncoder<W>
+    where
+        W: Write
+{
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.write_chunk(buf, &[])?;
+        Ok(buf.len())
+    }
+    
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Write;
+    
+    use crate::map::HTTPHeadMap;
+    use crate::parser::chunked::ChunkedDecoder;
+    use crate::response::chunked::ChunkedEncoder;
+    
+    #[test]
+    fn encode() {
+        let mut encoder = ChunkedEncoder::new(Vec::new());
+        encoder.write_all(b"hello").unwrap();
+        encoder.write_chunk(b" world!!!!!!", &[("part", Some("two words")), ("last", None)]).unwrap();
+        
+        let mut trailer = HTTPHeadMap::new();
+        trailer.insert("Expires".to_string(), "never".to_string());
+        let buf = encoder.finish(&trailer).unwrap();
+        assert_eq!(buf, b"5\r\nhello\r\nC;part=\"two words\";last\r\n world!!!!!!\r\n0\r\nExpires: never\r\n\r\n");
+        
+        let mut decoder = ChunkedDecoder::new();
+        assert_eq!(decoder.feed(&buf).unwrap(), buf.len());
+        assert_eq!(decoder.body(), b"hello world!!!!!!");
+        assert_eq!(decoder.trailer().get("Expires").unwrap(), "never");
+    }
+}
@@ -1,7 +1,16 @@
+use std::io;
+use std::io::Write;
+
+use crate::cookie::CookieJar;
+use crate::error::ProtocolError;
 use crate::header::method::HTTPClientMethod;
+use crate::header::name::HeaderName;
 use crate::header::version::HTTPVersion;
 use crate::map::HTTPHeadMap;
-use crate::response::{HTTPBytes, HTTPResponse, HTTPResponseBuilder};
+use crate::parser::{BodyReader, ParserLimits};
+use crate::response::{HTTPBytes, HTTPResponse, HTTPResponseBuilder, invalid_input, validate_framing};
+use crate::response::chunked::ChunkedEncoder;
+use crate::uri::{TargetForm, Uri, UriResult};
 
 ///
 /// 客户端给服务器的响应，或者客户端的响应
@@ -12,30 +21,64 @@ use crate::response::{HTTPBytes, HTTPResponse, HTTPResponseBuilder};
 pub struct HTTPClientResponse {
     response: HTTPResponse,
     method: HTTPClientMethod,
-    resource: String
+    resource: String,
+    //resource解析之后的结果，构建器和解析器都只解析一次
+    uri: UriResult<Uri>
 }
 
 impl HTTPClientResponse {
     pub fn new(response: HTTPResponse, method: HTTPClientMethod, resource: String) -> Self {
+        let uri = Uri::parse_target(&resource);
+        HTTPClientResponse {
+            response,
+            method,
+            resource,
+            uri
+        }
+    }
+    
+    ///
+    /// 使用已经解析好的请求目标，`resource`是它原本的文本
+    ///
+    pub(crate) fn with_uri(response: HTTPResponse, method: HTTPClientMethod, resource: String, uri: Uri) -> Self {
         HTTPClientResponse {
             response,
             method,
-            resource
+            resource,
+            uri: Ok(uri)
         }
     }
     
+    pub(crate) fn into_parts(self) -> (HTTPResponse, HTTPClientMethod, UriResult<Uri>) {
+        (self.response, self.method, self.uri)
+    }
+    
     pub fn resource(&self) -> String {
         self.resource.clone()
     }
     
+    ///
+    /// 解析之后的请求目标，不是四种形式之一时返回None
+    ///
+    pub fn uri(&self) -> Option<&Uri> {
+        self.uri.as_ref().ok()
+    }
+    
     pub fn method(&self) -> HTTPClientMethod {
-        self.method
+        self.method.clone()
     }
     
     pub fn http_version(&self) -> HTTPVersion {
         self.response.version
     }
     
+    ///
+    /// 处理完这个请求之后是否保持连接
+    ///
+    pub fn is_keep_alive(&self) -> bool {
+        self.response.is_keep_alive()
+    }
+    
     pub fn header(&self) -> &HTTPHeadMap {
         &self.response.header
     }
@@ -60,30 +103,97 @@ impl HTTPClientResponse {
         &mut self.response.body
     }
     
-    pub fn http(self) -> String {
-        //header迭代器优化
-        let header = self.response.header
-                         .map(|(k, v)| format!("{}:{}\r\n", k, v))
-                         .collect::<Vec<String>>()
-                         .join("");
-        /*let mut header = String::new();
-        for i in header_iter {
-            header.push_str(&i)
+    pub fn trailer(&self) -> &HTTPHeadMap {
+        &self.response.trailer
+    }
+    
+    ///
+    /// 检查请求目标和所有header，发送时不会产生被注入的行
+    ///
+    pub fn validate(&self) -> Result<(), ProtocolError> {
+        self.method.validate()?;
+        if self.uri.is_err() {
+            return Err(ProtocolError::InvalidTarget)
         }
-        let header = header.trim()
-            .parse::<String>()
-            .unwrap();*/
-        /*let header = header_iter.join("");*/
-        
-        let method = self.method.to_string();
-        let resource = self.resource;
-        let version = self.response.version.to_string();
-        let body = String::from_utf8_lossy(&self.response.body);
-        format!("{} {} {}\r\n{}\r\n{}", method, resource, version, header, body)
+        self.response.validate()
     }
     
-    pub fn http_bytes(self) -> Vec<u8> {
-        self.http().into_bytes()
+    ///
+    /// 把起始行、header以及原样的body直接写入`w`，消息不合法时返回`InvalidInput`并且不写入任何东西
+    ///
+    pub fn write_to<W>(&self, w: &mut W) -> io::Result<()>
+        where
+            W: Write
+    {
+        self.validate()
+            .and_then(|_| validate_framing(self.response.version, &self.response.header, &self.response.body, None))
+            .map_err(invalid_input)?;
+        write!(w, "{} {} {}\r\n", self.method, self.resource, self.response.version)?;
+        self.response.write_to(w, false)
+    }
+    
+    ///
+    /// 只写入起始行和header，body由调用者按照Content-Length流式写入
+    ///
+    pub(crate) fn write_head_to<W>(&self, w: &mut W) -> io::Result<()>
+        where
+            W: Write
+    {
+        self.validate().map_err(invalid_input)?;
+        write!(w, "{} {} {}\r\n", self.method, self.resource, self.response.version)?;
+        self.response.write_head_to(w)
+    }
+    
+    ///
+    /// 写入起始行和header，body长度未知时使用返回的编码器分段写入，body字段会被忽略
+    ///
+    /// 请求不能以连接关闭结束，HTTP/1.0的请求返回`InvalidInput`
+    ///
+    pub fn stream_to<W>(&self, w: W) -> io::Result<ChunkedEncoder<W>>
+        where
+            W: Write
+    {
+        self.validate().map_err(invalid_input)?;
+        if !self.response.version.supports_chunked() {
+            return Err(invalid_input(ProtocolError::UnsupportedTransferEncoding))
+        }
+        let mut w = w;
+        write!(w, "{} {} {}\r\n", self.method, self.resource, self.response.version)?;
+        self.response.write_chunked_to(w)
+    }
+    
+    ///
+    /// body不是UTF-8时会被替换，发送请使用`http_bytes`或者`write_to`，消息不合法时返回`InvalidInput`
+    ///
+    pub fn http(&self) -> io::Result<String> {
+        Ok(String::from_utf8_lossy(&self.http_bytes()?).into_owned())
+    }
+    
+    ///
+    /// 消息不合法时返回`InvalidInput`
+    ///
+    pub fn http_bytes(&self) -> io::Result<Vec<u8>> {
+        let mut buf = Vec::with_capacity(self.response.body.len() + 128);
+        self.write_to(&mut buf)?;
+        Ok(buf)
+    }
+}
+
+///
+/// 请求目标必须是四种形式之一(RFC 9112 3.2)，也就不会包含空白和控制字符
+///
+pub(crate) fn is_valid_target(target: &str) -> bool {
+    Uri::parse_target(target).is_ok()
+}
+
+///
+/// authority-form只用于CONNECT，并且CONNECT只能用authority-form，asterisk-form只用于OPTIONS(RFC 9112 3.2.3，3.2.4)
+///
+pub(crate) fn is_valid_form(method: &str, form: TargetForm) -> bool {
+    match form {
+        TargetForm::Authority => method == "CONNECT",
+        TargetForm::Asterisk => method == "OPTIONS",
+        TargetForm::Origin | TargetForm::Absolute => method != "CONNECT"
     }
 }
 
@@ -116,88 +226,98 @@ impl HTTPClientResponseFormatter {
         this
     }
     
-    #[allow(unused_assignments)]
+    ///
+    /// 需要一次性给出完整的消息，从socket分段读取时请使用[`HTTPClientParser`](crate::parser::client::HTTPClientParser)
+    ///
     pub fn build(self) -> Option<HTTPClientResponse> {
-        if let Ok(response) = String::from_utf8(self.cache) {
-            let mut space = response.lines();
-            /*
-            GET / HTTP/1.1
-            Host: 127.0.0.1:8000
-            
-            <dir>w</dir>
-            */
-            
-            //第一行的方法行
-            let (method, version);
-            let mut resource = String::new();
-            if space.clone().count() > 1 {
-                let method_line = space.next().unwrap();
-                let mut method_line = method_line.split_whitespace();
-                
-                if method_line.clone().count() == 3 {
-                    method = method_line.next().unwrap();
-                    /*resource = method_line.next().unwrap();*/
-                    let resource_temp = method_line.next().unwrap();
-                    if !resource_temp.starts_with('/') {
-                        resource = format!("/{}", resource_temp);
-                    } else {
-                        resource = resource_temp.to_string();
-                    }
-                    version = method_line.next().unwrap();
-                } else {
+        let mut this = self;
+        this.build_next()
+    }
+    
+    ///
+    /// 解析缓存中的第一个消息，body之后的字节留在缓存中给下一个消息
+    ///
+    pub fn build_next(&mut self) -> Option<HTTPClientResponse> {
+        /*
+        GET / HTTP/1.1
+        Host: 127.0.0.1:8000
+        Content-Length: 12
+        
+        <dir>w</dir>
+        */
+        let (head_len, body_start) = Self::find_head_end(&self.cache);
+        let head = std::str::from_utf8(&self.cache[..head_len]).ok()?;
+        let mut space = head.lines();
+        
+        //第一行的方法行
+        let mut method_line = space.next()?.split_whitespace();
+        if method_line.clone().count() != 3 {
+            return None
+        }
+        let method = HTTPClientMethod::from(method_line.next().unwrap()).ok()?;
+        //请求目标必须是四种形式之一
+        let resource = method_line.next().unwrap();
+        let uri = Uri::parse_target(resource).ok()?;
+        if !is_valid_form(method.as_str(), uri.form()) {
+            return None
+        }
+        let resource = resource.to_string();
+        let version = HTTPVersion::from(method_line.next().unwrap()).ok()?;
+        
+        //第二行以及以后的header行
+        let mut header = HTTPHeadMap::new();
+        for i in space {
+            let _ = header.try_append(i.trim());
+        }
+        
+        //Body，长度规则和HTTPClientParser相同，同时有Content-Length和Transfer-Encoding时拒绝
+        let (body, trailer, body_len) = match BodyReader::from_header(&header, true, ParserLimits::unlimited()).ok()? {
+            Some(mut reader) => {
+                let used = reader.feed(self.cache.get(body_start..)?).ok()?;
+                if !reader.is_complete() {
                     return None
                 }
-            } else {
-                return None
+                (reader.take_body(), reader.take_trailer(), used)
             }
-            
-            //第二行以及以后的header行
-            let header = HTTPHeadMap::new();
-            if space.clone().count() > 1 {
-                let result = space.clone()
-                                  .map_while(|w| if w.ends_with("\r\n") {
-                                      None
-                                  } else {
-                                      Some(w)
-                                  });
-                
-                for i in result {
-                    let _ = header.try_insert(i.trim());
-                }
+            None => (Vec::new(), HTTPHeadMap::new(), 0)
+        };
+        self.cache.drain(..body_start + body_len);
+        
+        //构建行
+        let mut response = HTTPResponseBuilder::new(
+            version,
+            header,
+            body
+        );
+        *response.trailer_mut() = trailer;
+        
+        Some(HTTPClientResponse::with_uri(response, method, resource, uri))
+    }
+    
+    ///
+    /// 返回(header部分的长度, body开始的位置)，没有空行时整个缓存都是header
+    ///
+    fn find_head_end(cache: &[u8]) -> (usize, usize) {
+        for (index, byte) in cache.iter().enumerate() {
+            if *byte != b'\n' {
+                continue
             }
-            
-            //跳过header遍历的行
-            let space = space.skip(header.len());
-            
-            //Body
-            let mut body = String::new();
-            for i in space {
-                body.push_str(&format!("{}\r\n", i))
+            let rest = &cache[index + 1..];
+            if rest.starts_with(b"\r\n") {
+                return (index, index + 3)
             }
-            let body = body.trim()
-                           .parse::<String>()
-                           .unwrap();
-            
-            //构建行
-            let body = body.vec_u8();
-            let version = HTTPVersion::from(version);
-            let method = HTTPClientMethod::from(method);
-            if version.is_err() || method.is_err() {
-                return None
+            if rest.starts_with(b"\n") {
+                return (index, index + 2)
             }
-            let (version, method) = (
-                version.unwrap(),
-                method.unwrap()
-            );
-            let response = HTTPResponseBuilder::new(
-                version,
-                header,
-                body
-            );
-            
-            return Some(HTTPClientResponse::new(response, method, resource))
         }
-        None
+        (cache.len(), cache.len())
+    }
+    
+    ///
+    /// 还没有被解析的字节
+    ///
+    pub fn remaining(&self) -> &[u8] {
+        &self.cache
     }
 }
 
@@ -211,7 +331,7 @@ mod test {
     fn build() {
         let client = HTTPClientResponseFormatter::new_from(
             String::from(
-                "POST w/xp HTTP/2
+                "POST /w/xp HTTP/2
                     Host: 127.0.0.1:8000
                     Hostd: 127.0.0.1:8000
             
@@ -241,7 +361,7 @@ mod test {
     fn time() {
         let client = HTTPClientResponseFormatter::new_from(
             String::from(
-                "POST w/xp HTTP/2
+                "POST /w/xp HTTP/2
                     Host: 127.0.0.1:8000
                     Hostd: 127.0.0.1:8000
             
@@ -252,7 +372,7 @@ mod test {
                 .collect::<Vec<_>>()
         ).build().unwrap();
         let time = Instant::now();
-        let http = client.http();
+        let http = client.http().unwrap();
         let time = time.elapsed();
         println!("{}", http);
         println!("Time :{:.4}ms", time.as_micros() as f64 / 1000.0)
@@ -292,6 +412,33 @@ impl HTTPClientResponseBuilder {
         this
     }
     
+    ///
+    /// 检查之后的`resource`，请求目标必须是origin-form、absolute-form、authority-form或者`*`
+    ///
+    pub fn try_resource<T>(self, resource: T) -> Result<Self, ProtocolError>
+        where
+            T: ToString
+    {
+        let resource = resource.to_string();
+        if !is_valid_target(&resource) {
+            return Err(ProtocolError::InvalidTarget)
+        }
+        Ok(self.resource(resource))
+    }
+    
+    ///
+    /// 加上`jar`中发给`uri`的cookie，替换已有的Cookie header，没有匹配的cookie时不做修改
+    ///
+    pub fn cookies(self, jar: &CookieJar, uri: &Uri) -> Self {
+        let mut this = self;
+        if let Some(value) = jar.header_value(uri) {
+            let mut response = this.response.unwrap_or(HTTPResponseBuilder::default().build());
+            response.header.insert(HeaderName::COOKIE, value);
+            this.response = Some(response);
+        }
+        this
+    }
+    
     pub fn build(self) -> HTTPClientResponse {
         let response = self.response.unwrap_or(HTTPResponseBuilder::default().build());
         let method = self.method.unwrap_or(HTTPClientMethod::GET);
@@ -302,12 +449,14 @@ impl HTTPClientResponseBuilder {
 
 #[cfg(test)]
 mod test1 {
-    use std::io::{Read, Write};
+    use std::io::{ErrorKind, Read, Write};
     use std::net::TcpStream;
     use std::time::Instant;
     
+    use crate::error::ProtocolError;
     use crate::header::method::HTTPClientMethod;
-    use crate::map::HTTPHeadMap;
+    use crate::header::version::HTTPVersion;
+    use crate::map::{HeaderMappingError, HTTPHeadMap};
     use crate::response::client::{HTTPClientResponseBuilder, HTTPClientResponseFormatter};
     use crate::response::HTTPResponseBuilder;
     
@@ -318,11 +467,110 @@ mod test1 {
             .resource("/api")
             .method(HTTPClientMethod::POST)
             .build();
-        let http = response.http();
+        let http = response.http().unwrap();
         println!("Build:{}", http);
         
         let format = HTTPClientResponseFormatter::new_from(http).build();
-        println!("Format:{}", format.unwrap().http());
+        println!("Format:{}", format.unwrap().http().unwrap());
+    }
+    
+    #[test]
+    fn content_length() {
+        let response = HTTPClientResponseBuilder::new()
+            .response(HTTPResponseBuilder::builder().body(" two\r\n\r\nlines ").build())
+            .method(HTTPClientMethod::POST)
+            .build();
+        let mut http = response.http_bytes().unwrap();
+        assert!(http.starts_with(b"POST / HTTP/1.1\r\nContent-Length: 14\r\n\r\n"));
+        http.extend_from_slice(b"GET /next HTTP/1.1\r\n\r\n");
+        
+        let mut format = HTTPClientResponseFormatter::new_from(http);
+        assert_eq!(format.build_next().unwrap().body(), b" two\r\n\r\nlines ");
+        assert_eq!(format.remaining(), b"GET /next HTTP/1.1\r\n\r\n");
+        assert_eq!(format.build_next().unwrap().resource(), "/next");
+        assert!(format.remaining().is_empty());
+    }
+    
+    #[test]
+    fn stream() {
+        let request = |version| HTTPClientResponseBuilder::new()
+            .response(HTTPResponseBuilder::builder().version(version).build())
+            .method(HTTPClientMethod::POST)
+            .build();
+        assert!(request(HTTPVersion::HTTP1_1).stream_to(Vec::new()).is_ok());
+        //请求的body不能以连接关闭结束
+        assert_eq!(request(HTTPVersion::HTTP1_0).stream_to(Vec::new()).unwrap_err().kind(), ErrorKind::InvalidInput);
+    }
+    
+    #[test]
+    fn chunked() {
+        let mut format = HTTPClientResponseFormatter::new_from("POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n3\r\nabc\r\n0\r\nDigest: x\r\n\r\nGET /next HTTP/1.1\r\n\r\n");
+        let request = format.build_next().unwrap();
+        assert_eq!(request.body(), b"abc");
+        assert_eq!(request.trailer().get("Digest").unwrap(), "x");
+        assert_eq!(format.remaining(), b"GET /next HTTP/1.1\r\n\r\n");
+        
+        //没有读完的chunked body不会被当成下一个消息
+        let mut format = HTTPClientResponseFormatter::new_from("POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n3\r\nabc\r\n");
+        assert!(format.build_next().is_none());
+        
+        let request = |raw: &str| HTTPClientResponseFormatter::new_from(raw).build();
+        assert!(request("POST / HTTP/1.1\r\nContent-Length: 3\r\nTransfer-Encoding: chunked\r\n\r\n0\r\n\r\n").is_none());
+        assert!(request("POST / HTTP/1.1\r\nTransfer-Encoding: gzip\r\n\r\nabc").is_none());
+    }
+    
+    #[test]
+    fn target() {
+        let resource = |raw: &str| HTTPClientResponseFormatter::new_from(raw).build().unwrap().resource();
+        assert_eq!(resource("GET http://example.com/x HTTP/1.1\r\n\r\n"), "http://example.com/x");
+        assert_eq!(resource("CONNECT example.com:443 HTTP/1.1\r\n\r\n"), "example.com:443");
+        assert_eq!(resource("OPTIONS * HTTP/1.1\r\n\r\n"), "*");
+        //缺少开头'/'的路径不是合法的目标，不会被补上'/'
+        assert!(HTTPClientResponseFormatter::new_from("GET index.html HTTP/1.1\r\n\r\n").build().is_none());
+        
+        let request = HTTPClientResponseFormatter::new_from("GET /a?b=1 HTTP/1.1\r\n\r\n").build().unwrap();
+        assert_eq!(request.uri().unwrap().path(), "/a");
+        assert!(HTTPClientResponseBuilder::new().resource("a b").build().uri().is_none());
+    }
+    
+    #[test]
+    fn keep_alive() {
+        let request = |raw: &str| HTTPClientResponseFormatter::new_from(raw).build().unwrap();
+        assert!(request("GET / HTTP/1.1\r\nHost: a\r\n\r\n").is_keep_alive());
+        assert!(!request("GET / HTTP/1.1\r\nConnection: close\r\n\r\n").is_keep_alive());
+        assert!(!request("GET / HTTP/1.0\r\n\r\n").is_keep_alive());
+        assert!(request("GET / HTTP/1.0\r\nConnection: Keep-Alive\r\n\r\n").is_keep_alive());
+        assert_eq!(request("GET / HTTP/2.0\r\n\r\n").http_version(), HTTPVersion::HTTP2);
+    }
+    
+    #[test]
+    fn injection() {
+        let builder = HTTPClientResponseBuilder::new();
+        assert_eq!(builder.clone().try_resource("/ HTTP/1.1\r\nHost: evil").unwrap_err(), ProtocolError::InvalidTarget);
+        assert_eq!(builder.clone().try_resource("").unwrap_err(), ProtocolError::InvalidTarget);
+        assert!(builder.clone().try_resource("/search?q=a%20b").is_ok());
+        assert_eq!(builder.clone().try_resource("/a%2").unwrap_err(), ProtocolError::InvalidTarget);
+        assert!(HTTPClientResponseFormatter::new_from("GET /a%zz HTTP/1.1\r\n\r\n").build().is_none());
+        
+        let response = builder.clone().resource("/a b").build();
+        let mut buf = Vec::new();
+        assert_eq!(response.write_to(&mut buf).unwrap_err().kind(), ErrorKind::InvalidInput);
+        assert!(buf.is_empty());
+        
+        let mut header = HTTPHeadMap::new();
+        assert_eq!(header.checked_insert("X-Name", "a\r\nInjected: b".to_string()), Err(HeaderMappingError::InvalidValue));
+        assert_eq!(header.checked_append("Bad:Name", "a".to_string()), Err(HeaderMappingError::InvalidName));
+        assert!(header.is_empty());
+        
+        header.insert("X-Name", "a\r\nInjected: b".to_string());
+        let response = builder.response(HTTPResponseBuilder::builder().header(header).build()).build();
+        assert_eq!(response.validate(), Err(ProtocolError::InvalidField));
+        assert!(response.http_bytes().is_err());
+        assert_eq!(response.http().unwrap_err().kind(), ErrorKind::InvalidInput);
+        
+        //未检查的构建器也不会让序列化panic
+        let response = HTTPClientResponseBuilder::new().resource("/a b").build();
+        assert_eq!(response.http_bytes().unwrap_err().kind(), ErrorKind::InvalidInput);
     }
     
     #[test]
@@ -333,7 +581,7 @@ mod test1 {
             .method(HTTPClientMethod::POST)
             .build();
         let time = Instant::now();
-        let http = response.http();
+        let http = response.http().unwrap();
         let time = time.elapsed();
         
         println!("{}", http);
@@ -343,7 +591,7 @@ mod test1 {
     #[test]
     fn send() {
         let mut conn = TcpStream::connect("www.baidu.com:80").unwrap();
-        let header = HTTPHeadMap::new();
+        let mut header = HTTPHeadMap::new();
         header.try_insert(r"User-Agent:Mozilla/5.0 (iPad; U; CPU OS 4_3_3 like Mac OS X; en-us) AppleWebKit/533.17.9 (KHTML, like Gecko) Version/5.0.2 Mobile/8J2 Safari/6533.18.5").unwrap();
         header.try_insert(r"Content-Type:text/html").unwrap();
         let response = HTTPClientResponseBuilder::new()
@@ -355,7 +603,7 @@ mod test1 {
                     .build()
             ).build();
         
-        let http = response.http();
+        let http = response.http().unwrap();
         conn.write_all(http.as_bytes()).unwrap();
         conn.flush().unwrap();
         
@@ -1,23 +1,37 @@
+use std::io;
+use std::io::Write;
+
+use crate::error::{Error, ProtocolError};
+use crate::header::name::HeaderName;
+use crate::header::status::StatusCode;
+use crate::header::typed::Connection;
 use crate::header::version::HTTPVersion;
 use crate::map::HTTPHeadMap;
+use crate::parser::chunked::is_chunked;
 use crate::prelude::HeaderMappingType;
+use crate::response::chunked::ChunkedEncoder;
 
 pub mod server;
 pub mod client;
+pub mod chunked;
 
 #[derive(Clone, Debug)]
 pub struct HTTPResponse {
     version: HTTPVersion,
+    status: StatusCode,
     header: HTTPHeadMap,
-    body: Vec<u8>
+    body: Vec<u8>,
+    trailer: HTTPHeadMap
 }
 
 impl HTTPResponse {
     pub fn new(version: HTTPVersion, header: HTTPHeadMap, body: Vec<u8>) -> Self {
         HTTPResponse {
             version,
+            status: StatusCode::OK,
             header,
-            body
+            body,
+            trailer: HTTPHeadMap::new()
         }
     }
     
@@ -25,6 +39,17 @@ impl HTTPResponse {
         self.version
     }
     
+    ///
+    /// 默认是200，作为[`HTTPServerResponse`](server::HTTPServerResponse)的一部分时和状态行一致
+    ///
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+    
+    pub fn set_status(&mut self, status: StatusCode) {
+        self.status = status
+    }
+    
     pub fn header(&self) -> &HTTPHeadMap {
         &self.header
     }
@@ -36,13 +61,192 @@ impl HTTPResponse {
     pub fn body_mut(&mut self) -> &mut Vec<u8> {
         &mut self.body
     }
+    
+    ///
+    /// chunked编码的body之后的trailer
+    ///
+    pub fn trailer(&self) -> &HTTPHeadMap {
+        &self.trailer
+    }
+    
+    pub fn trailer_mut(&mut self) -> &mut HTTPHeadMap {
+        &mut self.trailer
+    }
+    
+    ///
+    /// 按照Connection header决定是否保持连接，没有时使用版本的默认行为
+    ///
+    pub fn is_keep_alive(&self) -> bool {
+        is_keep_alive(self.version, &self.header)
+    }
+    
+    pub(crate) fn into_parts(self) -> (HTTPVersion, HTTPHeadMap, Vec<u8>, HTTPHeadMap) {
+        (self.version, self.header, self.body, self.trailer)
+    }
+    
+    ///
+    /// 检查header和trailer，不合法的字段会破坏消息结构
+    ///
+    pub(crate) fn validate(&self) -> Result<(), ProtocolError> {
+        validate_fields(&self.header, &self.trailer)
+    }
+    
+    pub(crate) fn write_to<W>(&self, w: &mut W, empty_length: bool) -> io::Result<()>
+        where
+            W: Write
+    {
+        write_fields(w, &self.header, &self.body, &self.trailer, empty_length)
+    }
+    
+    ///
+    /// 只写入header和空行，body由调用者按照header中的长度写入
+    ///
+    pub(crate) fn write_head_to<W>(&self, w: &mut W) -> io::Result<()>
+        where
+            W: Write
+    {
+        for (key, value) in &self.header {
+            write!(w, "{}: {}\r\n", key, value)?;
+        }
+        w.write_all(b"\r\n")
+    }
+    
+    pub(crate) fn write_chunked_to<W>(&self, w: W) -> io::Result<ChunkedEncoder<W>>
+        where
+            W: Write
+    {
+        write_chunked_fields(w, &self.header, self.version)
+    }
+}
+
+pub(crate) fn is_keep_alive(version: HTTPVersion, header: &HTTPHeadMap) -> bool {
+    match header.typed_get::<Connection>() {
+        Some(connection) if connection.is_close() => false,
+        Some(connection) if connection.is_keep_alive() => version >= HTTPVersion::HTTP1_0,
+        _ => version.keep_alive_default()
+    }
+}
+
+pub(crate) fn validate_fields(header: &HTTPHeadMap, trailer: &HTTPHeadMap) -> Result<(), ProtocolError> {
+    header.validate()
+          .and_then(|_| trailer.validate())
+          .map_err(|_| ProtocolError::InvalidField)
+}
+
+///
+/// 检查发送时决定body长度的字段，接收方和发送方对长度的理解不同会导致请求走私(RFC 9112 6)
+///
+/// HTTP/1.0的对端不能理解Transfer-Encoding(RFC 9112 6.1)，`status`是响应的状态码，请求是None
+///
+pub(crate) fn validate_framing(version: HTTPVersion, header: &HTTPHeadMap, body: &[u8], status: Option<StatusCode>) -> Result<(), ProtocolError> {
+    let chunked = header.contains_key(HeaderName::TRANSFER_ENCODING);
+    if !version.supports_chunked() && chunked {
+        return Err(ProtocolError::UnsupportedTransferEncoding)
+    }
+    
+    let lengths = header.get_all(HeaderName::CONTENT_LENGTH);
+    if lengths.len() > 1 {
+        return Err(ProtocolError::DuplicateContentLength)
+    }
+    let length = match lengths.first() {
+        Some(value) if !value.is_empty() && value.bytes().all(|b| b.is_ascii_digit()) => {
+            Some(value.parse::<u64>().map_err(|_| ProtocolError::InvalidContentLength)?)
+        }
+        Some(_) => return Err(ProtocolError::InvalidContentLength),
+        None => None
+    };
+    if length.is_some() && chunked {
+        return Err(ProtocolError::ConflictingLength)
+    }
+    
+    //1xx、204、304没有body，只有304的Content-Length可以是被选中的表示的长度(RFC 9110 8.6)
+    match status {
+        Some(status) if !status.allows_body() => {
+            if !body.is_empty() || chunked || (length.is_some() && status != StatusCode::NOT_MODIFIED) {
+                return Err(ProtocolError::UnexpectedBody)
+            }
+            Ok(())
+        }
+        //解析或者修改之后留下的Content-Length必须和现在的body一致
+        _ => match length {
+            Some(length) if length != body.len() as u64 => Err(ProtocolError::ContentLengthMismatch),
+            _ => Ok(())
+        }
+    }
+}
+
+///
+/// 写入header、空行以及原样的body，起始行由调用者写入，请求和响应共用
+///
+/// Transfer-Encoding为chunked时body按照chunked编码写入，并写入trailer；
+/// 否则没有Content-Length时自动按照body长度添加，`empty_length`为true时空body也会添加`Content-Length: 0`，
+/// 已有的Content-Length由`validate_framing`检查
+///
+pub(crate) fn write_fields<W>(w: &mut W, header: &HTTPHeadMap, body: &[u8], trailer: &HTTPHeadMap, empty_length: bool) -> io::Result<()>
+    where
+        W: Write
+{
+    for (key, value) in header {
+        write!(w, "{}: {}\r\n", key, value)?;
+    }
+    
+    if is_chunked(header) {
+        w.write_all(b"\r\n")?;
+        let mut encoder = ChunkedEncoder::new(w);
+        encoder.write_all(body)?;
+        encoder.finish(trailer)?;
+        return Ok(())
+    }
+    
+    if !header.contains_key(HeaderName::CONTENT_LENGTH) && (empty_length || !body.is_empty()) {
+        write!(w, "Content-Length: {}\r\n", body.len())?;
+    }
+    w.write_all(b"\r\n")?;
+    w.write_all(body)
+}
+
+///
+/// 写入header以及`Transfer-Encoding: chunked`，返回写body用的编码器
+///
+/// `version`不支持chunked时改为写入`Connection: close`，body在连接关闭时结束，这只对响应有效
+///
+pub(crate) fn write_chunked_fields<W>(w: W, header: &HTTPHeadMap, version: HTTPVersion) -> io::Result<ChunkedEncoder<W>>
+    where
+        W: Write
+{
+    let mut w = w;
+    if !version.supports_chunked() {
+        for (key, value) in header {
+            if *key == HeaderName::CONTENT_LENGTH || *key == HeaderName::TRANSFER_ENCODING || *key == HeaderName::CONNECTION {
+                continue
+            }
+            write!(w, "{}: {}\r\n", key, value)?;
+        }
+        w.write_all(b"Connection: close\r\n\r\n")?;
+        return Ok(ChunkedEncoder::close_delimited(w))
+    }
+    
+    for (key, value) in header {
+        //长度未知，不能再有Content-Length
+        if *key == HeaderName::CONTENT_LENGTH {
+            continue
+        }
+        write!(w, "{}: {}\r\n", key, value)?;
+    }
+    if !is_chunked(header) {
+        w.write_all(b"Transfer-Encoding: chunked\r\n")?;
+    }
+    w.write_all(b"\r\n")?;
+    Ok(ChunkedEncoder::new(w))
 }
 
 #[derive(Clone, Debug, Default)]
 pub struct HTTPResponseBuilder {
     version: Option<HTTPVersion>,
+    status: Option<StatusCode>,
     header: Option<HTTPHeadMap>,
-    body: Option<Vec<u8>>
+    body: Option<Vec<u8>>,
+    trailer: Option<HTTPHeadMap>
 }
 
 impl HTTPResponseBuilder {
@@ -61,6 +265,12 @@ impl HTTPResponseBuilder {
         this
     }
     
+    pub fn status(self, status: StatusCode) -> Self {
+        let mut this = self;
+        this.status = Some(status);
+        this
+    }
+    
     pub fn header(self, header: HTTPHeadMap) -> Self {
         let mut this = self;
         this.header = Some(header);
@@ -72,23 +282,42 @@ impl HTTPResponseBuilder {
             T: HeaderMappingType
     {
         let mut this = self;
-        let header = this.header.unwrap_or_default();
+        let mut header = this.header.unwrap_or_default();
         
-        header.insert_tuple(t.parse_key_value().unwrap_or_default());
+        //无法解析的header直接忽略
+        if let Ok(tuple) = t.parse_key_value() {
+            header.insert_tuple(tuple);
+        }
         
         this.header = Some(header);
         this
     }
     
+    pub fn trailer(self, trailer: HTTPHeadMap) -> Self {
+        let mut this = self;
+        this.trailer = Some(trailer);
+        this
+    }
+    
     pub fn build(self) -> HTTPResponse {
         let version = self.version.unwrap_or(HTTPVersion::HTTP1_1);
         let header = self.header.unwrap_or_default();
         let body = self.body.unwrap_or_default();
         
-        HTTPResponse::new(version, header, body)
+        let mut response = HTTPResponse::new(version, header, body);
+        response.status = self.status.unwrap_or_default();
+        response.trailer = self.trailer.unwrap_or_default();
+        response
     }
 }
 
+///
+/// 拒绝发送不合法的消息时返回的错误
+///
+pub(crate) fn invalid_input(error: ProtocolError) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, Error::from(error))
+}
+
 pub trait HTTPBytes {
     fn vec_u8(&self) -> Vec<u8>;
     fn string(&self) -> String;
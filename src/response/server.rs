@@ -1,5 +1,16 @@
+use std::io;
+use std::io::Write;
+
+use crate::error::ProtocolError;
 use crate::header::method::HTTPServerMethod;
-use crate::response::{HTTPResponse, HTTPResponseBuilder};
+use crate::header::name::HeaderName;
+use crate::header::status::StatusCode;
+use crate::header::version::HTTPVersion;
+use crate::map::HTTPHeadMap;
+use crate::parser::chunked::is_chunked;
+use crate::parser::server::HTTPServerParser;
+use crate::response::{HTTPBytes, HTTPResponse, HTTPResponseBuilder, invalid_input, validate_framing};
+use crate::response::chunked::ChunkedEncoder;
 
 ///
 /// 服务器给客户端的响应，或者服务器的响应
@@ -40,6 +51,13 @@ impl HTTPServerResponseBuilder {
         this
     }
     
+    ///
+    /// 和`method`一样，使用标准的原因短语
+    ///
+    pub fn status(self, status: StatusCode) -> Self {
+        self.method(status.into())
+    }
+    
     pub fn build(self) -> HTTPServerResponse {
         let response = self.response.unwrap_or(
             HTTPResponseBuilder::builder().build()
@@ -54,34 +72,363 @@ impl HTTPServerResponseBuilder {
 }
 
 impl HTTPServerResponse {
+    ///
+    /// `response`的状态码和`method`保持一致
+    ///
     pub fn new(response: HTTPResponse, method: HTTPServerMethod) -> Self {
+        let mut response = response;
+        if let Some(status) = method.status() {
+            response.status = status;
+        }
         HTTPServerResponse {
             response,
             method
         }
     }
     
-    pub fn http(self) -> String {
-        let method = self.method;
-        let version = self.response.version;
-        let body = self.response.body;
-        let header = self.response.header;
+    ///
+    /// 状态码不在100到999之间时是None
+    ///
+    pub fn status(&self) -> Option<StatusCode> {
+        self.method.status()
+    }
+    
+    pub fn method(&self) -> HTTPServerMethod {
+        self.method.clone()
+    }
+    
+    pub fn http_version(&self) -> HTTPVersion {
+        self.response.version
+    }
+    
+    ///
+    /// 读取完这个响应之后是否可以复用连接，body读取到连接关闭的响应不能复用
+    ///
+    pub fn is_keep_alive(&self) -> bool {
+        let header = self.header();
+        let until_close = self.method.status().map(|status| status.allows_body()).unwrap_or(true)
+            && !header.contains_key(HeaderName::CONTENT_LENGTH)
+            && !is_chunked(header);
+        !until_close && self.response.is_keep_alive()
+    }
+    
+    pub fn header(&self) -> &HTTPHeadMap {
+        &self.response.header
+    }
+    
+    pub fn header_mut(&mut self) -> &mut HTTPHeadMap {
+        &mut self.response.header
+    }
+    
+    pub fn body(&self) -> &Vec<u8> {
+        &self.response.body
+    }
+    
+    pub fn body_mut(&mut self) -> &mut Vec<u8> {
+        &mut self.response.body
+    }
+    
+    pub fn trailer(&self) -> &HTTPHeadMap {
+        &self.response.trailer
+    }
+    
+    ///
+    /// 检查状态行和所有header，发送时不会产生被注入的行
+    ///
+    pub fn validate(&self) -> Result<(), ProtocolError> {
+        self.method.validate()?;
+        self.response.validate()
+    }
+    
+    ///
+    /// 把状态行、header以及原样的body直接写入`w`，消息不合法时返回`InvalidInput`并且不写入任何东西
+    ///
+    pub fn write_to<W>(&self, w: &mut W) -> io::Result<()>
+        where
+            W: Write
+    {
+        self.validate()
+            .and_then(|_| validate_framing(self.response.version, &self.response.header, &self.response.body, self.method.status()))
+            .map_err(invalid_input)?;
+        write!(w, "{} {}\r\n", self.response.version, self.method)?;
+        //1xx，204，304不能有body
+        let empty_length = self.method
+                               .status()
+                               .map(|status| status.allows_body())
+                               .unwrap_or(true);
+        self.response.write_to(w, empty_length)
+    }
+    
+    ///
+    /// 写入状态行和header，body长度未知时使用返回的编码器分段写入，body字段会被忽略
+    ///
+    /// HTTP/1.0的响应不使用chunked，而是写入`Connection: close`，写完之后需要关闭连接
+    ///
+    pub fn stream_to<W>(&self, w: W) -> io::Result<ChunkedEncoder<W>>
+        where
+            W: Write
+    {
+        self.validate().map_err(invalid_input)?;
+        let mut w = w;
+        write!(w, "{} {}\r\n", self.response.version, self.method)?;
+        self.response.write_chunked_to(w)
+    }
+    
+    ///
+    /// body不是UTF-8时会被替换，发送请使用`http_bytes`或者`write_to`，消息不合法时返回`InvalidInput`
+    ///
+    pub fn http(&self) -> io::Result<String> {
+        Ok(String::from_utf8_lossy(&self.http_bytes()?).into_owned())
+    }
+    
+    ///
+    /// 消息不合法时返回`InvalidInput`
+    ///
+    pub fn http_bytes(&self) -> io::Result<Vec<u8>> {
+        let mut buf = Vec::with_capacity(self.response.body.len() + 128);
+        self.write_to(&mut buf)?;
+        Ok(buf)
+    }
+}
+
+impl From<HTTPResponse> for HTTPServerResponse {
+    fn from(response: HTTPResponse) -> Self {
+        let method = response.status.into();
+        HTTPServerResponse::new(response, method)
+    }
+}
+
+impl From<HTTPServerResponse> for HTTPResponse {
+    fn from(response: HTTPServerResponse) -> Self {
+        response.response
+    }
+}
+
+///
+/// 把服务器发来的完整响应解析为HTTPServerResponse
+///
+/// 缓存的末尾被视为连接关闭，分段读取时请使用[`HTTPServerParser`]
+///
+#[derive(Clone, Debug, Default)]
+pub struct HTTPServerResponseFormatter {
+    cache: Vec<u8>
+}
+
+impl HTTPServerResponseFormatter {
+    pub fn init() -> Self {
+        Self::default()
+    }
+    
+    pub fn new_from<T>(cache: T) -> Self
+        where
+            T: HTTPBytes
+    {
+        let cache = cache.vec_u8();
+        HTTPServerResponseFormatter {
+            cache
+        }
+    }
+    
+    pub fn cache<T>(self, cache: T) -> Self
+        where
+            T: HTTPBytes
+    {
+        let mut this = self;
+        this.cache = cache.vec_u8();
+        this
+    }
+    
+    pub fn build(self) -> Option<HTTPServerResponse> {
+        let mut parser = HTTPServerParser::new();
+        let progress = parser.feed(&self.cache).ok()?;
+        if !progress.is_complete() {
+            parser.finish().ok()?;
+        }
+        parser.take()
+    }
+}
+
+#[cfg(test)]
+mod format_test {
+    use std::io::{ErrorKind, Write};
+    
+    use crate::error::{Error, ProtocolError};
+    use crate::header::method::HTTPServerMethod;
+    use crate::header::status::StatusCode;
+    use crate::header::version::HTTPVersion;
+    use crate::map::HTTPHeadMap;
+    use crate::response::HTTPResponseBuilder;
+    use crate::response::server::{HTTPServerResponse, HTTPServerResponseBuilder, HTTPServerResponseFormatter};
+    
+    #[test]
+    fn build() {
+        let response = HTTPServerResponseBuilder::builder()
+            .response(
+                HTTPResponseBuilder::builder()
+                    .header_insert("Server: http-rs_plus")
+                    .body("<h1>Hello!</h1>")
+                    .build()
+            ).method(HTTPServerMethod::NotFound)
+            .build();
+        
+        let response = HTTPServerResponseFormatter::new_from(response.http().unwrap())
+            .build()
+            .unwrap();
+        assert_eq!(response.method().code(), 404);
+        assert_eq!(response.body(), b"<h1>Hello!</h1>");
+    }
+    
+    #[test]
+    fn write_binary() {
+        let body = vec![0x1f, 0x8b, 0x08, 0x00, 0xff, 0xfe, b'\r', b'\n'];
+        let response = HTTPServerResponseBuilder::builder()
+            .response(
+                HTTPResponseBuilder::builder()
+                    .header_insert("Content-Encoding: gzip")
+                    .body(body.clone())
+                    .build()
+            ).build();
+        
+        let mut buf = Vec::new();
+        response.write_to(&mut buf).unwrap();
+        assert!(buf.starts_with(b"HTTP/1.1 200 OK\r\nContent-Encoding: gzip\r\nContent-Length: 8\r\n\r\n"));
+        assert!(buf.ends_with(&body));
+        assert_eq!(buf, response.http_bytes().unwrap());
+    }
+    
+    #[test]
+    fn multi_value() {
+        let mut header = HTTPHeadMap::new();
+        header.append("Set-Cookie".to_string(), "a=1".to_string());
+        header.append("Set-Cookie".to_string(), "b=2".to_string());
+        let response = HTTPServerResponseBuilder::builder()
+            .response(HTTPResponseBuilder::builder().header(header).build())
+            .build();
+        
+        let http = response.http().unwrap();
+        assert!(http.contains("Set-Cookie: a=1\r\nSet-Cookie: b=2\r\n"));
+        
+        let response = HTTPServerResponseFormatter::new_from(http).build().unwrap();
+        assert_eq!(response.header().get_all("set-cookie"), vec!["a=1", "b=2"]);
+    }
+    
+    #[test]
+    fn stream() {
+        let response = HTTPServerResponseBuilder::builder().build();
+        let mut encoder = response.stream_to(Vec::new()).unwrap();
+        encoder.write_all(b"first").unwrap();
+        encoder.write_all(b"second").unwrap();
+        let buf = encoder.finish(&HTTPHeadMap::new()).unwrap();
         
-        let method = method.to_string();
-        let version = version.to_string();
-        let body = String::from_utf8_lossy(&body);
+        let response = HTTPServerResponseFormatter::new_from(buf).build().unwrap();
+        assert_eq!(response.header().get("Transfer-Encoding").unwrap(), "chunked");
+        assert_eq!(response.body(), b"firstsecond");
         
-        //Header迭代器优化
-        let header = header
-            .map(|(key, value)| format!("{}:{};\r\n", key, value))
-            .collect::<Vec<String>>()
-            .join("");
+        //HTTP/1.0的客户端不能解码chunked，body读取到连接关闭
+        let response = HTTPServerResponseBuilder::builder()
+            .response(
+                HTTPResponseBuilder::builder()
+                    .version(HTTPVersion::HTTP1_0)
+                    .header_insert("Connection: keep-alive")
+                    .header_insert("Content-Length: 3")
+                    .build()
+            ).build();
+        let mut encoder = response.stream_to(Vec::new()).unwrap();
+        encoder.write_all(b"first").unwrap();
+        let buf = encoder.finish(&HTTPHeadMap::new()).unwrap();
+        assert_eq!(buf, b"HTTP/1.0 200 OK\r\nConnection: close\r\n\r\nfirst");
+        
+        let mut header = HTTPHeadMap::new();
+        header.insert("Transfer-Encoding", "chunked".to_string());
+        let response = HTTPServerResponseBuilder::builder()
+            .response(HTTPResponseBuilder::builder().version(HTTPVersion::HTTP1_0).header(header).build())
+            .build();
+        assert_eq!(response.http_bytes().unwrap_err().kind(), ErrorKind::InvalidInput);
+    }
+    
+    #[test]
+    fn status() {
+        let response = HTTPResponseBuilder::builder()
+            .status(StatusCode::CREATED)
+            .build();
+        assert_eq!(response.status(), StatusCode::CREATED);
         
-        format!("{} {}\r\n{}\r\n{}", version, method, header, body)
+        let response = HTTPServerResponse::from(response);
+        assert_eq!(response.method().code(), 201);
+        assert_eq!(response.status(), Some(StatusCode::CREATED));
+        assert!(response.http().unwrap().starts_with("HTTP/1.1 201 Created\r\n"));
     }
     
-    pub fn http_bytes(self) -> Vec<u8> {
-        self.http().into_bytes()
+    #[test]
+    fn keep_alive() {
+        let response = |raw: &str| HTTPServerResponseFormatter::new_from(raw).build().unwrap();
+        assert!(response("HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n").is_keep_alive());
+        assert!(response("HTTP/1.1 204 No Content\r\n\r\n").is_keep_alive());
+        //body读取到连接关闭
+        assert!(!response("HTTP/1.1 200 OK\r\n\r\n").is_keep_alive());
+        assert!(!response("HTTP/1.0 200 OK\r\nContent-Length: 0\r\n\r\n").is_keep_alive());
+    }
+    
+    #[test]
+    fn framing() {
+        let error = |status: StatusCode, fields: &[(&str, &str)], body: &str| {
+            let mut header = HTTPHeadMap::new();
+            for (key, value) in fields {
+                header.append(key.to_string(), value.to_string());
+            }
+            let response = HTTPServerResponseBuilder::builder()
+                .response(HTTPResponseBuilder::builder().header(header).body(body).build())
+                .status(status)
+                .build();
+            let mut buf = Vec::new();
+            match response.write_to(&mut buf) {
+                Ok(()) => None,
+                Err(error) => {
+                    assert!(buf.is_empty());
+                    assert_eq!(error.kind(), ErrorKind::InvalidInput);
+                    match *error.into_inner().unwrap().downcast::<Error>().unwrap() {
+                        Error::Protocol(error) => Some(error),
+                        _ => unreachable!()
+                    }
+                }
+            }
+        };
+        
+        assert_eq!(error(StatusCode::OK, &[("Content-Length", "2"), ("Transfer-Encoding", "chunked")], "ok"), Some(ProtocolError::ConflictingLength));
+        assert_eq!(error(StatusCode::OK, &[("Content-Length", "2"), ("Content-Length", "3")], "ok"), Some(ProtocolError::DuplicateContentLength));
+        assert_eq!(error(StatusCode::OK, &[("Content-Length", "+2")], "ok"), Some(ProtocolError::InvalidContentLength));
+        assert_eq!(error(StatusCode::OK, &[("Content-Length", "two")], "ok"), Some(ProtocolError::InvalidContentLength));
+        assert_eq!(error(StatusCode::NO_CONTENT, &[("Content-Length", "8")], "no body"), Some(ProtocolError::UnexpectedBody));
+        assert_eq!(error(StatusCode::NO_CONTENT, &[], "no body"), Some(ProtocolError::UnexpectedBody));
+        assert_eq!(error(StatusCode::CONTINUE, &[("Transfer-Encoding", "chunked")], ""), Some(ProtocolError::UnexpectedBody));
+        //304的Content-Length是被选中的表示的长度
+        assert_eq!(error(StatusCode::NOT_MODIFIED, &[("Content-Length", "8")], ""), None);
+        assert_eq!(error(StatusCode::OK, &[("Content-Length", "2")], "ok"), None);
+        assert_eq!(error(StatusCode::OK, &[("Content-Length", "100")], "ok"), Some(ProtocolError::ContentLengthMismatch));
+        
+        //解析之后修改了body，留下的Content-Length不会被原样发送
+        let mut response = HTTPServerResponseFormatter::new_from("HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nhello").build().unwrap();
+        response.body_mut().extend_from_slice(b" world");
+        assert_eq!(response.http_bytes().unwrap_err().kind(), ErrorKind::InvalidInput);
+        response.header_mut().remove("Content-Length");
+        assert!(response.http_bytes().unwrap().ends_with(b"Content-Length: 11\r\n\r\nhello world"));
+    }
+    
+    #[test]
+    fn injection() {
+        let response = HTTPServerResponseBuilder::builder()
+            .method(HTTPServerMethod::Other(200, "OK\r\nSet-Cookie: admin=1".to_string()))
+            .build();
+        let mut buf = Vec::new();
+        assert_eq!(response.write_to(&mut buf).unwrap_err().kind(), ErrorKind::InvalidInput);
+        assert!(response.stream_to(Vec::new()).is_err());
+        assert!(buf.is_empty());
+        
+        let response = HTTPServerResponseBuilder::builder().build();
+        let mut trailer = HTTPHeadMap::new();
+        trailer.append("X-Checksum", "a\nb".to_string());
+        let encoder = response.stream_to(Vec::new()).unwrap();
+        assert!(encoder.finish(&trailer).is_err());
     }
 }
 
@@ -110,7 +457,7 @@ mod test {
             if let Ok(mut s) = i {
                 let mut buf = [0; 4096];
                 s.read(&mut buf).unwrap();
-                s.write_all(&response.clone().http_bytes()).unwrap();
+                s.write_all(&response.http_bytes().unwrap()).unwrap();
                 s.flush().unwrap();
             } else {
                 continue
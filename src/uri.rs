@@ -0,0 +1,719 @@
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::net::{IpAddr, Ipv6Addr};
+use std::path::{Component, Path, PathBuf};
+use std::str::FromStr;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum UriError {
+    Empty,
+    //scheme不是字母开头或者包含不允许的字符
+    InvalidScheme,
+    //userinfo或者authority的结构不对
+    InvalidAuthority,
+    //reg-name包含不允许的字符或者IPv6地址不合法
+    InvalidHost,
+    //端口不是数字或者超过65535
+    InvalidPort,
+    //path、query或者fragment包含不允许的字符
+    InvalidChar,
+    //%后面不是两位十六进制数字
+    InvalidPercentEncoding,
+    //不能作为请求目标，例如带有fragment
+    InvalidForm,
+    //路径包含编码之后的分隔符(%2F)，按照策略拒绝，或者文件名中包含分隔符
+    EncodedSeparator,
+    //路径的".."超出了根目录
+    Traversal
+}
+
+pub type UriResult<T> = Result<T, UriError>;
+
+///
+/// 请求行中请求目标的四种形式(RFC 9112 3.2)
+///
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum TargetForm {
+    //GET /index.html?a=1 HTTP/1.1
+    Origin,
+    //GET http://example.com/index.html HTTP/1.1，发给代理
+    Absolute,
+    //CONNECT example.com:443 HTTP/1.1
+    Authority,
+    //OPTIONS * HTTP/1.1
+    Asterisk
+}
+
+///
+/// `[userinfo@]host[:port]`，IPv6地址不带方括号保存
+///
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Authority {
+    userinfo: Option<String>,
+    host: String,
+    ipv6: bool,
+    port: Option<u16>
+}
+
+impl Authority {
+    pub fn parse(raw: &str) -> UriResult<Self> {
+        let (userinfo, host_port) = match raw.rfind('@') {
+            Some(index) => (Some(&raw[..index]), &raw[index + 1..]),
+            None => (None, raw)
+        };
+        if let Some(userinfo) = userinfo {
+            check_chars(userinfo, |b| is_unreserved(b) || is_sub_delim(b) || b == b':')?;
+        }
+        
+        let (host, port, ipv6) = if let Some(rest) = host_port.strip_prefix('[') {
+            let end = rest.find(']').ok_or(UriError::InvalidHost)?;
+            let host = &rest[..end];
+            Ipv6Addr::from_str(host).map_err(|_| UriError::InvalidHost)?;
+            let port = match &rest[end + 1..] {
+                "" => None,
+                port => Some(port.strip_prefix(':').ok_or(UriError::InvalidAuthority)?)
+            };
+            (host, port, true)
+        } else {
+            let (host, port) = match host_port.rfind(':') {
+                Some(index) => (&host_port[..index], Some(&host_port[index + 1..])),
+                None => (host_port, None)
+            };
+            check_chars(host, |b| is_unreserved(b) || is_sub_delim(b)).map_err(|error| match error {
+                UriError::InvalidChar => UriError::InvalidHost,
+                error => error
+            })?;
+            (host, port, false)
+        };
+        
+        //端口可以为空("example.com:")，视为没有端口
+        let port = match port {
+            Some("") | None => None,
+            Some(port) if port.bytes().all(|b| b.is_ascii_digit()) => {
+                Some(port.parse::<u16>().map_err(|_| UriError::InvalidPort)?)
+            }
+            Some(_) => return Err(UriError::InvalidPort)
+        };
+        
+        Ok(Authority {
+            userinfo: userinfo.map(String::from),
+            host: host.to_string(),
+            ipv6,
+            port
+        })
+    }
+    
+    pub fn userinfo(&self) -> Option<&str> {
+        self.userinfo.as_deref()
+    }
+    
+    ///
+    /// IPv6地址不带方括号
+    ///
+    pub fn host(&self) -> &str {
+        &self.host
+    }
+    
+    pub fn port(&self) -> Option<u16> {
+        self.port
+    }
+    
+    ///
+    /// host是IP地址时返回解析之后的地址
+    ///
+    pub fn ip(&self) -> Option<IpAddr> {
+        IpAddr::from_str(&self.host).ok()
+    }
+    
+    pub fn is_ipv6(&self) -> bool {
+        self.ipv6
+    }
+}
+
+impl Display for Authority {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if let Some(userinfo) = &self.userinfo {
+            write!(f, "{}@", userinfo)?;
+        }
+        if self.ipv6 {
+            write!(f, "[{}]", self.host)?;
+        } else {
+            f.write_str(&self.host)?;
+        }
+        if let Some(port) = self.port {
+            write!(f, ":{}", port)?;
+        }
+        Ok(())
+    }
+}
+
+///
+/// RFC 3986的URI引用，各部分保存原本的写法，百分号编码只检查不解码
+///
+/// `Display`输出和解析的输入相同(端口的前导0和空端口除外)
+///
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Uri {
+    form: TargetForm,
+    scheme: Option<String>,
+    authority: Option<Authority>,
+    path: String,
+    query: Option<String>,
+    fragment: Option<String>
+}
+
+impl Uri {
+    ///
+    /// 解析URI或者相对引用，例如`https://user@example.com:8443/a?b#c`，`../x`，`//host/p`
+    ///
+    pub fn parse(raw: &str) -> UriResult<Self> {
+        if raw.is_empty() {
+            return Err(UriError::Empty)
+        }
+        
+        let (rest, fragment) = match raw.find('#') {
+            Some(index) => (&raw[..index], Some(&raw[index + 1..])),
+            None => (raw, None)
+        };
+        let (rest, query) = match rest.find('?') {
+            Some(index) => (&rest[..index], Some(&rest[index + 1..])),
+            None => (rest, None)
+        };
+        
+        //第一个':'在'/'之前时是scheme
+        let (scheme, rest) = match rest.find(':') {
+            Some(index) if !rest[..index].contains('/') => {
+                let scheme = &rest[..index];
+                if !is_scheme(scheme) {
+                    return Err(UriError::InvalidScheme)
+                }
+                (Some(scheme), &rest[index + 1..])
+            }
+            _ => (None, rest)
+        };
+        
+        let (authority, path) = match rest.strip_prefix("//") {
+            Some(rest) => {
+                let end = rest.find('/').unwrap_or(rest.len());
+                (Some(Authority::parse(&rest[..end])?), &rest[end..])
+            }
+            None => (None, rest)
+        };
+        
+        check_chars(path, |b| is_pchar(b) || b == b'/')?;
+        if let Some(query) = query {
+            check_chars(query, |b| is_pchar(b) || b == b'/' || b == b'?')?;
+        }
+        if let Some(fragment) = fragment {
+            check_chars(fragment, |b| is_pchar(b) || b == b'/' || b == b'?')?;
+        }
+        
+        let form = if scheme.is_some() {
+            TargetForm::Absolute
+        } else {
+            TargetForm::Origin
+        };
+        Ok(Uri {
+            form,
+            scheme: scheme.map(String::from),
+            authority,
+            path: path.to_string(),
+            query: query.map(String::from),
+            fragment: fragment.map(String::from)
+        })
+    }
+    
+    ///
+    /// 解析请求行中的请求目标，四种形式都可以，不能带fragment
+    ///
+    pub fn parse_target(raw: &str) -> UriResult<Self> {
+        if raw.is_empty() {
+            return Err(UriError::Empty)
+        }
+        if raw.contains('#') {
+            return Err(UriError::InvalidForm)
+        }
+        
+        if raw == "*" {
+            return Ok(Uri {
+                form: TargetForm::Asterisk,
+                scheme: None,
+                authority: None,
+                path: String::from("*"),
+                query: None,
+                fragment: None
+            })
+        }
+        
+        if raw.starts_with('/') {
+            //origin-form，"//host"不是合法的origin-form
+            if raw.starts_with("//") {
+                return Err(UriError::InvalidForm)
+            }
+            return Uri::parse(raw)
+        }
+        
+        //example.com:443的"example.com"也可以是scheme，冒号之后只有端口时按照authority-form处理
+        let is_authority = match raw.rfind(':') {
+            Some(index) => {
+                let port = &raw[index + 1..];
+                !port.is_empty() && port.bytes().all(|b| b.is_ascii_digit()) && !raw.contains('/') && !raw.contains('?')
+            }
+            None => false
+        };
+        if is_authority {
+            let authority = Authority::parse(raw)?;
+            if authority.userinfo.is_some() {
+                return Err(UriError::InvalidForm)
+            }
+            return Ok(Uri {
+                form: TargetForm::Authority,
+                scheme: None,
+                authority: Some(authority),
+                path: String::new(),
+                query: None,
+                fragment: None
+            })
+        }
+        
+        let uri = Uri::parse(raw)?;
+        if uri.scheme.is_none() {
+            return Err(UriError::InvalidForm)
+        }
+        Ok(uri)
+    }
+    
+    pub fn form(&self) -> TargetForm {
+        self.form
+    }
+    
+    pub fn scheme(&self) -> Option<&str> {
+        self.scheme.as_deref()
+    }
+    
+    pub fn authority(&self) -> Option<&Authority> {
+        self.authority.as_ref()
+    }
+    
+    pub fn host(&self) -> Option<&str> {
+        self.authority
+            .as_ref()
+            .map(|authority| authority.host())
+    }
+    
+    pub fn port(&self) -> Option<u16> {
+        self.authority
+            .as_ref()
+            .and_then(|authority| authority.port())
+    }
+    
+    ///
+    /// 没有写端口时使用scheme的默认端口，只认识http，https，ws，wss
+    ///
+    pub fn port_or_default(&self) -> Option<u16> {
+        self.port().or_else(|| {
+            match self.scheme()?.to_ascii_lowercase().as_str() {
+                "http" | "ws" => Some(80),
+                "https" | "wss" => Some(443),
+                _ => None
+            }
+        })
+    }
+    
+    ///
+    /// 保留百分号编码的路径，authority-form时为空，asterisk-form时是`*`
+    ///
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+    
+    pub fn query(&self) -> Option<&str> {
+        self.query.as_deref()
+    }
+    
+    pub fn fragment(&self) -> Option<&str> {
+        self.fragment.as_deref()
+    }
+    
+    pub fn is_absolute(&self) -> bool {
+        self.scheme.is_some()
+    }
+    
+    ///
+    /// 直接发给服务器时使用的origin-form请求目标，空路径写成`/`
+    ///
+    pub fn path_and_query(&self) -> String {
+        let path = if self.path.is_empty() { "/" } else { &self.path };
+        match &self.query {
+            Some(query) => format!("{}?{}", path, query),
+            None => path.to_string()
+        }
+    }
+    
+    ///
+    /// 路径经过[`normalize_path`]处理之后的URI，authority-form和asterisk-form原样返回
+    ///
+    pub fn normalize(&self, policy: EncodedSeparator) -> UriResult<Uri> {
+        let mut uri = self.clone();
+        if matches!(self.form, TargetForm::Origin | TargetForm::Absolute) {
+            uri.path = normalize_path(&self.path, policy)?;
+        }
+        Ok(uri)
+    }
+}
+
+impl FromStr for Uri {
+    type Err = UriError;
+    
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Uri::parse(s)
+    }
+}
+
+impl Default for Uri {
+    fn default() -> Self {
+        Uri {
+            form: TargetForm::Origin,
+            scheme: None,
+            authority: None,
+            path: String::from("/"),
+            query: None,
+            fragment: None
+        }
+    }
+}
+
+impl Display for Uri {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if self.form == TargetForm::Authority {
+            if let Some(authority) = &self.authority {
+                return write!(f, "{}", authority)
+            }
+        }
+        
+        if let Some(scheme) = &self.scheme {
+            write!(f, "{}:", scheme)?;
+        }
+        if let Some(authority) = &self.authority {
+            write!(f, "//{}", authority)?;
+        }
+        f.write_str(&self.path)?;
+        if let Some(query) = &self.query {
+            write!(f, "?{}", query)?;
+        }
+        if let Some(fragment) = &self.fragment {
+            write!(f, "#{}", fragment)?;
+        }
+        Ok(())
+    }
+}
+
+fn is_scheme(scheme: &str) -> bool {
+    let mut bytes = scheme.bytes();
+    match bytes.next() {
+        Some(first) if first.is_ascii_alphabetic() => {
+            bytes.all(|b| b.is_ascii_alphanumeric() || b == b'+' || b == b'-' || b == b'.')
+        }
+        _ => false
+    }
+}
+
+pub(crate) fn is_unreserved(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'-' || b == b'.' || b == b'_' || b == b'~'
+}
+
+pub(crate) fn is_sub_delim(b: u8) -> bool {
+    b"!$&'()*+,;=".contains(&b)
+}
+
+fn is_pchar(b: u8) -> bool {
+    is_unreserved(b) || is_sub_delim(b) || b == b':' || b == b'@'
+}
+
+///
+/// 检查字符，`%`必须跟着两位十六进制数字
+///
+fn check_chars<F>(raw: &str, allowed: F) -> UriResult<()>
+    where
+        F: Fn(u8) -> bool
+{
+    let bytes = raw.as_bytes();
+    let mut index = 0;
+    while index < bytes.len() {
+        match bytes[index] {
+            b'%' => {
+                match bytes.get(index + 1..index + 3) {
+                    Some([high, low]) if high.is_ascii_hexdigit() && low.is_ascii_hexdigit() => index += 3,
+                    _ => return Err(UriError::InvalidPercentEncoding)
+                }
+            }
+            b if allowed(b) => index += 1,
+            _ => return Err(UriError::InvalidChar)
+        }
+    }
+    Ok(())
+}
+
+///
+/// 路径中`%2F`的处理方式，`/a%2Fb`可以是一个名为`a/b`的段，也可以是两段
+///
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum EncodedSeparator {
+    //保持编码，作为段的一部分
+    Keep,
+    //解码为'/'，再移除点段，"/a/..%2F..%2Fb"会变成"/b"
+    Decode,
+    //返回UriError::EncodedSeparator
+    Reject
+}
+
+impl Default for EncodedSeparator {
+    fn default() -> Self {
+        EncodedSeparator::Keep
+    }
+}
+
+///
+/// 按照RFC 3986 5.2.4移除`.`和`..`段，绝对路径的`..`不会超出根目录
+///
+/// 不处理百分号编码，`%2E%2E`需要先经过[`normalize_path`]解码
+///
+pub fn remove_dot_segments(path: &str) -> String {
+    let absolute = path.starts_with('/');
+    let raw = if absolute { &path[1..] } else { path };
+    let segments = raw.split('/').collect::<Vec<_>>();
+    
+    let mut output = Vec::with_capacity(segments.len());
+    for (index, segment) in segments.iter().enumerate() {
+        let last = index + 1 == segments.len();
+        match *segment {
+            "." => {}
+            ".." => {
+                output.pop();
+            }
+            segment => {
+                output.push(segment);
+                continue
+            }
+        }
+        //以点段结尾的路径保留最后的'/'
+        if last {
+            output.push("");
+        }
+    }
+    
+    let path = output.join("/");
+    if absolute {
+        format!("/{}", path)
+    } else {
+        path
+    }
+}
+
+///
+/// 规范化路径(RFC 3986 6.2.2)：
+/// 解码不需要编码的字符(包括`%2E`)，其余编码的十六进制改为大写，按照`policy`处理`%2F`，最后移除点段
+///
+pub fn normalize_path(path: &str, policy: EncodedSeparator) -> UriResult<String> {
+    check_chars(path, |b| is_pchar(b) || b == b'/')?;
+    
+    let bytes = path.as_bytes();
+    let mut buf = String::with_capacity(path.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        if bytes[index] != b'%' {
+            buf.push(bytes[index] as char);
+            index += 1;
+            continue
+        }
+        
+        let byte = hex_value(bytes[index + 1]) << 4 | hex_value(bytes[index + 2]);
+        match byte {
+            b'/' => match policy {
+                EncodedSeparator::Keep => buf.push_str("%2F"),
+                EncodedSeparator::Decode => buf.push('/'),
+                EncodedSeparator::Reject => return Err(UriError::EncodedSeparator)
+            },
+            byte if is_unreserved(byte) => buf.push(byte as char),
+            byte => buf.push_str(&format!("%{:02X}", byte))
+        }
+        index += 3;
+    }
+    
+    Ok(remove_dot_segments(&buf))
+}
+
+///
+/// 把请求路径(不带查询字符串)安全地拼接到`base`之后，用于把URL映射到文件
+///
+/// 每一段都会被完全解码，`..`超出`base`时返回`UriError::Traversal`，
+/// 解码之后包含`/`、`\`、NUL或者盘符等不能作为单个文件名的段返回`UriError::EncodedSeparator`
+///
+pub fn safe_join<P>(base: P, path: &str, policy: EncodedSeparator) -> UriResult<PathBuf>
+    where
+        P: AsRef<Path>
+{
+    check_chars(path, |b| is_pchar(b) || b == b'/')?;
+    if policy == EncodedSeparator::Reject && path.to_ascii_uppercase().contains("%2F") {
+        return Err(UriError::EncodedSeparator)
+    }
+    
+    let mut segments = Vec::new();
+    for raw in path.split('/') {
+        let decoded = percent_decode(raw);
+        //Decode策略下编码的'/'也是分隔符
+        let parts = match policy {
+            EncodedSeparator::Decode => decoded.split(|b| *b == b'/').collect::<Vec<_>>(),
+            _ => vec![&decoded[..]]
+        };
+        
+        for part in parts {
+            let segment = String::from_utf8(part.to_vec())
+                .map_err(|_| UriError::InvalidChar)?;
+            match segment.as_str() {
+                "" | "." => {}
+                ".." => {
+                    segments.pop().ok_or(UriError::Traversal)?;
+                }
+                segment => {
+                    let mut components = Path::new(segment).components();
+                    let single = matches!(components.next(), Some(Component::Normal(_))) && components.next().is_none();
+                    if !single || segment.contains(['/', '\\', '\0']) {
+                        return Err(UriError::EncodedSeparator)
+                    }
+                    segments.push(segment.to_string());
+                }
+            }
+        }
+    }
+    
+    let mut joined = base.as_ref().to_path_buf();
+    joined.extend(segments);
+    Ok(joined)
+}
+
+///
+/// 调用之前已经检查过`%`后面是两位十六进制数字
+///
+fn percent_decode(raw: &str) -> Vec<u8> {
+    let bytes = raw.as_bytes();
+    let mut buf = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        if bytes[index] == b'%' {
+            buf.push(hex_value(bytes[index + 1]) << 4 | hex_value(bytes[index + 2]));
+            index += 3;
+        } else {
+            buf.push(bytes[index]);
+            index += 1;
+        }
+    }
+    buf
+}
+
+fn hex_value(b: u8) -> u8 {
+    match b {
+        b'0'..=b'9' => b - b'0',
+        b'a'..=b'f' => b - b'a' + 10,
+        b'A'..=b'F' => b - b'A' + 10,
+        _ => 0
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+    
+    use crate::uri::{EncodedSeparator, normalize_path, remove_dot_segments, safe_join, TargetForm, Uri, UriError};
+    
+    #[test]
+    fn parse() {
+        let uri = Uri::parse("https://user:pw@Example.com:8443/a/b%20c?x=1&y=2#top").unwrap();
+        assert_eq!(uri.scheme(), Some("https"));
+        assert_eq!(uri.authority().unwrap().userinfo(), Some("user:pw"));
+        assert_eq!(uri.host(), Some("Example.com"));
+        assert_eq!(uri.port(), Some(8443));
+        assert_eq!(uri.path(), "/a/b%20c");
+        assert_eq!(uri.query(), Some("x=1&y=2"));
+        assert_eq!(uri.fragment(), Some("top"));
+        assert_eq!(uri.to_string(), "https://user:pw@Example.com:8443/a/b%20c?x=1&y=2#top");
+        
+        let uri = Uri::parse("http://[::1]/").unwrap();
+        assert_eq!(uri.host(), Some("::1"));
+        assert!(uri.authority().unwrap().ip().unwrap().is_ipv6());
+        assert_eq!(uri.port_or_default(), Some(80));
+        assert_eq!(uri.to_string(), "http://[::1]/");
+        
+        let uri = Uri::parse("../x?q").unwrap();
+        assert!(!uri.is_absolute());
+        assert_eq!(uri.path(), "../x");
+        
+        assert_eq!(Uri::parse("http://[::g]/"), Err(UriError::InvalidHost));
+        assert_eq!(Uri::parse("http://host:99999/"), Err(UriError::InvalidPort));
+        assert_eq!(Uri::parse("/a%2"), Err(UriError::InvalidPercentEncoding));
+        assert_eq!(Uri::parse("/a%zz"), Err(UriError::InvalidPercentEncoding));
+        assert_eq!(Uri::parse("/a b"), Err(UriError::InvalidChar));
+        assert_eq!(Uri::parse("1http://x"), Err(UriError::InvalidScheme));
+    }
+    
+    #[test]
+    fn target() {
+        let uri = Uri::parse_target("/where?q=now").unwrap();
+        assert_eq!(uri.form(), TargetForm::Origin);
+        assert_eq!(uri.path_and_query(), "/where?q=now");
+        
+        let uri = Uri::parse_target("http://www.example.org/pub/WWW/TheProject.html").unwrap();
+        assert_eq!(uri.form(), TargetForm::Absolute);
+        assert_eq!(uri.host(), Some("www.example.org"));
+        assert_eq!(uri.path_and_query(), "/pub/WWW/TheProject.html");
+        
+        let uri = Uri::parse_target("www.example.com:443").unwrap();
+        assert_eq!(uri.form(), TargetForm::Authority);
+        assert_eq!((uri.host(), uri.port()), (Some("www.example.com"), Some(443)));
+        assert_eq!(uri.to_string(), "www.example.com:443");
+        
+        let uri = Uri::parse_target("[2001:db8::1]:8080").unwrap();
+        assert_eq!(uri.form(), TargetForm::Authority);
+        assert_eq!(uri.host(), Some("2001:db8::1"));
+        
+        assert_eq!(Uri::parse_target("*").unwrap().form(), TargetForm::Asterisk);
+        
+        assert_eq!(Uri::parse_target("/a#frag"), Err(UriError::InvalidForm));
+        assert_eq!(Uri::parse_target("index.html"), Err(UriError::InvalidForm));
+        assert_eq!(Uri::parse_target("//evil/x"), Err(UriError::InvalidForm));
+        assert_eq!(Uri::parse_target(""), Err(UriError::Empty));
+    }
+    
+    #[test]
+    fn dot_segments() {
+        assert_eq!(remove_dot_segments("/a/b/c/./../../g"), "/a/g");
+        assert_eq!(remove_dot_segments("mid/content=5/../6"), "mid/6");
+        assert_eq!(remove_dot_segments("/static/../../etc/passwd"), "/etc/passwd");
+        assert_eq!(remove_dot_segments("/a/.."), "/");
+        assert_eq!(remove_dot_segments("../a/./"), "a/");
+        
+        assert_eq!(normalize_path("/a/%2e%2E/b%7e/%c3%a9", EncodedSeparator::Keep).unwrap(), "/b~/%C3%A9");
+        assert_eq!(normalize_path("/a/..%2F..%2Fb", EncodedSeparator::Keep).unwrap(), "/a/..%2F..%2Fb");
+        assert_eq!(normalize_path("/a/b/..%2F..%2Fc", EncodedSeparator::Decode).unwrap(), "/c");
+        assert_eq!(normalize_path("/a%2fb", EncodedSeparator::Reject), Err(UriError::EncodedSeparator));
+        
+        let uri = Uri::parse_target("http://host/x/../y?z=1").unwrap();
+        assert_eq!(uri.normalize(EncodedSeparator::Keep).unwrap().to_string(), "http://host/y?z=1");
+    }
+    
+    #[test]
+    fn join() {
+        let base = Path::new("/srv/www");
+        assert_eq!(safe_join(base, "/css/./site%20v2.css", EncodedSeparator::Keep).unwrap(), base.join("css/site v2.css"));
+        assert_eq!(safe_join(base, "/a/../index.html", EncodedSeparator::Keep).unwrap(), base.join("index.html"));
+        assert_eq!(safe_join(base, "/a/b%2Fc", EncodedSeparator::Decode).unwrap(), base.join("a/b/c"));
+        
+        assert_eq!(safe_join(base, "/static/../../etc/passwd", EncodedSeparator::Keep), Err(UriError::Traversal));
+        assert_eq!(safe_join(base, "/%2e%2e/etc/passwd", EncodedSeparator::Keep), Err(UriError::Traversal));
+        assert_eq!(safe_join(base, "/..%2F..%2Fetc", EncodedSeparator::Decode), Err(UriError::Traversal));
+        assert_eq!(safe_join(base, "/..%2F..%2Fetc", EncodedSeparator::Keep), Err(UriError::EncodedSeparator));
+        assert_eq!(safe_join(base, "/a%5C..%5C..%5Cetc", EncodedSeparator::Keep), Err(UriError::EncodedSeparator));
+        assert_eq!(safe_join(base, "/a%00.txt", EncodedSeparator::Keep), Err(UriError::EncodedSeparator));
+        assert_eq!(safe_join(base, "/a%2Fb", EncodedSeparator::Reject), Err(UriError::EncodedSeparator));
+    }
+}
\ No newline at end of file